@@ -20,6 +20,169 @@ pub struct Config {
 
     #[serde(default = "default_services_path")]
     pub services_path: String,
+
+    #[serde(default = "default_command_check_concurrency")]
+    pub command_check_concurrency: usize,
+
+    /// Intervalle entre deux exécutions des scripts de supervision (0 pour désactiver).
+    #[serde(default = "default_command_check_interval_secs")]
+    pub command_check_interval_secs: u64,
+
+    /// Intervalle entre deux passes des sondes TLS (0 pour désactiver).
+    #[serde(default = "default_tls_interval_secs")]
+    pub tls_interval_secs: u64,
+
+    /// Intervalle entre deux passes des sondes DNS (0 pour désactiver).
+    #[serde(default = "default_dns_interval_secs")]
+    pub dns_interval_secs: u64,
+
+    #[serde(default = "default_top_processes_limit")]
+    pub top_processes_limit: usize,
+
+    #[serde(default = "default_docker_crash_loop_restarts")]
+    pub docker_crash_loop_restarts: i64,
+
+    #[serde(default = "default_docker_crash_loop_window_secs")]
+    pub docker_crash_loop_window_secs: u64,
+
+    /// Utilisateurs autorisés à démarrer, arrêter ou recréer des conteneurs.
+    #[serde(default)]
+    pub docker_action_users: Vec<String>,
+
+    #[serde(default = "default_audit_log_file")]
+    pub audit_log_file: String,
+
+    /// Origines (`https://hôte[:port]`) acceptées pour les actions, en plus de l'hôte
+    /// de la requête ; utile derrière un reverse proxy qui réécrit `Host`.
+    #[serde(default)]
+    pub trusted_origins: Vec<String>,
+
+    /// Espace Docker récupérable (Go) au-delà duquel une alerte est levée.
+    #[serde(default = "default_docker_reclaimable_warning_gb")]
+    pub docker_reclaimable_warning_gb: u64,
+
+    /// Intervalle entre deux `docker system df` (0 pour désactiver).
+    #[serde(default = "default_docker_df_interval_secs")]
+    pub docker_df_interval_secs: u64,
+
+    /// Seuils PSI sur la moyenne 60 s (% du temps) : `some` en Warning, `full` en Critical.
+    #[serde(default = "default_psi_some_warning")]
+    pub psi_some_warning: f64,
+
+    #[serde(default = "default_psi_full_critical")]
+    pub psi_full_critical: f64,
+
+    /// Préfixes des périphériques bloc ignorés par la mesure des I/O.
+    #[serde(default = "default_disk_io_ignored_prefixes")]
+    pub disk_io_ignored_prefixes: Vec<String>,
+
+    #[serde(default = "default_smartctl_path")]
+    pub smartctl_path: String,
+
+    /// Disques interrogés par smartctl ; vide : détection via `smartctl --scan`.
+    #[serde(default)]
+    pub smart_devices: Vec<String>,
+
+    /// Intervalle entre deux relevés SMART (0 pour désactiver).
+    #[serde(default = "default_smart_interval_secs")]
+    pub smart_interval_secs: u64,
+
+    /// Intervalle entre deux relevés d'occupation des disques (0 pour désactiver).
+    #[serde(default = "default_disk_sample_interval_secs")]
+    pub disk_sample_interval_secs: u64,
+
+    /// Historique conservé pour la prévision de remplissage, en heures.
+    #[serde(default = "default_disk_history_hours")]
+    pub disk_history_hours: u64,
+
+    /// Fichier où l'historique est enregistré entre deux démarrages ; vide : mémoire seulement.
+    #[serde(default = "default_disk_history_file")]
+    pub disk_history_file: String,
+
+    /// Alerte si un disque sera plein dans moins de ces délais (heures).
+    #[serde(default = "default_disk_full_warning_hours")]
+    pub disk_full_warning_hours: f64,
+
+    #[serde(default = "default_disk_full_critical_hours")]
+    pub disk_full_critical_hours: f64,
+
+    /// Ports en écoute attendus (`tcp/22`, `udp/53`, `tcp/127.0.0.1:5432`) ;
+    /// vide : pas de comparaison.
+    #[serde(default)]
+    pub expected_ports: Vec<String>,
+
+    /// Ne pas signaler les sockets inattendues qui n'écoutent que sur la boucle locale.
+    #[serde(default = "default_expected_ports_ignore_loopback")]
+    pub expected_ports_ignore_loopback: bool,
+
+    /// Intervalle entre deux inventaires des sockets (0 pour désactiver).
+    #[serde(default = "default_sockets_interval_secs")]
+    pub sockets_interval_secs: u64,
+
+    /// Intervalle entre deux relevés mdraid / ZFS / LVM / Btrfs (0 pour désactiver).
+    #[serde(default = "default_storage_interval_secs")]
+    pub storage_interval_secs: u64,
+
+    /// Jeu de règles du pare-feu de référence, créé au démarrage s'il n'existe pas.
+    #[serde(default = "default_firewall_baseline_file")]
+    pub firewall_baseline_file: String,
+
+    /// Utilisateurs autorisés à remplacer la référence du pare-feu.
+    #[serde(default)]
+    pub firewall_baseline_users: Vec<String>,
+
+    /// Intervalle entre deux relevés du jeu de règles du pare-feu (0 pour désactiver).
+    #[serde(default = "default_firewall_interval_secs")]
+    pub firewall_interval_secs: u64,
+
+    /// Intervalle entre deux relevés `wg show` (0 pour désactiver).
+    #[serde(default = "default_wireguard_interval_secs")]
+    pub wireguard_interval_secs: u64,
+
+    /// Âge du dernier handshake WireGuard (secondes) au-delà duquel un pair avec
+    /// keepalive est signalé en Warning, puis en Critical.
+    #[serde(default = "default_wireguard_handshake_warning_secs")]
+    pub wireguard_handshake_warning_secs: u64,
+
+    #[serde(default = "default_wireguard_handshake_critical_secs")]
+    pub wireguard_handshake_critical_secs: u64,
+
+    /// Interface dont l'adresse est affichée comme IP locale ; vide : celle de la
+    /// route IPv4 par défaut.
+    #[serde(default)]
+    pub primary_interface: String,
+
+    /// Intervalle entre deux recherches de l'adresse publique (0 pour désactiver).
+    #[serde(default = "default_public_ip_ttl_secs")]
+    pub public_ip_ttl_secs: u64,
+
+    /// Délai maximal de chaque fournisseur d'adresse publique.
+    #[serde(default = "default_public_ip_timeout_secs")]
+    pub public_ip_timeout_secs: u64,
+
+    /// `http`, `stun` ou `command`.
+    #[serde(default = "default_public_ip_method")]
+    pub public_ip_method: String,
+
+    /// Services renvoyant l'adresse publique en texte brut, essayés dans l'ordre ;
+    /// liste IPv6 vide : pas de recherche IPv6.
+    #[serde(default = "default_public_ip_providers_v4")]
+    pub public_ip_providers_v4: Vec<String>,
+
+    #[serde(default = "default_public_ip_providers_v6")]
+    pub public_ip_providers_v6: Vec<String>,
+
+    /// Serveurs STUN (`hôte:port`) de la méthode `stun`.
+    #[serde(default = "default_public_ip_stun_servers")]
+    pub public_ip_stun_servers: Vec<String>,
+
+    /// Commande de la méthode `command`, qui affiche l'adresse publique.
+    #[serde(default)]
+    pub public_ip_command: String,
+
+    /// Durée pendant laquelle un changement d'adresse publique reste en alerte.
+    #[serde(default = "default_public_ip_change_alert_hours")]
+    pub public_ip_change_alert_hours: u64,
 }
 
 impl Config {
@@ -49,6 +212,155 @@ fn default_services_path() -> String {
     "/etc/monitor_server/services.toml".to_string()
 }
 
+fn default_command_check_concurrency() -> usize {
+    4
+}
+
+fn default_command_check_interval_secs() -> u64 {
+    60
+}
+
+fn default_tls_interval_secs() -> u64 {
+    900
+}
+
+fn default_dns_interval_secs() -> u64 {
+    60
+}
+
+fn default_top_processes_limit() -> usize {
+    10
+}
+
+fn default_docker_crash_loop_restarts() -> i64 {
+    3
+}
+
+fn default_docker_crash_loop_window_secs() -> u64 {
+    300
+}
+
+fn default_audit_log_file() -> String {
+    "audit.log".to_string()
+}
+
+fn default_docker_reclaimable_warning_gb() -> u64 {
+    10
+}
+
+fn default_docker_df_interval_secs() -> u64 {
+    300
+}
+
+fn default_psi_some_warning() -> f64 {
+    20.0
+}
+
+fn default_psi_full_critical() -> f64 {
+    10.0
+}
+
+fn default_disk_io_ignored_prefixes() -> Vec<String> {
+    vec!["loop".to_string(), "ram".to_string()]
+}
+
+fn default_smartctl_path() -> String {
+    "smartctl".to_string()
+}
+
+fn default_smart_interval_secs() -> u64 {
+    3600
+}
+
+fn default_disk_sample_interval_secs() -> u64 {
+    600
+}
+
+fn default_disk_history_hours() -> u64 {
+    168
+}
+
+fn default_disk_history_file() -> String {
+    "/var/lib/monitor_server/disk_history.json".to_string()
+}
+
+fn default_disk_full_warning_hours() -> f64 {
+    72.0
+}
+
+fn default_disk_full_critical_hours() -> f64 {
+    24.0
+}
+
+fn default_expected_ports_ignore_loopback() -> bool {
+    true
+}
+
+fn default_sockets_interval_secs() -> u64 {
+    30
+}
+
+fn default_storage_interval_secs() -> u64 {
+    60
+}
+
+fn default_firewall_baseline_file() -> String {
+    "/var/lib/monitor_server/firewall_baseline.json".to_string()
+}
+
+fn default_firewall_interval_secs() -> u64 {
+    60
+}
+
+fn default_wireguard_interval_secs() -> u64 {
+    60
+}
+
+fn default_wireguard_handshake_warning_secs() -> u64 {
+    300
+}
+
+fn default_wireguard_handshake_critical_secs() -> u64 {
+    900
+}
+
+fn default_public_ip_ttl_secs() -> u64 {
+    900
+}
+
+fn default_public_ip_timeout_secs() -> u64 {
+    3
+}
+
+fn default_public_ip_method() -> String {
+    "http".to_string()
+}
+
+fn default_public_ip_providers_v4() -> Vec<String> {
+    vec![
+        "https://api.ipify.org".to_string(),
+        "https://ipv4.icanhazip.com".to_string(),
+    ]
+}
+
+fn default_public_ip_providers_v6() -> Vec<String> {
+    vec![
+        "https://api6.ipify.org".to_string(),
+        "https://ipv6.icanhazip.com".to_string(),
+    ]
+}
+
+fn default_public_ip_stun_servers() -> Vec<String> {
+    vec![
+        "stun.l.google.com:19302".to_string(),
+        "stun.cloudflare.com:3478".to_string(),
+    ]
+}
+
+fn default_public_ip_change_alert_hours() -> u64 {
+    24
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -68,6 +380,54 @@ mod tests {
         assert_eq!(config.server_port, 8550);
         assert_eq!(config.htpasswd_path, "/etc/monitor_server/htpasswd");
         assert_eq!(config.services_path, "/etc/monitor_server/services.toml");
+        assert_eq!(config.command_check_concurrency, 4);
+        assert_eq!(config.command_check_interval_secs, 60);
+        assert_eq!(config.tls_interval_secs, 900);
+        assert_eq!(config.dns_interval_secs, 60);
+        assert_eq!(config.top_processes_limit, 10);
+        assert_eq!(config.docker_crash_loop_restarts, 3);
+        assert_eq!(config.docker_crash_loop_window_secs, 300);
+        assert!(config.docker_action_users.is_empty());
+        assert_eq!(config.audit_log_file, "audit.log");
+        assert!(config.trusted_origins.is_empty());
+        assert_eq!(config.docker_reclaimable_warning_gb, 10);
+        assert_eq!(config.docker_df_interval_secs, 300);
+        assert_eq!(config.psi_some_warning, 20.0);
+        assert_eq!(config.psi_full_critical, 10.0);
+        assert_eq!(config.disk_io_ignored_prefixes, vec!["loop", "ram"]);
+        assert_eq!(config.smartctl_path, "smartctl");
+        assert!(config.smart_devices.is_empty());
+        assert_eq!(config.smart_interval_secs, 3600);
+        assert_eq!(config.disk_sample_interval_secs, 600);
+        assert_eq!(config.disk_history_hours, 168);
+        assert_eq!(
+            config.disk_history_file,
+            "/var/lib/monitor_server/disk_history.json"
+        );
+        assert_eq!(config.disk_full_warning_hours, 72.0);
+        assert_eq!(config.disk_full_critical_hours, 24.0);
+        assert!(config.expected_ports.is_empty());
+        assert!(config.expected_ports_ignore_loopback);
+        assert_eq!(config.sockets_interval_secs, 30);
+        assert_eq!(config.storage_interval_secs, 60);
+        assert_eq!(
+            config.firewall_baseline_file,
+            "/var/lib/monitor_server/firewall_baseline.json"
+        );
+        assert!(config.firewall_baseline_users.is_empty());
+        assert_eq!(config.firewall_interval_secs, 60);
+        assert_eq!(config.wireguard_interval_secs, 60);
+        assert_eq!(config.wireguard_handshake_warning_secs, 300);
+        assert_eq!(config.wireguard_handshake_critical_secs, 900);
+        assert!(config.primary_interface.is_empty());
+        assert_eq!(config.public_ip_ttl_secs, 900);
+        assert_eq!(config.public_ip_timeout_secs, 3);
+        assert_eq!(config.public_ip_method, "http");
+        assert_eq!(config.public_ip_providers_v4.len(), 2);
+        assert_eq!(config.public_ip_providers_v6.len(), 2);
+        assert_eq!(config.public_ip_stun_servers.len(), 2);
+        assert!(config.public_ip_command.is_empty());
+        assert_eq!(config.public_ip_change_alert_hours, 24);
     }
 
     #[test]
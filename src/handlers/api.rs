@@ -0,0 +1,575 @@
+use crate::{
+    config::Config,
+    models::{
+        errors::SystemError,
+        templates::{
+            CertificateStatus, CommandCheckStatus, DnsProbeStatus, LogLine, MemoryBreakdown, VmStat,
+        },
+    },
+    security::{audit::record_action, auth::AuthenticatedUser},
+    services::{
+        cache::ProbeCaches,
+        cgroups::{measure_cgroups, CGROUP_ROOT},
+        command_checks::perfdata_history,
+        compose::{group_by_project, load_compose_expectations},
+        disk_forecast::{forecast_alerts, DiskHistory},
+        disk_io::measure_disk_io,
+        docker::attach_stats,
+        docker_actions::{run_container_action, ContainerAction},
+        docker_disk::{docker_disk_usage, prune, PruneQuery},
+        docker_events::DockerInventory,
+        docker_logs::{fetch_logs, follow_logs, LogQuery},
+        firewall::{collect_ruleset, save_baseline},
+        hardware::{get_memory_breakdown, get_vmstat},
+        metrics::{
+            command_check_metrics, container_metrics, memory_metrics, pressure_metrics, Metrics,
+        },
+        network::network_report,
+        pressure::system_pressure,
+        processes::{measure_processes, top_processes, ProcessQuery},
+        public_ip::PublicIpCache,
+        service_checker::load_services_from_config,
+        smart::SmartCache,
+        sockets::check_expected_ports,
+    },
+};
+use actix_web::{body::BoxBody, web, HttpResponse, Responder};
+use futures_util::StreamExt;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize)]
+pub struct ActionQuery {
+    /// Nom du conteneur, à ressaisir pour confirmer l'action.
+    confirm: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ActionResult {
+    container: String,
+    action: String,
+    id: String,
+}
+
+#[derive(Deserialize)]
+pub struct EventsQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ChecksReport {
+    certificates: Vec<CertificateStatus>,
+    dns: Vec<DnsProbeStatus>,
+    commands: Vec<CommandCheckStatus>,
+}
+
+#[derive(Serialize)]
+struct MemoryReport {
+    memory: MemoryBreakdown,
+    vmstat: Option<VmStat>,
+}
+
+/// Résultats de toutes les sondes déclarées dans `services.toml`, perfdata incluses.
+///
+/// Tous les résultats sont ceux du dernier relevé (voir `tls_interval_secs`,
+/// `dns_interval_secs` et `command_check_interval_secs`).
+pub async fn get_checks(probes: web::Data<ProbeCaches>) -> impl Responder<Body = BoxBody> {
+    let report = ChecksReport {
+        certificates: probes.certificates.report(),
+        dns: probes.dns.report(),
+        commands: probes.commands.report(),
+    };
+    info!(
+        "API checks: {} certificates, {} DNS probes, {} commands",
+        report.certificates.len(),
+        report.dns.len(),
+        report.commands.len()
+    );
+
+    HttpResponse::Ok().json(report)
+}
+
+/// Valeurs successives des perfdata de chaque script, relevées à chaque exécution
+/// de la boucle de fond (`command_check_interval_secs`).
+pub async fn get_perfdata_history() -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(perfdata_history())
+}
+
+/// Table des processus : `?sort=cpu|rss|threads|pid|name&limit=N&user=..&filter=..&state=..`.
+pub async fn get_processes(query: web::Query<ProcessQuery>) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+
+    match measure_processes().await {
+        Ok(measured) => {
+            HttpResponse::Ok().json(top_processes(&measured, &query, config.top_processes_limit))
+        }
+        Err(e) => {
+            error!("{}", e.message());
+            HttpResponse::InternalServerError().body(e.message().to_string())
+        }
+    }
+}
+
+/// Conteneurs Docker avec leurs statistiques (CPU, mémoire, réseau, I/O bloc).
+pub async fn get_container_list(
+    inventory: web::Data<DockerInventory>,
+) -> impl Responder<Body = BoxBody> {
+    let mut containers = inventory.containers();
+    attach_stats(&inventory, &mut containers).await;
+    info!("API containers: {} containers", containers.len());
+    HttpResponse::Ok().json(containers)
+}
+
+/// Conteneurs regroupés par projet Docker Compose, avec l'état agrégé de chaque projet.
+pub async fn get_compose_projects(
+    inventory: web::Data<DockerInventory>,
+) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let (projects, _) = group_by_project(
+        inventory.containers(),
+        &load_compose_expectations(&config.services_path),
+    );
+    info!("API compose: {} projects", projects.len());
+    HttpResponse::Ok().json(projects)
+}
+
+/// État de connexion de chaque endpoint de conteneurs (Docker local, distant, Podman).
+pub async fn get_container_endpoints(
+    inventory: web::Data<DockerInventory>,
+) -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(inventory.endpoints())
+}
+
+/// Derniers événements Docker reçus (`?limit=N`, 100 au plus), du plus récent au plus ancien.
+pub async fn get_container_events(
+    inventory: web::Data<DockerInventory>,
+    query: web::Query<EventsQuery>,
+) -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(inventory.recent_events(query.limit.unwrap_or(100)))
+}
+
+/// Journaux d'un conteneur : `?tail=N|all&since=TS&stdout=..&stderr=..&timestamps=..`.
+/// Avec `follow=true`, les lignes sont poussées en Server-Sent Events (un événement
+/// `stdout` / `stderr` par ligne).
+pub async fn get_container_logs(
+    path: web::Path<(String, String)>,
+    query: web::Query<LogQuery>,
+    inventory: web::Data<DockerInventory>,
+) -> impl Responder<Body = BoxBody> {
+    let (endpoint, id) = path.into_inner();
+    let Some(container) = inventory.container(&endpoint, &id) else {
+        return docker_error_response(SystemError::DockerContainerNotFound);
+    };
+    let docker = match inventory.docker(&container.endpoint) {
+        Ok(docker) => docker,
+        Err(e) => return docker_error_response(e),
+    };
+
+    if query.follow {
+        let lines = follow_logs(&docker, &container.id, &query);
+        return HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .insert_header(("Cache-Control", "no-cache"))
+            .streaming(lines.map(|line| Ok::<_, actix_web::Error>(sse_event(&line))));
+    }
+
+    match fetch_logs(&docker, &container.id, &query).await {
+        Ok(lines) => {
+            info!("API logs: {} lines for container {}", lines.len(), id);
+            HttpResponse::Ok().json(lines)
+        }
+        Err(e) => docker_error_response(e),
+    }
+}
+
+/// `POST /api/v1/containers/{endpoint}/{id}/{start|stop|restart|recreate}?confirm=<nom>`.
+///
+/// Réservé aux utilisateurs listés dans `docker_action_users` ; chaque tentative,
+/// refusée ou non, est inscrite dans le journal d'audit.
+pub async fn post_container_action(
+    path: web::Path<(String, String, ContainerAction)>,
+    query: web::Query<ActionQuery>,
+    user: Option<web::ReqData<AuthenticatedUser>>,
+    inventory: web::Data<DockerInventory>,
+) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let (endpoint, id, action) = path.into_inner();
+    let user = user.map(|user| user.into_inner().0).unwrap_or_default();
+    let audit = |target: &str, result: &str| {
+        record_action(
+            &config.audit_log_file,
+            &user,
+            &action.to_string(),
+            target,
+            result,
+        )
+    };
+
+    let Some(container) = inventory.container(&endpoint, &id) else {
+        audit(&format!("{} on {}", id, endpoint), "not found");
+        return HttpResponse::NotFound()
+            .body(SystemError::DockerContainerNotFound.message().to_string());
+    };
+    let target = format!(
+        "{} ({}) on {}",
+        container.name, container.id, container.endpoint
+    );
+
+    if !config.docker_action_users.contains(&user) {
+        audit(&target, "denied");
+        return HttpResponse::Forbidden()
+            .body(format!("User '{}' may not manage containers", user));
+    }
+    if query.confirm.as_deref() != Some(container.name.as_str()) {
+        audit(&target, "not confirmed");
+        return HttpResponse::PreconditionRequired().body(format!(
+            "Confirm the {} of '{}' with ?confirm={}",
+            action, container.name, container.name
+        ));
+    }
+
+    let result = match inventory.docker(&container.endpoint) {
+        Ok(docker) => run_container_action(&docker, &container.id, action).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(new_id) => {
+            audit(&target, "ok");
+            HttpResponse::Ok().json(ActionResult {
+                container: container.name,
+                action: action.to_string(),
+                id: new_id.chars().take(12).collect(),
+            })
+        }
+        Err(e) => {
+            audit(&target, &format!("failed: {}", e.message()));
+            docker_error_response(e)
+        }
+    }
+}
+
+/// Consommation par cgroup (slices, services de `services.toml`, conteneurs), lue dans `/sys/fs/cgroup`.
+pub async fn get_cgroups(inventory: web::Data<DockerInventory>) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let services = load_services_from_config(&config.services_path);
+
+    match measure_cgroups(Path::new(CGROUP_ROOT), &services, &inventory.containers()).await {
+        Ok(cgroups) => HttpResponse::Ok().json(cgroups),
+        Err(e) => HttpResponse::ServiceUnavailable().body(e.message().to_string()),
+    }
+}
+
+/// Répartition de la mémoire (`/proc/meminfo`) et compteurs de `/proc/vmstat`.
+pub async fn get_memory() -> impl Responder<Body = BoxBody> {
+    match get_memory_breakdown() {
+        Ok(memory) => HttpResponse::Ok().json(MemoryReport {
+            memory,
+            vmstat: get_vmstat().ok(),
+        }),
+        Err(e) => HttpResponse::InternalServerError().body(e.message().to_string()),
+    }
+}
+
+/// Métriques au format d'exposition Prometheus : mémoire, swap et `/proc/vmstat`,
+/// conteneurs, pression (PSI) du système et des cgroups, scripts de supervision.
+pub async fn get_metrics(
+    inventory: web::Data<DockerInventory>,
+    probes: web::Data<ProbeCaches>,
+) -> impl Responder<Body = BoxBody> {
+    let mut metrics = Metrics::default();
+    match get_memory_breakdown() {
+        Ok(memory) => memory_metrics(&mut metrics, &memory, get_vmstat().ok().as_ref()),
+        Err(e) => error!("Memory metrics skipped: {}", e.message()),
+    }
+
+    let mut containers = inventory.containers();
+    attach_stats(&inventory, &mut containers).await;
+    container_metrics(&mut metrics, &containers);
+
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let services = load_services_from_config(&config.services_path);
+    let cgroups = measure_cgroups(Path::new(CGROUP_ROOT), &services, &containers)
+        .await
+        .unwrap_or_default();
+    pressure_metrics(&mut metrics, system_pressure().ok().as_ref(), &cgroups);
+
+    command_check_metrics(&mut metrics, &probes.commands.report());
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render())
+}
+
+/// IOPS, débit, latence et occupation de chaque périphérique bloc.
+pub async fn get_disk_io() -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+
+    match measure_disk_io(&config.disk_io_ignored_prefixes).await {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => HttpResponse::InternalServerError().body(e.message().to_string()),
+    }
+}
+
+/// Dernier relevé SMART (mis en cache, voir `smart_interval_secs`).
+pub async fn get_drives(smart: web::Data<SmartCache>) -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(smart.report())
+}
+
+/// Occupation et prévision de remplissage de chaque point de montage.
+pub async fn get_disk_forecasts(history: web::Data<DiskHistory>) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let mut forecasts = history.forecasts();
+    forecast_alerts(
+        &mut forecasts,
+        config.disk_full_warning_hours,
+        config.disk_full_critical_hours,
+    );
+    HttpResponse::Ok().json(forecasts)
+}
+
+/// Sockets en écoute, connexions TCP par état et écarts avec `expected_ports`.
+///
+/// Inventaire du dernier relevé (voir `sockets_interval_secs`).
+pub async fn get_sockets(probes: web::Data<ProbeCaches>) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+
+    match probes.sockets.report() {
+        Some(mut inventory) => {
+            check_expected_ports(
+                &mut inventory,
+                &config.expected_ports,
+                config.expected_ports_ignore_loopback,
+            );
+            HttpResponse::Ok().json(inventory)
+        }
+        None => HttpResponse::ServiceUnavailable()
+            .body(SystemError::SocketsUnavailable.message().to_string()),
+    }
+}
+
+/// Jeu de règles actif du pare-feu, compteurs et écarts avec la référence, d'après
+/// le dernier relevé (voir `firewall_interval_secs`).
+pub async fn get_firewall(probes: web::Data<ProbeCaches>) -> impl Responder<Body = BoxBody> {
+    match probes.firewall.report() {
+        Some(report) => HttpResponse::Ok().json(report),
+        None => {
+            HttpResponse::ServiceUnavailable().body("Neither nft nor iptables-save is installed")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BaselineQuery {
+    /// Empreinte du jeu de règles (`fingerprint` de `/api/v1/firewall`) pour confirmer
+    /// le remplacement de la référence.
+    confirm: Option<String>,
+}
+
+/// `POST /api/v1/firewall/baseline?confirm=<empreinte>` : le jeu de règles courant devient
+/// la référence.
+///
+/// L'empreinte confirme que le jeu enregistré est bien celui qui a été consulté.
+/// Réservé aux utilisateurs listés dans `firewall_baseline_users` ; chaque tentative
+/// est inscrite au journal d'audit.
+pub async fn post_firewall_baseline(
+    query: web::Query<BaselineQuery>,
+    probes: web::Data<ProbeCaches>,
+    user: Option<web::ReqData<AuthenticatedUser>>,
+) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let user = user.map(|user| user.into_inner().0).unwrap_or_default();
+    let audit = |result: &str| {
+        record_action(
+            &config.audit_log_file,
+            &user,
+            "firewall baseline",
+            &config.firewall_baseline_file,
+            result,
+        )
+    };
+
+    if !config.firewall_baseline_users.contains(&user) {
+        audit("denied");
+        return HttpResponse::Forbidden().body(format!(
+            "User '{}' may not update the firewall baseline",
+            user
+        ));
+    }
+    let Some(mut report) = collect_ruleset().await else {
+        audit("failed: no firewall tool");
+        return HttpResponse::ServiceUnavailable()
+            .body("Neither nft nor iptables-save is installed");
+    };
+    if let Some(e) = &report.error {
+        audit(&format!("failed: {}", e));
+        return HttpResponse::InternalServerError().body(e.clone());
+    }
+    if query.confirm.as_deref() != Some(report.fingerprint.as_str()) {
+        audit("not confirmed");
+        return HttpResponse::PreconditionRequired().body(format!(
+            "Confirm the new firewall baseline with ?confirm={}",
+            report.fingerprint
+        ));
+    }
+
+    match save_baseline(&mut report, &config.firewall_baseline_file) {
+        Ok(()) => {
+            audit("ok");
+            probes.firewall.set(Some(report.clone()));
+            HttpResponse::Ok().json(report)
+        }
+        Err(e) => {
+            audit(&format!("failed: {}", e));
+            HttpResponse::InternalServerError().body(e.to_string())
+        }
+    }
+}
+
+/// Interfaces WireGuard, pairs, dernier handshake et volumes échangés (dernier relevé).
+pub async fn get_wireguard(probes: web::Data<ProbeCaches>) -> impl Responder<Body = BoxBody> {
+    match probes.wireguard.report() {
+        Some(report) => HttpResponse::Ok().json(report),
+        None => HttpResponse::ServiceUnavailable()
+            .body("wg is not installed or WireGuard monitoring is disabled"),
+    }
+}
+
+/// Interfaces, routes, résolveurs DNS et interface principale.
+pub async fn get_network() -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    HttpResponse::Ok().json(network_report(&config.primary_interface))
+}
+
+/// Dernière adresse publique connue et ses changements (mis en cache, voir `public_ip_ttl_secs`).
+pub async fn get_public_ip(public_ip: web::Data<PublicIpCache>) -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(public_ip.report())
+}
+
+/// Grappes mdraid, pools ZFS, thin pools LVM et compteurs d'erreurs Btrfs
+/// (mis en cache, voir `storage_interval_secs`).
+pub async fn get_storage(probes: web::Data<ProbeCaches>) -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(probes.storage.report())
+}
+
+/// PSI du système (`/proc/pressure`) ; 503 si le noyau ne le fournit pas.
+pub async fn get_pressure() -> impl Responder<Body = BoxBody> {
+    match system_pressure() {
+        Ok(pressure) => HttpResponse::Ok().json(pressure),
+        Err(e) => HttpResponse::ServiceUnavailable().body(e.message().to_string()),
+    }
+}
+
+/// `GET /api/v1/containers/disk-usage` : `docker system df` de chaque endpoint connecté
+/// (mis en cache, voir `docker_df_interval_secs`).
+pub async fn get_docker_disk_usage(
+    probes: web::Data<ProbeCaches>,
+) -> impl Responder<Body = BoxBody> {
+    HttpResponse::Ok().json(probes.docker_disk.report())
+}
+
+/// `POST /api/v1/containers/prune?endpoint=<nom>&confirm=<nom>[&images=..&containers=..&volumes=..&build_cache=..]`.
+///
+/// Mêmes droits et même audit que les actions sur les conteneurs. Par défaut seules
+/// les images orphelines sont supprimées ; les volumes doivent être demandés explicitement
+/// et sont refusés (409) sur les démons qui supprimeraient aussi les volumes nommés.
+pub async fn post_docker_prune(
+    query: web::Query<PruneQuery>,
+    user: Option<web::ReqData<AuthenticatedUser>>,
+    inventory: web::Data<DockerInventory>,
+    probes: web::Data<ProbeCaches>,
+) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let user = user.map(|user| user.into_inner().0).unwrap_or_default();
+    let endpoint = &query.endpoint;
+    let action = ["containers", "images", "volumes", "build-cache"]
+        .into_iter()
+        .zip([
+            query.containers,
+            query.images,
+            query.volumes,
+            query.build_cache,
+        ])
+        .filter(|(_, selected)| *selected)
+        .map(|(kind, _)| kind)
+        .collect::<Vec<_>>()
+        .join("+");
+    let audit = |result: &str| {
+        record_action(
+            &config.audit_log_file,
+            &user,
+            &format!("prune {}", action),
+            endpoint,
+            result,
+        )
+    };
+
+    let Some(endpoint_config) = inventory.endpoint(endpoint) else {
+        audit("unknown endpoint");
+        return HttpResponse::NotFound().body(format!("Unknown container endpoint '{}'", endpoint));
+    };
+    if !config.docker_action_users.contains(&user) {
+        audit("denied");
+        return HttpResponse::Forbidden()
+            .body(format!("User '{}' may not manage containers", user));
+    }
+    if action.is_empty() {
+        audit("nothing to prune");
+        return HttpResponse::BadRequest().body("Nothing to prune");
+    }
+    if query.confirm.as_deref() != Some(endpoint.as_str()) {
+        audit("not confirmed");
+        return HttpResponse::PreconditionRequired().body(format!(
+            "Confirm the prune of {} on '{}' with ?confirm={}",
+            action, endpoint, endpoint
+        ));
+    }
+
+    let result = match inventory.docker(endpoint) {
+        Ok(docker) => prune(&docker, &endpoint_config, &query).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(report) => {
+            audit(&format!("ok, {} bytes reclaimed", report.space_reclaimed));
+            // L'espace récupérable affiché ne doit pas attendre le prochain relevé.
+            probes.docker_disk.set(docker_disk_usage(&inventory).await);
+            HttpResponse::Ok().json(report)
+        }
+        Err(e) => {
+            audit(&format!("failed: {}", e.message()));
+            docker_error_response(e)
+        }
+    }
+}
+
+/// Un champ `data:` par segment : un `\r` ou un `\n` dans le message terminerait le champ.
+fn sse_event(line: &LogLine) -> web::Bytes {
+    let data = match &line.timestamp {
+        Some(timestamp) => format!("{} {}", timestamp, line.message),
+        None => line.message.clone(),
+    };
+    let mut event = format!("event: {}\n", line.stream);
+    for segment in data.split(['\r', '\n']) {
+        event.push_str("data: ");
+        event.push_str(segment);
+        event.push('\n');
+    }
+    event.push('\n');
+    web::Bytes::from(event)
+}
+
+fn docker_error_response(e: SystemError) -> HttpResponse {
+    match e {
+        SystemError::DockerContainerNotFound => {
+            HttpResponse::NotFound().body(e.message().to_string())
+        }
+        SystemError::DockerConnectionFailed => {
+            HttpResponse::ServiceUnavailable().body(e.message().to_string())
+        }
+        SystemError::DockerVolumePruneUnsafe => {
+            HttpResponse::Conflict().body(e.message().to_string())
+        }
+        _ => HttpResponse::InternalServerError().body(e.message().to_string()),
+    }
+}
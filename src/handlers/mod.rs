@@ -1 +1,2 @@
+pub mod api;
 pub mod status;
\ No newline at end of file
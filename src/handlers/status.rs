@@ -1,22 +1,55 @@
 use crate::{
-    models::{errors::SystemError, templates::StatusTemplate},
+    models::{
+        errors::SystemError,
+        templates::{ContainerTemplate, StatusTemplate},
+    },
+    security::auth::AuthenticatedUser,
     services::{
-        docker::get_containers,
+        cache::ProbeCaches,
+        cgroups::{cgroup_alerts, measure_cgroups, CGROUP_ROOT},
+        command_checks::command_check_alerts,
+        compose::{compose_alerts, group_by_project, load_compose_expectations},
+        disk_forecast::{forecast_alerts, DiskHistory},
+        disk_io::measure_disk_io,
+        dns::dns_alerts,
+        docker::{attach_stats, container_alerts},
+        docker_disk::docker_disk_alerts,
+        docker_endpoints::endpoint_alerts,
+        docker_events::DockerInventory,
+        docker_logs::{fetch_logs, LogQuery},
+        firewall::firewall_alerts,
         hardware::{
-            get_disk_info, get_kernel_version, get_memory_info, get_network_traffic,
-            get_system_version, get_temperature, get_uptime,
+            get_disk_info, get_kernel_version, get_memory_breakdown, get_memory_info,
+            get_network_traffic, get_system_version, get_temperature, get_uptime, get_vmstat,
+        },
+        network::network_report,
+        pressure::{pressure_alerts, system_pressure, PressureThresholds},
+        processes::{
+            check_processes, measure_processes, process_check_alerts, top_processes, ProcessQuery,
+            ProcessSort,
         },
+        public_ip::{public_ip_alerts, PublicIpCache},
         service_checker::{check_services, is_service_active, load_services_from_config},
+        smart::{smart_alerts, SmartCache},
+        sockets::{check_expected_ports, socket_alerts},
+        storage::storage_alerts,
+        tls::certificate_alerts,
+        wireguard::wireguard_alerts,
     },
 };
 use actix_web::{body::BoxBody, web, HttpResponse, Responder};
 use askama::Template;
 use chrono::{Datelike, Local};
-use get_if_addrs::get_if_addrs;
 use log::{debug, error, info};
-use reqwest::Client;
+use std::path::Path;
 use crate::config::Config;
 
+/// Nombre d'événements Docker affichés dans la frise du tableau de bord.
+const DOCKER_EVENTS_SHOWN: usize = 20;
+
+/// Lignes de journal affichées sur la page de détail d'un conteneur.
+const CONTAINER_PAGE_LOG_LINES: usize = 200;
+
 pub async fn get_service_status(path: web::Path<String>) -> impl Responder<Body = BoxBody> {
     
     let service = path.into_inner();
@@ -27,7 +60,14 @@ pub async fn get_service_status(path: web::Path<String>) -> impl Responder<Body
     }
 }
 
-pub async fn get_status(req: actix_web::HttpRequest) -> impl Responder<Body = BoxBody> {
+pub async fn get_status(
+    req: actix_web::HttpRequest,
+    inventory: web::Data<DockerInventory>,
+    smart: web::Data<SmartCache>,
+    disk_history: web::Data<DiskHistory>,
+    public_ip: web::Data<PublicIpCache>,
+    probes: web::Data<ProbeCaches>,
+) -> impl Responder<Body = BoxBody> {
     let config = Config::from_file("config").expect("Failed to load configuration");
     info!("Starting to gather system status");
     let hostname = hostname::get()
@@ -57,6 +97,10 @@ pub async fn get_status(req: actix_web::HttpRequest) -> impl Responder<Body = Bo
         memory_info.0, memory_info.1
     );
 
+    let smart = smart.report();
+    let memory = get_memory_breakdown().ok();
+    let vmstat = get_vmstat().ok();
+
     let disk_info = get_disk_info("/").unwrap_or((0, 0));
     debug!(
         "Disk info: available: {}, total: {}",
@@ -72,8 +116,12 @@ pub async fn get_status(req: actix_web::HttpRequest) -> impl Responder<Body = Bo
     let temperature = get_temperature().unwrap_or_else(|_| "N/A".to_string());
     debug!("Temperature: {}", temperature);
 
-    let containers = get_containers().await;
+    let mut containers = inventory.containers();
+    attach_stats(&inventory, &mut containers).await;
     info!("Docker containers retrieved: {}", containers.len());
+    let container_endpoints = inventory.endpoints();
+    let docker_events = inventory.recent_events(DOCKER_EVENTS_SHOWN);
+    let docker_disk = probes.docker_disk.report();
 
     // Vérification des services
     let all_services = load_services_from_config(&config.services_path);
@@ -95,17 +143,117 @@ pub async fn get_status(req: actix_web::HttpRequest) -> impl Responder<Body = Bo
         info!("{:?} services are inactive", inactive_services);
     }
 
-    let ip_addresses = get_ip_addresses()
-        .await
-        .unwrap_or(("Unknown".to_string(), "Unknown".to_string()));
-    info!(
-        "Local IP: {}, Public IP: {}",
-        ip_addresses.0, ip_addresses.1
+    // Ces mesures attendent un intervalle entre leurs relevés : on les lance ensemble.
+    let (measured_processes, cgroups, disk_io) = tokio::join!(
+        measure_processes(),
+        measure_cgroups(Path::new(CGROUP_ROOT), &all_services, &containers),
+        measure_disk_io(&config.disk_io_ignored_prefixes),
     );
-    debug!(
-        "Local IP: {}, Public IP: {}",
-        ip_addresses.0, ip_addresses.1
+    let measured_processes = measured_processes.unwrap_or_default();
+    let cgroups = cgroups.unwrap_or_default();
+    let disk_io = disk_io.unwrap_or_default();
+    debug!("Processes sampled: {}", measured_processes.len());
+    debug!("Cgroups measured: {}", cgroups.len());
+    debug!("Block devices measured: {}", disk_io.len());
+
+    let storage = probes.storage.report();
+
+    let pressure = system_pressure().ok();
+    let mut sockets = probes.sockets.report();
+    if let Some(sockets) = &mut sockets {
+        check_expected_ports(
+            sockets,
+            &config.expected_ports,
+            config.expected_ports_ignore_loopback,
+        );
+    }
+    let firewall = probes.firewall.report();
+    let wireguard = probes.wireguard.report();
+
+    let process_checks = check_processes(&config.services_path, &measured_processes);
+    info!("Process checks run: {}", process_checks.len());
+
+    let top_cpu = top_processes(
+        &measured_processes,
+        &ProcessQuery::default(),
+        config.top_processes_limit,
     );
+    let top_rss = top_processes(
+        &measured_processes,
+        &ProcessQuery {
+            sort: ProcessSort::Rss,
+            ..Default::default()
+        },
+        config.top_processes_limit,
+    );
+
+    let certificates = probes.certificates.report();
+    info!("TLS certificates cached: {}", certificates.len());
+
+    let dns_probes = probes.dns.report();
+    info!("DNS probes cached: {}", dns_probes.len());
+
+    let command_checks = probes.commands.report();
+    info!("Command checks cached: {}", command_checks.len());
+
+    let mut alerts = certificate_alerts(&certificates);
+    alerts.extend(dns_alerts(&dns_probes));
+    alerts.extend(command_check_alerts(&command_checks));
+    alerts.extend(process_check_alerts(&process_checks));
+    alerts.extend(cgroup_alerts(&cgroups));
+    alerts.extend(smart_alerts(&smart));
+    alerts.extend(storage_alerts(&storage));
+    if let Some(sockets) = &sockets {
+        alerts.extend(socket_alerts(sockets));
+    }
+    if let Some(firewall) = &firewall {
+        alerts.extend(firewall_alerts(firewall));
+    }
+    if let Some(wireguard) = &wireguard {
+        alerts.extend(wireguard_alerts(wireguard));
+    }
+    let public_ip = public_ip.report();
+    alerts.extend(public_ip_alerts(
+        &public_ip,
+        config.public_ip_change_alert_hours as i64 * 3600,
+    ));
+    let mut disk_forecasts = disk_history.forecasts();
+    alerts.extend(forecast_alerts(
+        &mut disk_forecasts,
+        config.disk_full_warning_hours,
+        config.disk_full_critical_hours,
+    ));
+    let thresholds = PressureThresholds::from_config(&config);
+    if let Some(pressure) = &pressure {
+        alerts.extend(pressure_alerts("system", pressure, thresholds));
+    }
+    // Les slices reprennent la pression de leurs services : seuls services et conteneurs sont alertés.
+    for cgroup in cgroups.iter().filter(|cgroup| cgroup.kind != "slice") {
+        if let Some(cgroup_pressure) = &cgroup.pressure {
+            alerts.extend(pressure_alerts(&cgroup.name, cgroup_pressure, thresholds));
+        }
+    }
+    alerts.extend(endpoint_alerts(&container_endpoints));
+    alerts.extend(container_alerts(&containers));
+    alerts.extend(docker_disk_alerts(
+        &docker_disk,
+        config.docker_reclaimable_warning_gb << 30,
+    ));
+
+    let (compose_projects, containers) = group_by_project(
+        containers,
+        &load_compose_expectations(&config.services_path),
+    );
+    alerts.extend(compose_alerts(&compose_projects));
+    if !alerts.is_empty() {
+        info!("{} alerts raised", alerts.len());
+    }
+
+    let network = network_report(&config.primary_interface);
+    let local_ip = network
+        .primary_address()
+        .unwrap_or_else(|| "Unknown".to_string());
+    info!("Local IP: {}", local_ip);
 
     let current_year = Local::now().year() as u32;
 
@@ -116,16 +264,39 @@ pub async fn get_status(req: actix_web::HttpRequest) -> impl Responder<Body = Bo
         uptime,
         memory_used: format_size(memory_info.0),
         memory_total: format_size(memory_info.1),
+        memory,
+        vmstat,
         disk_available: format_size(disk_info.0),
         disk_total: format_size(disk_info.1),
+        disk_io,
+        smart,
+        storage,
+        disk_forecasts,
+        sockets,
+        firewall,
+        wireguard,
         temperature,
         network_in: format_size(network_traffic.0),
         network_out: format_size(network_traffic.1),
         containers,
+        compose_projects,
+        container_endpoints,
+        docker_events,
+        docker_disk,
         current_year,
-        local_ip: ip_addresses.0,
-        public_ip: ip_addresses.1,
+        local_ip,
+        public_ip,
+        network,
         services_status,
+        certificates,
+        dns_probes,
+        command_checks,
+        process_checks,
+        top_cpu,
+        top_rss,
+        cgroups,
+        pressure,
+        alerts,
     };
 
     match template.render() {
@@ -140,40 +311,60 @@ pub async fn get_status(req: actix_web::HttpRequest) -> impl Responder<Body = Bo
     }
 }
 
-async fn get_ip_addresses() -> Result<(String, String), SystemError> {
-    // Récupérer l'IP privée
-    let private_ip = match get_if_addrs() {
-        Ok(interfaces) => interfaces
-            .into_iter()
-            .find_map(|iface| {
-                if !iface.is_loopback() {
-                    match iface.addr {
-                        get_if_addrs::IfAddr::V4(addr) => Some(addr.ip.to_string()),
-                        _ => None,
-                    }
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| "Unknown".to_string()),
-        Err(_) => "Unknown".to_string(),
+/// Page de détail d'un conteneur : état, statistiques et fin des journaux.
+pub async fn get_container_page(
+    path: web::Path<(String, String)>,
+    inventory: web::Data<DockerInventory>,
+    user: Option<web::ReqData<AuthenticatedUser>>,
+) -> impl Responder<Body = BoxBody> {
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let (endpoint, id) = path.into_inner();
+    let Some(container) = inventory.container(&endpoint, &id) else {
+        info!("Container {} not found on {}", id, endpoint);
+        return HttpResponse::NotFound()
+            .body(SystemError::DockerContainerNotFound.message().to_string());
     };
 
-    // Récupérer l'IP publique (asynchrone)
-    let client = Client::new();
-    let public_ip = match client.get("https://api.ipify.org").send().await {
-        Ok(response) => match response.text().await {
-            Ok(ip) => ip,
-            Err(_) => "Unknown".to_string(),
-        },
-        Err(_) => "Unknown".to_string(),
+    let mut containers = [container];
+    attach_stats(&inventory, &mut containers).await;
+    let [container] = containers;
+
+    let query = LogQuery {
+        tail: CONTAINER_PAGE_LOG_LINES.to_string(),
+        timestamps: true,
+        ..Default::default()
+    };
+    let logs = match inventory.docker(&container.endpoint) {
+        Ok(docker) => fetch_logs(&docker, &container.id, &query).await,
+        Err(e) => Err(e),
+    };
+    let (logs, log_error) = match logs {
+        Ok(logs) => (logs, None),
+        Err(e) => (vec![], Some(e.message().to_string())),
     };
 
-    Ok((private_ip, public_ip))
+    let template = ContainerTemplate {
+        hostname: hostname::get()
+            .map(|h| h.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "Unknown".to_string()),
+        can_manage: user.is_some_and(|user| config.docker_action_users.contains(&user.0)),
+        container,
+        logs,
+        log_error,
+        current_year: Local::now().year() as u32,
+    };
+
+    match template.render() {
+        Ok(html) => HttpResponse::Ok().content_type("text/html").body(html),
+        Err(e) => {
+            error!("Failed to render template: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
 }
 
 // Fonction pour convertir une taille en unité lisible
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     match bytes {
         b if b >= 1 << 30 => format!("{:.2} GB", b as f64 / (1 << 30) as f64),
         b if b >= 1 << 20 => format!("{:.2} MB", b as f64 / (1 << 20) as f64),
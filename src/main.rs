@@ -9,10 +9,34 @@ use actix_web::{
     middleware::{Logger, NormalizePath, TrailingSlash},
     web, App, HttpServer,
 };
+use handlers::{
+    api::{
+        get_cgroups, get_checks, get_compose_projects, get_container_endpoints, get_container_events,
+        get_container_list, get_container_logs, get_disk_forecasts, get_disk_io, get_docker_disk_usage, get_drives, get_memory, get_metrics, get_perfdata_history, get_network, get_pressure, get_public_ip, get_firewall, get_processes, get_sockets, get_storage, get_wireguard,
+        post_container_action, post_docker_prune, post_firewall_baseline,
+    },
+    status::{get_container_page, get_service_status, get_status},
+};
 use logging::init_logging;
-use handlers::status::{get_service_status, get_status};
 use security::{auth::AuthMiddleware, htaccess::load_htpasswd};
-use std::sync::Arc;
+use services::{
+    cache::ProbeCaches,
+    command_checks::{watch_command_checks, CommandCheckSettings},
+    docker::CrashLoopPolicy,
+    docker_disk::watch_docker_disk,
+    docker_endpoints::load_container_endpoints,
+    docker_events::{watch_docker_events, DockerInventory},
+    disk_forecast::{watch_disk_usage, DiskHistory, ForecastSettings},
+    dns::{watch_dns, DnsSettings},
+    firewall::{watch_firewall, FirewallSettings},
+    public_ip::{watch_public_ip, PublicIpCache, PublicIpSettings},
+    smart::{watch_smart, SmartCache, SmartSettings},
+    sockets::watch_sockets,
+    storage::watch_storage,
+    tls::{watch_certificates, TlsSettings},
+    wireguard::{watch_wireguard, WireGuardSettings},
+};
+use std::{sync::Arc, time::Duration};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -21,13 +45,139 @@ async fn main() -> std::io::Result<()> {
     let htpasswd = Arc::new(load_htpasswd(&config.htpasswd_path));
     init_logging(&config).expect("Failed to initialize logging");
 
+    // Inventaire des conteneurs tenu à jour en tâche de fond par le flux
+    // d'événements de chaque endpoint (Docker local, distant, Podman)
+    let endpoints = load_container_endpoints(&config.services_path);
+    let inventory = web::Data::new(DockerInventory::new(endpoints.clone()));
+    for endpoint in endpoints {
+        actix_web::rt::spawn(watch_docker_events(
+            inventory.clone().into_inner(),
+            endpoint,
+            CrashLoopPolicy::from_config(&config),
+        ));
+    }
+
+    // Santé des disques : smartctl est lent, le relevé est mis en cache
+    let smart = web::Data::new(SmartCache::default());
+    actix_web::rt::spawn(watch_smart(
+        smart.clone().into_inner(),
+        SmartSettings::from_config(&config),
+    ));
+
+    // Sondes lentes ou bloquantes (TLS, DNS, sockets, stockage, docker system df,
+    // WireGuard, pare-feu, scripts de supervision), exécutées en tâche de fond
+    let probes = web::Data::new(ProbeCaches::default());
+    actix_web::rt::spawn(watch_certificates(
+        probes.certificates.clone(),
+        TlsSettings::from_config(&config),
+    ));
+    actix_web::rt::spawn(watch_dns(
+        probes.dns.clone(),
+        DnsSettings::from_config(&config),
+    ));
+    actix_web::rt::spawn(watch_sockets(
+        probes.sockets.clone(),
+        Duration::from_secs(config.sockets_interval_secs),
+    ));
+    actix_web::rt::spawn(watch_storage(
+        probes.storage.clone(),
+        Duration::from_secs(config.storage_interval_secs),
+    ));
+    actix_web::rt::spawn(watch_docker_disk(
+        probes.docker_disk.clone(),
+        inventory.clone().into_inner(),
+        Duration::from_secs(config.docker_df_interval_secs),
+    ));
+    actix_web::rt::spawn(watch_wireguard(
+        probes.wireguard.clone(),
+        WireGuardSettings::from_config(&config),
+    ));
+    actix_web::rt::spawn(watch_firewall(
+        probes.firewall.clone(),
+        FirewallSettings::from_config(&config),
+    ));
+    actix_web::rt::spawn(watch_command_checks(
+        probes.commands.clone(),
+        CommandCheckSettings::from_config(&config),
+    ));
+
+    // Historique d'occupation des disques pour la prévision de remplissage
+    let forecast_settings = ForecastSettings::from_config(&config);
+    let disk_history = web::Data::new(DiskHistory::load(&forecast_settings.history_file));
+    actix_web::rt::spawn(watch_disk_usage(
+        disk_history.clone().into_inner(),
+        forecast_settings,
+    ));
+
+    // Adresse publique : recherche lente ou bloquée sans accès sortant, mise en cache
+    let public_ip = web::Data::new(PublicIpCache::default());
+    actix_web::rt::spawn(watch_public_ip(
+        public_ip.clone().into_inner(),
+        PublicIpSettings::from_config(&config),
+    ));
+
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .wrap(NormalizePath::new(TrailingSlash::Trim))
             .wrap(AuthMiddleware::new(Arc::clone(&htpasswd)))
+            .app_data(inventory.clone())
+            .app_data(smart.clone())
+            .app_data(disk_history.clone())
+            .app_data(public_ip.clone())
+            .app_data(probes.clone())
             .route("/status", web::get().to(get_status))
             .route("/status/{service}", web::get().to(get_service_status))
+            .route("/containers/{endpoint}/{id}", web::get().to(get_container_page))
+            .route("/metrics", web::get().to(get_metrics))
+            .route("/api/v1/checks", web::get().to(get_checks))
+            .route(
+                "/api/v1/checks/history",
+                web::get().to(get_perfdata_history),
+            )
+            .route("/api/v1/processes", web::get().to(get_processes))
+            .route("/api/v1/cgroups", web::get().to(get_cgroups))
+            .route("/api/v1/memory", web::get().to(get_memory))
+            .route("/api/v1/disks/io", web::get().to(get_disk_io))
+            .route("/api/v1/disks/smart", web::get().to(get_drives))
+            .route("/api/v1/disks/forecast", web::get().to(get_disk_forecasts))
+            .route("/api/v1/storage", web::get().to(get_storage))
+            .route("/api/v1/sockets", web::get().to(get_sockets))
+            .route("/api/v1/firewall", web::get().to(get_firewall))
+            .route("/api/v1/wireguard", web::get().to(get_wireguard))
+            .route("/api/v1/network", web::get().to(get_network))
+            .route("/api/v1/public-ip", web::get().to(get_public_ip))
+            .route(
+                "/api/v1/firewall/baseline",
+                web::post().to(post_firewall_baseline),
+            )
+            .route("/api/v1/pressure", web::get().to(get_pressure))
+            .route("/api/v1/compose", web::get().to(get_compose_projects))
+            .route("/api/v1/containers", web::get().to(get_container_list))
+            .route(
+                "/api/v1/containers/endpoints",
+                web::get().to(get_container_endpoints),
+            )
+            .route(
+                "/api/v1/containers/events",
+                web::get().to(get_container_events),
+            )
+            .route(
+                "/api/v1/containers/disk-usage",
+                web::get().to(get_docker_disk_usage),
+            )
+            .route(
+                "/api/v1/containers/prune",
+                web::post().to(post_docker_prune),
+            )
+            .route(
+                "/api/v1/containers/{endpoint}/{id}/logs",
+                web::get().to(get_container_logs),
+            )
+            .route(
+                "/api/v1/containers/{endpoint}/{id}/{action}",
+                web::post().to(post_container_action),
+            )
     })
     .bind(format!("{}:{}", config.server_address, config.server_port))?
     .run()
@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+/// État d'une sonde, aligné sur les conventions Nagios (OK/WARNING/CRITICAL/UNKNOWN).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum CheckState {
+    Ok,
+    Warning,
+    Critical,
+    #[default]
+    Unknown,
+}
+
+impl CheckState {
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Ok => "OK",
+            Self::Warning => "WARNING",
+            Self::Critical => "CRITICAL",
+            Self::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Code de sortie Nagios correspondant (0 à 3).
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Ok => 0,
+            Self::Warning => 1,
+            Self::Critical => 2,
+            Self::Unknown => 3,
+        }
+    }
+
+    /// Classe CSS utilisée par le template pour colorer l'état.
+    pub fn css_class(&self) -> &str {
+        match self {
+            Self::Ok => "active",
+            Self::Warning => "warning",
+            Self::Critical | Self::Unknown => "inactive",
+        }
+    }
+}
+
+/// Alerte affichée sur le tableau de bord et exposée par l'API.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub source: String,
+    pub state: CheckState,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(source: &str, state: CheckState, message: String) -> Self {
+        Self {
+            source: source.to_string(),
+            state,
+            message,
+        }
+    }
+}
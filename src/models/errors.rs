@@ -8,7 +8,25 @@ pub enum SystemError {
     TemperatureSensorsUnavailable,
     DockerConnectionFailed,
     DockerListContainersFailed,
+    DockerInspectFailed,
+    DockerStatsFailed,
+    DockerEventStreamFailed,
+    DockerContainerNotFound,
+    DockerLogsFailed,
+    DockerActionFailed,
+    DockerDiskUsageFailed,
+    DockerPruneFailed,
+    DockerVolumePruneUnsafe,
     UptimeUnavailable,
+    TlsProbeFailed,
+    DnsProbeFailed,
+    CommandCheckFailed,
+    ProcessInfoUnavailable,
+    CgroupUnavailable,
+    PressureUnavailable,
+    DiskStatsUnavailable,
+    SmartctlFailed,
+    SocketsUnavailable,
 }
 
 impl SystemError {
@@ -21,7 +39,27 @@ impl SystemError {
             //Self::SSHStatusCheckFailed => "Failed to check SSH service status.",
             Self::DockerConnectionFailed => "Failed to connect to Docker.",
             Self::DockerListContainersFailed => "Failed to list Docker containers.",
+            Self::DockerInspectFailed => "Failed to inspect Docker container.",
+            Self::DockerStatsFailed => "Failed to retrieve Docker container statistics.",
+            Self::DockerEventStreamFailed => "Docker event stream interrupted.",
+            Self::DockerContainerNotFound => "Docker container not found.",
+            Self::DockerLogsFailed => "Failed to retrieve Docker container logs.",
+            Self::DockerActionFailed => "Failed to run Docker container action.",
+            Self::DockerDiskUsageFailed => "Failed to retrieve Docker disk usage.",
+            Self::DockerPruneFailed => "Failed to prune Docker resources.",
+            Self::DockerVolumePruneUnsafe => {
+                "Volume prune refused: before API 1.42 (and on Podman) it also deletes named volumes."
+            }
             Self::UptimeUnavailable => "Failed to retrieve uptime information.",
+            Self::TlsProbeFailed => "Failed to inspect TLS certificate.",
+            Self::DnsProbeFailed => "Failed to run DNS probe.",
+            Self::CommandCheckFailed => "Failed to run command check.",
+            Self::ProcessInfoUnavailable => "Failed to read process information from /proc.",
+            Self::CgroupUnavailable => "cgroup v2 hierarchy not available.",
+            Self::DiskStatsUnavailable => "Failed to read /proc/diskstats.",
+            Self::SmartctlFailed => "Failed to run smartctl.",
+            Self::SocketsUnavailable => "Failed to read /proc/net/tcp.",
+            Self::PressureUnavailable => "No PSI support (kernel 4.20+ with CONFIG_PSI required).",
         }
     }
 }
\ No newline at end of file
@@ -1,2 +1,3 @@
+pub mod checks;
 pub mod errors;
 pub mod templates;
\ No newline at end of file
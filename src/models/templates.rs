@@ -1,4 +1,5 @@
 use askama::Template;
+use crate::models::checks::{Alert, CheckState};
 use serde::Serialize;
 
 #[derive(Template)]
@@ -10,26 +11,829 @@ pub struct StatusTemplate {
     pub uptime: String,
     pub memory_used: String,
     pub memory_total: String,
+    pub memory: Option<MemoryBreakdown>,
+    pub vmstat: Option<VmStat>,
     pub disk_available: String,
     pub disk_total: String,
+    pub disk_io: Vec<DiskIoStats>,
+    pub smart: SmartReport,
+    pub storage: StorageReport,
+    pub disk_forecasts: Vec<DiskForecast>,
+    pub sockets: Option<SocketInventory>,
+    pub firewall: Option<FirewallReport>,
+    pub wireguard: Option<WireGuardReport>,
+    pub network: NetworkReport,
     pub temperature: String,
     pub network_in: String,
     pub network_out: String,
     pub containers: Vec<ContainerStatus>,
+    pub compose_projects: Vec<ComposeProject>,
+    pub container_endpoints: Vec<EndpointStatus>,
+    pub docker_events: Vec<ContainerEvent>,
+    pub docker_disk: Vec<DockerDiskUsage>,
     pub services_status: Vec<(String, bool)>, // (nom du service, actif ou non)
     pub current_year: u32,
     pub local_ip: String,
-    pub public_ip: String,
+    pub public_ip: PublicIpReport,
+    pub certificates: Vec<CertificateStatus>,
+    pub dns_probes: Vec<DnsProbeStatus>,
+    pub command_checks: Vec<CommandCheckStatus>,
+    pub process_checks: Vec<ProcessCheckStatus>,
+    pub top_cpu: Vec<ProcessInfo>,
+    pub top_rss: Vec<ProcessInfo>,
+    pub cgroups: Vec<CgroupUsage>,
+    /// PSI du système ; `None` si le noyau ne le fournit pas.
+    pub pressure: Option<ResourcePressure>,
+    pub alerts: Vec<Alert>,
+}
+
+/// Filtres Askama utilisés par les templates.
+mod filters {
+    /// `{{ bytes|size }}` : taille en unité lisible (KB, MB, GB).
+    pub fn size(bytes: &u64) -> ::askama::Result<String> {
+        Ok(crate::handlers::status::format_size(*bytes))
+    }
+
+    /// `{{ bytes_per_sec|rate }}` : débit en unité lisible (KB/s, MB/s...).
+    pub fn rate(bytes_per_sec: &f64) -> ::askama::Result<String> {
+        Ok(format!(
+            "{}/s",
+            crate::handlers::status::format_size(*bytes_per_sec as u64)
+        ))
+    }
+
+    /// `{{ hours|eta }}` : délai approximatif (`~5 h`, `~3 days`).
+    pub fn eta(hours: &f64) -> ::askama::Result<String> {
+        Ok(crate::services::disk_forecast::format_eta(*hours))
+    }
+}
+
+/// Répartition de la mémoire d'après `/proc/meminfo`, en octets.
+#[derive(Default, Serialize)]
+pub struct MemoryBreakdown {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    /// Mémoire des applications : total - libre - buffers - cache, comme `free`.
+    pub used: u64,
+    pub buffers: u64,
+    /// Cache de pages et slab récupérable.
+    pub cached: u64,
+    pub shared: u64,
+    pub slab: u64,
+    pub slab_unreclaimable: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub swap_used: u64,
+    pub swap_cached: u64,
+}
+
+/// Segment de la barre mémoire du tableau de bord.
+pub struct MemorySegment {
+    pub label: &'static str,
+    /// Classe CSS qui donne sa couleur au segment.
+    pub class: &'static str,
+    pub bytes: u64,
+    pub percent: f64,
+}
+
+impl MemoryBreakdown {
+    /// Segments applications / buffers / cache / libre, dont la somme fait le total.
+    pub fn segments(&self) -> Vec<MemorySegment> {
+        let total = self.total.max(1) as f64;
+        [
+            ("Used", "mem-used", self.used),
+            ("Buffers", "mem-buffers", self.buffers),
+            ("Cache", "mem-cached", self.cached),
+            ("Free", "mem-free", self.free),
+        ]
+        .into_iter()
+        .map(|(label, class, bytes)| MemorySegment {
+            label,
+            class,
+            bytes,
+            percent: bytes as f64 / total * 100.0,
+        })
+        .collect()
+    }
+}
+
+/// Activité d'un périphérique bloc entre deux relevés de `/proc/diskstats`.
+#[derive(Serialize)]
+pub struct DiskIoStats {
+    pub device: String,
+    pub mount_points: Vec<String>,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    /// Temps de service moyen des requêtes, file d'attente comprise.
+    pub read_await_ms: f64,
+    pub write_await_ms: f64,
+    pub await_ms: f64,
+    /// Part du temps avec au moins une requête en cours (`%util` d'iostat).
+    pub utilization: f64,
+    /// Nombre moyen de requêtes en attente (`aqu-sz`).
+    pub queue_depth: f64,
+    pub in_flight: u64,
+}
+
+/// Dernier relevé SMART de l'ensemble des disques.
+#[derive(Clone, Default, Serialize)]
+pub struct SmartReport {
+    /// Date du relevé ; `None` tant que le premier n'est pas terminé.
+    pub updated: Option<String>,
+    /// Échec de `smartctl --scan`.
+    pub error: Option<String>,
+    pub drives: Vec<DriveHealth>,
+}
+
+/// Santé d'un disque d'après `smartctl --json -a` ; les compteurs absents ne
+/// s'appliquent pas au protocole (ATA ou NVMe).
+#[derive(Clone, Default, Serialize)]
+pub struct DriveHealth {
+    pub device: String,
+    pub model: String,
+    pub serial: String,
+    pub protocol: String,
+    /// Résultat du test global (`smart_status.passed`).
+    pub passed: Option<bool>,
+    pub state: CheckState,
+    pub temperature: Option<i64>,
+    pub power_on_hours: Option<u64>,
+    /// Attributs ATA 5, 197 et 198.
+    pub reallocated_sectors: Option<u64>,
+    pub pending_sectors: Option<u64>,
+    pub offline_uncorrectable: Option<u64>,
+    /// Journal de santé NVMe.
+    pub percentage_used: Option<u64>,
+    pub available_spare: Option<u64>,
+    pub media_errors: Option<u64>,
+    pub critical_warning: Option<u64>,
+    /// Motifs de l'état Warning / Critical.
+    pub problems: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Configuration réseau : interfaces, routes et résolveurs DNS.
+#[derive(Serialize)]
+pub struct NetworkReport {
+    pub interfaces: Vec<NetworkInterface>,
+    /// Routes par défaut en premier.
+    pub routes: Vec<Route>,
+    pub resolvers: ResolverConfig,
+    /// Interface principale (`primary_interface` ou route par défaut).
+    pub primary: Option<String>,
+}
+
+impl NetworkReport {
+    /// Première adresse IPv4 de l'interface principale, à défaut sa première adresse IPv6
+    /// globale.
+    pub fn primary_address(&self) -> Option<String> {
+        let interface = self
+            .interfaces
+            .iter()
+            .find(|interface| interface.primary)?;
+        let ips: Vec<std::net::IpAddr> = interface
+            .addresses
+            .iter()
+            .filter_map(|address| address.split('/').next()?.parse().ok())
+            .collect();
+        ips.iter()
+            .find(|ip| ip.is_ipv4())
+            .or_else(|| {
+                ips.iter()
+                    .find(|ip| matches!(ip, std::net::IpAddr::V6(v6) if v6.segments()[0] & 0xffc0 != 0xfe80))
+            })
+            .map(|ip| ip.to_string())
+    }
+
+    /// Passerelles des routes par défaut.
+    pub fn gateways(&self) -> Vec<String> {
+        self.routes
+            .iter()
+            .filter(|route| route.default)
+            .filter_map(|route| {
+                route
+                    .gateway
+                    .as_ref()
+                    .map(|gateway| format!("{} ({})", gateway, route.interface))
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Serialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    /// `operstate` : `up`, `down`, `unknown`...
+    pub state: String,
+    pub mac: Option<String>,
+    pub mtu: Option<u32>,
+    /// Adresses IPv4 et IPv6 en notation CIDR.
+    pub addresses: Vec<String>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub loopback: bool,
+    pub primary: bool,
 }
 
 #[derive(Serialize)]
+pub struct Route {
+    /// `ipv4` ou `ipv6`.
+    pub family: String,
+    /// Réseau de destination en notation CIDR.
+    pub destination: String,
+    pub gateway: Option<String>,
+    pub interface: String,
+    pub metric: u32,
+    pub default: bool,
+}
+
+/// Contenu de `/etc/resolv.conf`.
+#[derive(Default, Serialize)]
+pub struct ResolverConfig {
+    pub nameservers: Vec<String>,
+    pub search: Vec<String>,
+    pub options: Vec<String>,
+    /// Serveurs amont de systemd-resolved quand `/etc/resolv.conf` pointe sur son résolveur local.
+    pub upstream: Vec<String>,
+}
+
+/// Adresse publique, rafraîchie en tâche de fond (`public_ip_ttl_secs`).
+#[derive(Clone, Default, Serialize)]
+pub struct PublicIpReport {
+    pub enabled: bool,
+    /// `http`, `stun` ou `command`.
+    pub method: String,
+    /// Dernières adresses connues, conservées si une recherche échoue.
+    pub ipv4: Option<String>,
+    pub ipv6: Option<String>,
+    /// Date de la dernière recherche ; `None` tant que la première n'est pas terminée.
+    pub updated: Option<String>,
+    /// Échec de la dernière recherche.
+    pub error: Option<String>,
+    /// Changements d'adresse, le plus récent en premier.
+    pub changes: Vec<PublicIpChange>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PublicIpChange {
+    /// `ipv4` ou `ipv6`.
+    pub family: String,
+    pub previous: String,
+    pub current: String,
+    pub timestamp: i64,
+    pub date: String,
+}
+
+/// Tunnels WireGuard d'après `wg show all dump`.
+#[derive(Clone, Serialize)]
+pub struct WireGuardReport {
+    /// Échec de `wg` (droits root nécessaires).
+    pub error: Option<String>,
+    pub interfaces: Vec<WireGuardInterface>,
+}
+
+impl WireGuardReport {
+    pub fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            interfaces: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct WireGuardInterface {
+    pub name: String,
+    pub public_key: String,
+    pub listen_port: Option<u16>,
+    pub peers: Vec<WireGuardPeer>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct WireGuardPeer {
+    pub public_key: String,
+    /// Dernière adresse connue du pair.
+    pub endpoint: Option<String>,
+    pub allowed_ips: Vec<String>,
+    /// Horodatage Unix du dernier handshake ; `None` s'il n'y en a jamais eu.
+    pub latest_handshake: Option<i64>,
+    /// « 2 minutes ago » ou « never ».
+    pub handshake: String,
+    pub transfer_rx: u64,
+    pub transfer_tx: u64,
+    pub persistent_keepalive: Option<u64>,
+    pub health: CheckState,
+}
+
+/// Jeu de règles du pare-feu et écarts avec la référence (`firewall_baseline_file`).
+#[derive(Clone, Default, Serialize)]
+pub struct FirewallReport {
+    /// `nftables` ou `iptables`.
+    pub backend: String,
+    /// Échec de `nft` / `iptables-save` (droits root nécessaires).
+    pub error: Option<String>,
+    pub chains: Vec<FirewallChain>,
+    /// Empreinte du jeu de règles, à fournir pour en faire la référence.
+    pub fingerprint: String,
+    /// Date de la référence.
+    pub baseline: Option<String>,
+    /// Règles absentes de la référence.
+    pub added: usize,
+    /// Règles et politiques de la référence qui ont disparu.
+    pub removed: Vec<String>,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct FirewallChain {
+    /// `ip`, `ip6`, `inet`...
+    pub family: String,
+    pub table: String,
+    pub name: String,
+    /// Politique des chaînes de base (`accept`, `drop`).
+    pub policy: Option<String>,
+    pub policy_changed: bool,
+    pub rules: Vec<FirewallRule>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct FirewallRule {
+    /// Règle sans ses compteurs.
+    pub rule: String,
+    pub packets: Option<u64>,
+    pub bytes: Option<u64>,
+    /// Absente de la référence.
+    pub added: bool,
+}
+
+/// Sockets en écoute et connexions TCP, d'après `/proc/net`.
+#[derive(Clone, Default, Serialize)]
+pub struct SocketInventory {
+    pub listeners: Vec<ListeningSocket>,
+    pub unix_listeners: Vec<UnixSocket>,
+    /// Nombre de sockets TCP (IPv4 et IPv6) par état, `ESTABLISHED`, `TIME_WAIT`...
+    pub tcp_states: Vec<(String, usize)>,
+    /// Entrées de `expected_ports` sans socket en écoute.
+    pub missing: Vec<String>,
+}
+
+/// Socket TCP en écoute ou UDP liée, avec le processus qui la détient.
+#[derive(Clone, Serialize)]
+pub struct ListeningSocket {
+    /// `tcp`, `tcp6`, `udp` ou `udp6`.
+    pub protocol: String,
+    pub address: String,
+    pub port: u16,
+    pub loopback: bool,
+    pub inode: u64,
+    pub user: String,
+    /// Inconnus si le processus appartient à un autre utilisateur (sans root).
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    /// Absente de `expected_ports`.
+    pub unexpected: bool,
+}
+
+/// Socket Unix en écoute.
+#[derive(Clone, Serialize)]
+pub struct UnixSocket {
+    /// Chemin, ou `@nom` pour l'espace de noms abstrait.
+    pub path: String,
+    /// `stream` ou `seqpacket`.
+    pub kind: String,
+    pub inode: u64,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    pub user: Option<String>,
+}
+
+/// Occupation d'un point de montage et tendance calculée sur son historique.
+#[derive(Serialize)]
+pub struct DiskForecast {
+    pub mount_point: String,
+    pub device: String,
+    pub total: u64,
+    pub available: u64,
+    pub inodes_total: u64,
+    pub inodes_available: u64,
+    /// Relevés utilisés pour la tendance.
+    pub samples: usize,
+    /// Octets consommés par jour (négatif si l'espace libre augmente).
+    pub growth_per_day: Option<f64>,
+    /// `None` : historique insuffisant, occupation stable ou en baisse.
+    pub hours_until_full: Option<f64>,
+    pub inode_hours_until_full: Option<f64>,
+    pub health: CheckState,
+}
+
+impl DiskForecast {
+    /// Pourcentage utilisé, calculé comme `df` (espace réservé à root exclu).
+    pub fn used_percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.total - self.available) as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+/// Santé du stockage logiciel : mdraid, ZFS, thin pools LVM et Btrfs.
+#[derive(Clone, Default, Serialize)]
+pub struct StorageReport {
+    pub md_arrays: Vec<MdArray>,
+    pub zfs_pools: Vec<ZfsPool>,
+    pub thin_pools: Vec<ThinPool>,
+    pub btrfs: Vec<BtrfsFilesystem>,
+    /// Échecs des outils installés (`zpool`, `lvs`, `btrfs`).
+    pub errors: Vec<String>,
+}
+
+impl StorageReport {
+    pub fn is_empty(&self) -> bool {
+        self.md_arrays.is_empty()
+            && self.zfs_pools.is_empty()
+            && self.thin_pools.is_empty()
+            && self.btrfs.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// Grappe RAID logicielle décrite par `/proc/mdstat`.
+#[derive(Clone, Default, Serialize)]
+pub struct MdArray {
+    pub name: String,
+    /// `active` ou `inactive`.
+    pub state: String,
+    pub level: String,
+    pub devices: Vec<String>,
+    /// Membres marqués `(F)` et `(S)`.
+    pub failed: Vec<String>,
+    pub spares: Vec<String>,
+    /// `[3/2]` : membres attendus / actifs.
+    pub expected: usize,
+    pub active: usize,
+    /// `[_UU]` : état de chaque membre.
+    pub members: String,
+    /// `resync`, `recovery`, `reshape`, `check` ou `repair` en cours.
+    pub sync_action: Option<String>,
+    pub sync_progress: Option<f64>,
+    pub finish: Option<String>,
+    pub health: CheckState,
+}
+
+/// Pool ZFS d'après `zpool status` et `zfs list`.
+#[derive(Clone, Default, Serialize)]
+pub struct ZfsPool {
+    pub name: String,
+    /// `ONLINE`, `DEGRADED`, `FAULTED`...
+    pub state: String,
+    /// Dernier scrub ou resilver.
+    pub scan: Option<String>,
+    pub errors: String,
+    /// Vdevs qui ne sont pas `ONLINE`.
+    pub problems: Vec<String>,
+    pub used: Option<u64>,
+    pub available: Option<u64>,
+    pub health: CheckState,
+}
+
+/// Thin pool LVM et son remplissage.
+#[derive(Clone, Default, Serialize)]
+pub struct ThinPool {
+    pub vg: String,
+    pub lv: String,
+    pub size: u64,
+    pub data_percent: Option<f64>,
+    pub metadata_percent: Option<f64>,
+    pub health: CheckState,
+}
+
+/// Compteurs d'erreurs `btrfs device stats` d'un système de fichiers monté.
+#[derive(Clone, Default, Serialize)]
+pub struct BtrfsFilesystem {
+    pub device: String,
+    pub mount_point: String,
+    /// Compteurs non nuls.
+    pub problems: Vec<String>,
+    pub health: CheckState,
+}
+
+/// Compteurs de `/proc/vmstat`, cumulés depuis le démarrage.
+#[derive(Default, Serialize)]
+pub struct VmStat {
+    /// Défauts de page majeurs (lecture sur disque).
+    pub pgmajfault: u64,
+    /// Pages lues depuis / écrites vers le swap.
+    pub pswpin: u64,
+    pub pswpout: u64,
+    pub oom_kill: u64,
+}
+
+#[derive(Clone, Default, Serialize)]
 pub struct ContainerStatus {
+    /// Libellé de l'endpoint (Docker local, distant ou Podman) qui héberge le conteneur.
+    pub endpoint: String,
+    pub id: String,
+    pub name: String,
     pub image: String,
     pub state: String,
+    pub status: String,
+    pub created: String,
+    pub ports: Vec<String>,
+    pub restart_count: i64,
+    /// Redémarrages observés dans la fenêtre de détection des boucles.
+    pub recent_restarts: i64,
+    pub crash_loop: bool,
+    /// Statut du HEALTHCHECK (`starting`, `healthy`, `unhealthy`), absent s'il n'y en a pas.
+    pub health: Option<String>,
+    pub health_output: Option<String>,
+    pub exit_code: Option<i64>,
+    pub oom_killed: bool,
+    /// Politique de redémarrage (`no`, `always`, `unless-stopped`, `on-failure`).
+    pub restart_policy: String,
+    /// Horodatages Unix du dernier démarrage / arrêt.
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    pub stats: Option<ContainerStats>,
+    /// Labels `com.docker.compose.project` / `com.docker.compose.service`.
+    pub compose_project: Option<String>,
+    pub compose_service: Option<String>,
+}
+
+/// État de connexion d'un endpoint de conteneurs.
+#[derive(Serialize)]
+pub struct EndpointStatus {
+    pub name: String,
+    pub runtime: String,
+    pub address: String,
+    pub connected: bool,
+    pub error: Option<String>,
+    pub containers: usize,
+}
+
+/// Projet Docker Compose avec l'état agrégé de ses services.
+#[derive(Serialize)]
+pub struct ComposeProject {
+    pub name: String,
+    pub state: CheckState,
+    /// `all running`, `degraded` ou `down`.
+    pub status: String,
+    /// Nombre de services entièrement opérationnels.
+    pub running: usize,
+    pub services: Vec<ComposeService>,
+}
+
+/// Service d'un projet Compose ; sans conteneur, il est attendu mais absent.
+#[derive(Serialize)]
+pub struct ComposeService {
+    pub name: String,
+    pub state: CheckState,
+    pub containers: Vec<ContainerStatus>,
+}
+
+/// Consommation d'un conteneur issue de l'API stats de Docker.
+#[derive(Clone, Serialize)]
+pub struct ContainerStats {
+    pub cpu_percent: f64,
+    pub memory_usage: u64,
+    pub memory_limit: u64,
+    pub network_rx: u64,
+    pub network_tx: u64,
+    pub block_read: u64,
+    pub block_write: u64,
+}
+
+/// Événement du cycle de vie d'un conteneur reçu sur le flux `/events` de Docker.
+#[derive(Clone, Serialize)]
+pub struct ContainerEvent {
+    pub time: String,
+    pub timestamp: i64,
+    pub endpoint: String,
+    pub id: String,
+    pub name: String,
+    /// Action Docker (`start`, `die`, `oom`, `health_status: unhealthy`...).
+    pub action: String,
+    pub exit_code: Option<String>,
+}
+
+/// Occupation disque d'un endpoint (`docker system df`), en octets.
+#[derive(Clone, Default, Serialize)]
+pub struct DockerDiskUsage {
+    pub endpoint: String,
+    pub error: Option<String>,
+    pub images_count: usize,
+    pub images_size: u64,
+    pub dangling_images: usize,
+    pub images_reclaimable: u64,
+    /// Couches en écriture des conteneurs.
+    pub containers_size: u64,
+    pub containers_reclaimable: u64,
+    pub volumes_count: usize,
+    pub volumes_size: u64,
+    pub volumes_reclaimable: u64,
+    pub build_cache_size: u64,
+    pub build_cache_reclaimable: u64,
+    /// Total récupérable, toutes catégories confondues.
+    pub reclaimable: u64,
+    pub largest_images: Vec<ImageUsage>,
+    pub unused_volumes: Vec<VolumeUsage>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ImageUsage {
+    pub id: String,
+    pub tags: Vec<String>,
+    /// Image sans tag (`<none>:<none>`).
+    pub dangling: bool,
+    pub size: u64,
+    pub containers: u64,
+}
+
+/// Volume qu'aucun conteneur ne référence.
+#[derive(Clone, Serialize)]
+pub struct VolumeUsage {
+    pub name: String,
+    pub driver: String,
+    pub size: u64,
+}
+
+/// Résultat d'un nettoyage déclenché depuis l'API.
+#[derive(Default, Serialize)]
+pub struct PruneReport {
+    pub containers_deleted: usize,
+    pub images_deleted: usize,
+    pub volumes_deleted: usize,
+    pub build_cache_deleted: usize,
+    pub space_reclaimed: u64,
+}
+
+/// Page de détail d'un conteneur, avec la fin de ses journaux.
+#[derive(Template)]
+#[template(path = "container.html")]
+pub struct ContainerTemplate {
+    pub hostname: String,
+    pub container: ContainerStatus,
+    pub logs: Vec<LogLine>,
+    pub log_error: Option<String>,
+    /// L'utilisateur figure dans `docker_action_users`.
+    pub can_manage: bool,
+    pub current_year: u32,
+}
+
+/// Ligne de journal d'un conteneur (`stdout`, `stderr`, ou `console` pour un TTY).
+#[derive(Serialize)]
+pub struct LogLine {
+    pub stream: String,
+    pub timestamp: Option<String>,
+    pub message: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CertificateStatus {
+    pub name: String,
+    pub target: String,
+    pub subject: String,
+    pub sans: Vec<String>,
+    pub issuer: String,
+    pub chain_valid: bool,
+    pub chain_error: Option<String>,
+    pub not_after: String,
+    pub days_left: Option<i64>,
+    pub state: CheckState,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DnsProbeStatus {
+    pub name: String,
+    pub query: String,
+    pub record_type: String,
+    pub resolver: String,
+    pub answers: Vec<String>,
+    pub latency_ms: Option<f64>,
+    pub rcode: String,
+    pub state: CheckState,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CommandCheckStatus {
+    pub name: String,
+    pub state: CheckState,
+    pub exit_code: Option<i32>,
+    pub output: String,
+    pub long_output: String,
+    pub metrics: Vec<PerfMetric>,
+    pub duration_ms: u64,
+}
+
+/// Métrique issue des perfdata d'un script Nagios.
+#[derive(Debug, Clone, Serialize)]
+pub struct PerfMetric {
+    pub label: String,
+    pub value: f64,
+    pub unit: String,
+    pub warning: Option<String>,
+    pub critical: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Valeur d'une métrique de perfdata relevée lors d'une exécution.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PerfSample {
+    /// Horodatage Unix de l'exécution.
+    pub timestamp: i64,
+    pub value: f64,
+}
+
+#[derive(Serialize)]
+pub struct ProcessCheckStatus {
+    pub name: String,
+    pub count: usize,
+    pub min: usize,
+    pub max: Option<usize>,
+    pub state: CheckState,
+    pub processes: Vec<ProcessInfo>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: String,
+    pub user: String,
+    pub state: String,
+    pub cpu_percent: f64,
+    pub rss: u64,
+    pub threads: u64,
+    pub open_fds: Option<usize>,
+    pub uptime_secs: u64,
+}
+
+/// Consommation d'un cgroup v2 (slice, service systemd ou conteneur).
+#[derive(Default, Serialize)]
+pub struct CgroupUsage {
+    /// Nom de la slice, du service, ou du conteneur (ID court s'il est inconnu de l'inventaire).
+    pub name: String,
+    /// `slice`, `service` ou `container`.
+    pub kind: String,
+    /// Chemin relatif à `/sys/fs/cgroup`.
+    pub path: String,
+    pub container_id: Option<String>,
+    pub cpu_percent: Option<f64>,
+    pub cpu_usage_usec: Option<u64>,
+    pub memory_current: Option<u64>,
+    /// `None` lorsque la limite vaut `max`.
+    pub memory_max: Option<u64>,
+    pub memory_high: Option<u64>,
+    /// Compteurs `oom` / `oom_kill` de `memory.events`, cumulés depuis la création
+    /// du cgroup.
+    pub oom: u64,
+    pub oom_kill: u64,
+    /// Hausse de `oom_kill` dans l'heure écoulée, observée entre deux relevés.
+    pub oom_kill_recent: u64,
+    pub io_read: u64,
+    pub io_write: u64,
+    pub pids_current: Option<u64>,
+    pub pressure: Option<ResourcePressure>,
 }
+
+/// Pressure Stall Information par ressource (`/proc/pressure/*` ou `*.pressure` d'un cgroup).
+#[derive(Clone, Serialize)]
+pub struct ResourcePressure {
+    pub cpu: Option<PressureStats>,
+    pub memory: Option<PressureStats>,
+    pub io: Option<PressureStats>,
+}
+
+/// Lignes `some` (au moins une tâche bloquée) et `full` (toutes bloquées).
+#[derive(Clone, Serialize)]
+pub struct PressureStats {
+    pub some: Option<PressureLine>,
+    pub full: Option<PressureLine>,
+}
+
+/// Moyennes glissantes en % du temps et total cumulé en microsecondes.
+#[derive(Clone, Default, Serialize)]
+pub struct PressureLine {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    pub total: u64,
+}
+
 // Méthode d'aide pour vérifier si un service est actif
 /* impl StatusTemplate {
     pub fn is_active(&self, service: &str) -> bool {
         self.active_services.contains(&service.to_string())
     }
 } */
+
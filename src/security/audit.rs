@@ -0,0 +1,67 @@
+use chrono::Local;
+use log::{error, info};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    sync::{mpsc, LazyLock},
+    thread,
+};
+
+/// Écrivain dédié : les lignes sont ajoutées au fichier depuis un thread à part
+/// pour ne pas bloquer les workers actix sur le disque.
+static WRITER: LazyLock<mpsc::Sender<(String, String)>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel::<(String, String)>();
+    thread::spawn(move || {
+        for (path, line) in receiver {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(line.as_bytes()));
+            if let Err(e) = written {
+                error!("Failed to write audit log {}: {}", path, e);
+            }
+        }
+    });
+    sender
+});
+
+/// Ajoute une ligne au journal d'audit des actions effectuées depuis le tableau de bord.
+///
+/// Format : `[date] user=".." action=".." target=".." result=".."`, une action par ligne.
+/// Chaque champ est entre guillemets et ses caractères de contrôle sont échappés.
+pub fn record_action(path: &str, user: &str, action: &str, target: &str, result: &str) {
+    let line = format_entry(user, action, target, result);
+    info!("Audit: {}", line.trim_end());
+    if WRITER.send((path.to_string(), line)).is_err() {
+        error!("Failed to write audit log {}: writer stopped", path);
+    }
+}
+
+fn format_entry(user: &str, action: &str, target: &str, result: &str) -> String {
+    format!(
+        "[{}] user={:?} action={:?} target={:?} result={:?}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        user,
+        action,
+        target,
+        result
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_entry_escapes_fields() {
+        let line = format_entry(
+            "admin",
+            "stop",
+            "abc\n[2024-01-01 00:00:00] user=root",
+            "ok",
+        );
+        assert_eq!(line.matches('\n').count(), 1);
+        assert!(line.ends_with("user=\"admin\" action=\"stop\" target=\"abc\\n[2024-01-01 00:00:00] user=root\" result=\"ok\"\n"));
+    }
+}
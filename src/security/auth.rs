@@ -1,17 +1,24 @@
+use crate::config::Config;
+use crate::security::{audit::record_action, origin::check_origin};
 use actix_service::Transform;
 use actix_web::{
     body::{EitherBody, MessageBody},
     dev::{Service, ServiceRequest, ServiceResponse},
-    Error, HttpResponse,
+    Error, HttpMessage, HttpResponse,
 };
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use futures_util::future::{ok, LocalBoxFuture, Ready};
 use htpasswd_verify::Htpasswd;
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::task::{Context, Poll};
 use std::{collections::HashMap, sync::Arc}; // Importation des macros de logging
 
+/// Utilisateur authentifié, ajouté aux extensions de la requête pour les handlers
+/// (`web::ReqData<AuthenticatedUser>`).
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(pub String);
+
 pub struct AuthMiddleware {
     htpasswd: Arc<HashMap<String, String>>, // Utilisation de Arc
 }
@@ -84,6 +91,14 @@ where
                                         .check(username, password)
                                     {
                                         info!("User authenticated successfully");
+                                        if let Some(response) = reject_cross_origin(&req, username)
+                                        {
+                                            return Ok(
+                                                req.into_response(response.map_into_right_body())
+                                            );
+                                        }
+                                        req.extensions_mut()
+                                            .insert(AuthenticatedUser(username.to_string()));
                                         let res = service.call(req).await?;
                                         return Ok(res.map_into_left_body());
                                     } else {
@@ -119,3 +134,22 @@ where
         })
     }
 }
+
+/// 403 pour une requête modifiant l'état émise depuis un autre site ; le refus
+/// est inscrit au journal d'audit.
+fn reject_cross_origin(req: &ServiceRequest, user: &str) -> Option<HttpResponse> {
+    if req.method().is_safe() {
+        return None;
+    }
+    let config = Config::from_file("config").expect("Failed to load configuration");
+    let reason = check_origin(req.method(), req.headers(), &config.trusted_origins).err()?;
+    warn!("Rejected {} {}: {}", req.method(), req.path(), reason);
+    record_action(
+        &config.audit_log_file,
+        user,
+        &format!("{} {}", req.method(), req.path()),
+        req.query_string(),
+        &format!("rejected: {}", reason),
+    );
+    Some(HttpResponse::Forbidden().body(reason))
+}
@@ -1,2 +1,4 @@
+pub mod audit;
 pub mod auth;
 pub mod htaccess;
+pub mod origin;
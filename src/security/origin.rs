@@ -0,0 +1,105 @@
+use actix_web::http::{header, header::HeaderMap, Method};
+
+/// Refuse les requêtes modifiant l'état (POST...) émises depuis un autre site.
+///
+/// Le navigateur rejoue l'authentification Basic sur toute requête vers le serveur :
+/// sans ce contrôle, n'importe quelle page visitée par un administrateur pourrait
+/// déclencher une action. L'origine (`Origin`, à défaut `Referer`) doit correspondre
+/// à l'en-tête `Host` ou à l'une des `trusted_origins`. Une requête sans aucun des deux
+/// en-têtes vient d'un client hors navigateur (curl, script) et est acceptée.
+pub fn check_origin(
+    method: &Method,
+    headers: &HeaderMap,
+    trusted: &[String],
+) -> Result<(), String> {
+    if method.is_safe() {
+        return Ok(());
+    }
+    let source = headers
+        .get(header::ORIGIN)
+        .or_else(|| headers.get(header::REFERER))
+        .map(|value| value.to_str().unwrap_or_default());
+    let Some(source) = source else {
+        return Ok(());
+    };
+
+    let origin = origin_of(source);
+    if trusted
+        .iter()
+        .any(|trusted| trusted.trim_end_matches('/') == origin)
+    {
+        return Ok(());
+    }
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or_default();
+    match origin.split_once("://") {
+        Some((_, authority)) if !host.is_empty() && authority == host => Ok(()),
+        _ => Err(format!("cross-origin request from '{}'", source)),
+    }
+}
+
+/// `schéma://hôte[:port]` d'une URL (`Origin` en est déjà réduit à cette forme).
+fn origin_of(url: &str) -> &str {
+    match url.find("://") {
+        Some(scheme_end) => {
+            let rest = &url[scheme_end + 3..];
+            let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+            &url[..scheme_end + 3 + end]
+        }
+        None => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+
+    #[test]
+    fn test_check_origin() {
+        let headers = |pairs: &[(header::HeaderName, &'static str)]| {
+            let mut map = HeaderMap::new();
+            for (name, value) in pairs {
+                map.insert(name.clone(), HeaderValue::from_static(value));
+            }
+            map
+        };
+        let host = (header::HOST, "monitor.lan:8080");
+        let trusted = vec!["https://monitor.example.com".to_string()];
+
+        let same = headers(&[host.clone(), (header::ORIGIN, "http://monitor.lan:8080")]);
+        assert!(check_origin(&Method::POST, &same, &trusted).is_ok());
+
+        let referer = headers(&[
+            host.clone(),
+            (
+                header::REFERER,
+                "http://monitor.lan:8080/containers/abc?x=1",
+            ),
+        ]);
+        assert!(check_origin(&Method::POST, &referer, &trusted).is_ok());
+
+        let proxied = headers(&[
+            host.clone(),
+            (header::ORIGIN, "https://monitor.example.com"),
+        ]);
+        assert!(check_origin(&Method::POST, &proxied, &trusted).is_ok());
+
+        let foreign = headers(&[host.clone(), (header::ORIGIN, "https://evil.example")]);
+        assert!(check_origin(&Method::POST, &foreign, &trusted).is_err());
+        assert!(check_origin(&Method::GET, &foreign, &trusted).is_ok());
+
+        let sandboxed = headers(&[host.clone(), (header::ORIGIN, "null")]);
+        assert!(check_origin(&Method::POST, &sandboxed, &trusted).is_err());
+
+        let prefix = headers(&[
+            host.clone(),
+            (header::REFERER, "http://monitor.lan:8080.evil.example/"),
+        ]);
+        assert!(check_origin(&Method::POST, &prefix, &trusted).is_err());
+
+        assert!(check_origin(&Method::POST, &headers(&[host]), &trusted).is_ok());
+    }
+}
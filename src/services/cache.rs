@@ -0,0 +1,60 @@
+use crate::services::{
+    command_checks::CommandCheckCache, dns::DnsCache, docker_disk::DockerDiskCache,
+    firewall::FirewallCache, sockets::SocketCache, storage::StorageCache, tls::CertificateCache,
+    wireguard::WireGuardCache,
+};
+use std::sync::{Arc, RwLock};
+
+/// Dernier résultat d'une collecte trop lente pour le chemin d'une requête
+/// (sondes réseau, outils externes), rafraîchi en tâche de fond.
+pub struct ReportCache<T> {
+    report: RwLock<T>,
+}
+
+impl<T: Default> Default for ReportCache<T> {
+    fn default() -> Self {
+        Self {
+            report: RwLock::new(T::default()),
+        }
+    }
+}
+
+impl<T: Clone> ReportCache<T> {
+    pub fn report(&self) -> T {
+        self.report
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn set(&self, report: T) {
+        *self
+            .report
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = report;
+    }
+
+    /// Modifie le relevé en place, pour les collectes qui complètent le précédent.
+    pub fn update(&self, update: impl FnOnce(&mut T)) {
+        update(
+            &mut self
+                .report
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+    }
+}
+
+/// Caches alimentés par les sondes en tâche de fond, partagés par le tableau de bord
+/// et l'API (une seule donnée d'application).
+#[derive(Default)]
+pub struct ProbeCaches {
+    pub certificates: Arc<CertificateCache>,
+    pub dns: Arc<DnsCache>,
+    pub sockets: Arc<SocketCache>,
+    pub storage: Arc<StorageCache>,
+    pub docker_disk: Arc<DockerDiskCache>,
+    pub wireguard: Arc<WireGuardCache>,
+    pub commands: Arc<CommandCheckCache>,
+    pub firewall: Arc<FirewallCache>,
+}
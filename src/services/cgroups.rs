@@ -0,0 +1,468 @@
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{CgroupUsage, ContainerStatus},
+};
+use crate::services::pressure::cgroup_pressure;
+use log::debug;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Racine de la hiérarchie unifiée (cgroup v2).
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Intervalle entre les deux relevés de `cpu.stat`.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Profondeur maximale explorée pour trouver les scopes des conteneurs
+/// (`system.slice/docker-<id>.scope`, `machine.slice/libpod-<id>.scope`, `docker/<id>`).
+const CONTAINER_SEARCH_DEPTH: usize = 2;
+
+/// Part de `memory.max` au-delà de laquelle un cgroup est signalé.
+const MEMORY_WARNING_RATIO: f64 = 0.9;
+
+/// Fenêtre pendant laquelle un OOM kill reste signalé.
+const OOM_KILL_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Évolution du compteur `oom_kill` de chaque cgroup entre deux relevés : le compteur
+/// est cumulé depuis la création du cgroup, seules ses hausses récentes sont signalées.
+static OOM_KILLS: LazyLock<Mutex<HashMap<PathBuf, OomKills>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Default)]
+struct OomKills {
+    /// Dernière valeur relevée du compteur.
+    total: u64,
+    /// Hausses du compteur et leur date, dans la fenêtre.
+    increases: VecDeque<(Instant, u64)>,
+}
+
+impl OomKills {
+    /// Enregistre un relevé et renvoie le nombre de kills dans la fenêtre.
+    fn record(&mut self, total: u64, now: Instant) -> u64 {
+        // Compteur revenu en arrière : cgroup recréé sous le même chemin
+        let increase = if total < self.total {
+            total
+        } else {
+            total - self.total
+        };
+        self.total = total;
+        if increase > 0 {
+            self.increases.push_back((now, increase));
+        }
+        while self
+            .increases
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > OOM_KILL_WINDOW)
+        {
+            self.increases.pop_front();
+        }
+        self.increases.iter().map(|(_, increase)| increase).sum()
+    }
+}
+
+/// Cgroup suivi : slice de premier niveau, service configuré ou conteneur.
+#[derive(Debug, Clone, PartialEq)]
+struct CgroupTarget {
+    path: PathBuf,
+    name: String,
+    kind: &'static str,
+    container_id: Option<String>,
+}
+
+/// Compteurs lus dans les fichiers d'interface d'un cgroup ; les contrôleurs
+/// non activés laissent leurs champs à `None`.
+#[derive(Debug, Default)]
+struct CgroupSample {
+    cpu_usage_usec: Option<u64>,
+    memory_current: Option<u64>,
+    memory_max: Option<u64>,
+    memory_high: Option<u64>,
+    oom: u64,
+    oom_kill: u64,
+    io_read: u64,
+    io_write: u64,
+    pids_current: Option<u64>,
+}
+
+/// Consommation des slices, des services configurés et des conteneurs, lue
+/// directement dans `/sys/fs/cgroup` (sans passer par l'API Docker).
+///
+/// `containers` sert uniquement à nommer les conteneurs connus ; les autres
+/// apparaissent sous leur ID court.
+pub async fn measure_cgroups(
+    root: &Path,
+    services: &[String],
+    containers: &[ContainerStatus],
+) -> Result<Vec<CgroupUsage>, SystemError> {
+    if !root.join("cgroup.controllers").exists() {
+        debug!(
+            "{} ({})",
+            SystemError::CgroupUnavailable.message(),
+            root.display()
+        );
+        return Err(SystemError::CgroupUnavailable);
+    }
+
+    let targets = discover_cgroups(root, services);
+    let sampled_at = Instant::now();
+    let before: Vec<Option<u64>> = targets
+        .iter()
+        .map(|target| read_cpu_usage(&target.path))
+        .collect();
+    tokio::time::sleep(SAMPLE_INTERVAL).await;
+    // Temps réellement écoulé : le réveil peut être retardé si l'exécuteur est chargé
+    let interval_usec = sampled_at.elapsed().as_micros() as f64;
+
+    let names: HashMap<&str, &str> = containers
+        .iter()
+        .map(|container| (container.id.as_str(), container.name.as_str()))
+        .collect();
+
+    let now = Instant::now();
+    let mut oom_kills = OOM_KILLS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Oublie les cgroups disparus de cette hiérarchie
+    oom_kills.retain(|path, _| {
+        !path.starts_with(root) || targets.iter().any(|target| target.path == *path)
+    });
+
+    Ok(targets
+        .into_iter()
+        .zip(before)
+        .map(|(target, before)| {
+            let sample = read_cgroup(&target.path);
+            // Premier relevé d'un cgroup : ses kills passés ne sont pas datés, ils ne
+            // servent que de point de départ
+            let oom_kill_recent = match oom_kills.get_mut(&target.path) {
+                Some(history) => history.record(sample.oom_kill, now),
+                None => {
+                    oom_kills.insert(
+                        target.path.clone(),
+                        OomKills {
+                            total: sample.oom_kill,
+                            ..Default::default()
+                        },
+                    );
+                    0
+                }
+            };
+            let cpu_percent = match (before, sample.cpu_usage_usec) {
+                (Some(before), Some(after)) => {
+                    Some(after.saturating_sub(before) as f64 / interval_usec * 100.0)
+                }
+                _ => None,
+            };
+            let short_id: Option<String> = target
+                .container_id
+                .as_deref()
+                .map(|id| id.chars().take(12).collect());
+            let name = match short_id.as_deref() {
+                Some(id) => names.get(id).map_or(id, |name| *name).to_string(),
+                None => target.name,
+            };
+
+            CgroupUsage {
+                name,
+                kind: target.kind.to_string(),
+                path: target
+                    .path
+                    .strip_prefix(root)
+                    .unwrap_or(&target.path)
+                    .display()
+                    .to_string(),
+                container_id: short_id,
+                cpu_percent,
+                cpu_usage_usec: sample.cpu_usage_usec,
+                memory_current: sample.memory_current,
+                memory_max: sample.memory_max,
+                memory_high: sample.memory_high,
+                oom: sample.oom,
+                oom_kill: sample.oom_kill,
+                oom_kill_recent,
+                io_read: sample.io_read,
+                io_write: sample.io_write,
+                pids_current: sample.pids_current,
+                pressure: cgroup_pressure(&target.path),
+            }
+        })
+        .collect())
+}
+
+/// Alertes : processus tués par l'OOM killer dans l'heure, mémoire proche de `memory.max`.
+pub fn cgroup_alerts(cgroups: &[CgroupUsage]) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for cgroup in cgroups {
+        let source = format!("cgroup:{}", cgroup.name);
+        if cgroup.oom_kill_recent > 0 {
+            alerts.push(Alert::new(
+                &source,
+                CheckState::Warning,
+                format!(
+                    "{} process(es) killed by the OOM killer in {} within the last hour",
+                    cgroup.oom_kill_recent, cgroup.path
+                ),
+            ));
+        }
+        if let (Some(current), Some(max)) = (cgroup.memory_current, cgroup.memory_max) {
+            if max > 0 && current as f64 >= max as f64 * MEMORY_WARNING_RATIO {
+                alerts.push(Alert::new(
+                    &source,
+                    CheckState::Warning,
+                    format!(
+                        "memory at {:.0}% of memory.max ({} / {} bytes)",
+                        current as f64 / max as f64 * 100.0,
+                        current,
+                        max
+                    ),
+                ));
+            }
+        }
+    }
+    alerts
+}
+
+/// Slices de premier niveau, unités des services configurés et scopes des conteneurs.
+fn discover_cgroups(root: &Path, services: &[String]) -> Vec<CgroupTarget> {
+    let mut targets: Vec<CgroupTarget> = subdirectories(root)
+        .into_iter()
+        .filter(|(name, _)| name.ends_with(".slice"))
+        .map(|(name, path)| CgroupTarget {
+            path,
+            name,
+            kind: "slice",
+            container_id: None,
+        })
+        .collect();
+    targets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for service in services {
+        let unit = if service.contains('.') {
+            service.clone()
+        } else {
+            format!("{}.service", service)
+        };
+        let path = root.join("system.slice").join(&unit);
+        if path.is_dir() {
+            targets.push(CgroupTarget {
+                path,
+                name: service.clone(),
+                kind: "service",
+                container_id: None,
+            });
+        }
+    }
+
+    let mut containers = Vec::new();
+    find_containers(root, 0, &mut containers);
+    containers.sort_by(|a, b| a.container_id.cmp(&b.container_id));
+    targets.extend(containers);
+    targets
+}
+
+fn find_containers(dir: &Path, depth: usize, found: &mut Vec<CgroupTarget>) {
+    for (name, path) in subdirectories(dir) {
+        if let Some(id) = container_id(&name) {
+            found.push(CgroupTarget {
+                path,
+                name: name.clone(),
+                kind: "container",
+                container_id: Some(id.to_string()),
+            });
+        } else if depth < CONTAINER_SEARCH_DEPTH {
+            find_containers(&path, depth + 1, found);
+        }
+    }
+}
+
+/// ID d'un conteneur d'après le nom de son cgroup : `docker-<id>.scope`,
+/// `libpod-<id>.scope` (pilote systemd) ou `<id>` seul (pilote cgroupfs).
+fn container_id(name: &str) -> Option<&str> {
+    let id = name
+        .strip_prefix("docker-")
+        .or_else(|| name.strip_prefix("libpod-"))
+        .and_then(|id| id.strip_suffix(".scope"))
+        .unwrap_or(name);
+    (id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())).then_some(id)
+}
+
+fn subdirectories(dir: &Path) -> Vec<(String, PathBuf)> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+                .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.path())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_cgroup(path: &Path) -> CgroupSample {
+    let read = |file: &str| fs::read_to_string(path.join(file)).ok();
+    // `max` signifie « pas de limite »
+    let limit = |file: &str| read(file).and_then(|value| value.trim().parse::<u64>().ok());
+
+    let events = read("memory.events").unwrap_or_default();
+    let (io_read, io_write) = read("io.stat")
+        .map(|stat| parse_io_stat(&stat))
+        .unwrap_or_default();
+
+    CgroupSample {
+        cpu_usage_usec: read_cpu_usage(path),
+        memory_current: limit("memory.current"),
+        memory_max: limit("memory.max"),
+        memory_high: limit("memory.high"),
+        oom: flat_keyed(&events, "oom").unwrap_or(0),
+        oom_kill: flat_keyed(&events, "oom_kill").unwrap_or(0),
+        io_read,
+        io_write,
+        pids_current: limit("pids.current"),
+    }
+}
+
+fn read_cpu_usage(path: &Path) -> Option<u64> {
+    flat_keyed(
+        &fs::read_to_string(path.join("cpu.stat")).ok()?,
+        "usage_usec",
+    )
+}
+
+/// Valeur d'une clé dans un fichier « clé valeur » par ligne (`cpu.stat`, `memory.events`).
+fn flat_keyed(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        (name == key).then(|| value.trim().parse().ok())?
+    })
+}
+
+/// Octets lus et écrits, tous périphériques confondus
+/// (`8:0 rbytes=1 wbytes=2 rios=3 wios=4 dbytes=0 dios=0`).
+fn parse_io_stat(stat: &str) -> (u64, u64) {
+    stat.split_whitespace()
+        .filter_map(|field| field.split_once('='))
+        .fold((0, 0), |(read, write), (key, value)| {
+            let value = value.parse::<u64>().unwrap_or(0);
+            match key {
+                "rbytes" => (read + value, write),
+                "wbytes" => (read, write + value),
+                _ => (read, write),
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, files: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_measure_cgroups_from_hierarchy() {
+        let root =
+            std::env::temp_dir().join(format!("monitor_server_cgroup_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let id = "0123456789ab".repeat(5) + "cdef";
+        write(&root, &[("cgroup.controllers", "cpu io memory pids")]);
+        write(&root.join("user.slice"), &[("cpu.stat", "usage_usec 10\n")]);
+        write(
+            &root.join("system.slice/nginx.service"),
+            &[
+                ("cpu.stat", "usage_usec 1000\nuser_usec 800\n"),
+                ("memory.current", "943718400\n"),
+                ("memory.max", "1048576000\n"),
+                ("memory.high", "max\n"),
+                ("memory.events", "low 0\nhigh 0\nmax 4\noom 2\noom_kill 1\n"),
+                (
+                    "io.stat",
+                    "8:0 rbytes=4096 wbytes=1024 rios=1 wios=1\n259:0 rbytes=4096 wbytes=0\n",
+                ),
+                ("pids.current", "3\n"),
+            ],
+        );
+        write(
+            &root.join(format!("system.slice/docker-{}.scope", id)),
+            &[("memory.current", "1024\n"), ("memory.max", "max\n")],
+        );
+
+        let containers = [ContainerStatus {
+            id: id[..12].to_string(),
+            name: "web".to_string(),
+            ..Default::default()
+        }];
+        let cgroups = measure_cgroups(&root, &["nginx".to_string()], &containers)
+            .await
+            .unwrap();
+        // Le kill antérieur au premier relevé n'est pas signalé
+        assert_eq!(cgroups[2].oom_kill_recent, 0);
+        write(
+            &root.join("system.slice/nginx.service"),
+            &[("memory.events", "low 0\nhigh 0\nmax 4\noom 2\noom_kill 3\n")],
+        );
+        let cgroups = measure_cgroups(&root, &["nginx".to_string()], &containers)
+            .await
+            .unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let names: Vec<(&str, &str)> = cgroups
+            .iter()
+            .map(|cgroup| (cgroup.kind.as_str(), cgroup.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("slice", "system.slice"),
+                ("slice", "user.slice"),
+                ("service", "nginx"),
+                ("container", "web"),
+            ]
+        );
+
+        let nginx = &cgroups[2];
+        assert_eq!(nginx.path, "system.slice/nginx.service");
+        assert_eq!(nginx.cpu_percent, Some(0.0));
+        assert_eq!(nginx.memory_high, None);
+        assert_eq!(
+            (nginx.oom, nginx.oom_kill, nginx.oom_kill_recent),
+            (2, 3, 2)
+        );
+        assert_eq!((nginx.io_read, nginx.io_write), (8192, 1024));
+        assert_eq!(nginx.pids_current, Some(3));
+
+        let web = &cgroups[3];
+        assert_eq!(web.container_id.as_deref(), Some(&id[..12]));
+        assert_eq!((web.memory_current, web.memory_max), (Some(1024), None));
+        assert_eq!(web.cpu_percent, None);
+
+        let alerts = cgroup_alerts(&cgroups);
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts.iter().all(|alert| alert.source == "cgroup:nginx"));
+    }
+
+    #[test]
+    fn test_oom_kills_window() {
+        let start = Instant::now();
+        let mut history = OomKills {
+            total: 4,
+            ..Default::default()
+        };
+        assert_eq!(history.record(4, start), 0);
+        assert_eq!(history.record(6, start + Duration::from_secs(60)), 2);
+        assert_eq!(history.record(7, start + Duration::from_secs(1800)), 3);
+        assert_eq!(history.record(7, start + Duration::from_secs(3700)), 1);
+        // Cgroup recréé : le compteur repart de zéro
+        assert_eq!(history.record(1, start + Duration::from_secs(3800)), 2);
+        assert_eq!(history.record(1, start + Duration::from_secs(9000)), 0);
+    }
+}
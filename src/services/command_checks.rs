@@ -0,0 +1,535 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{CommandCheckStatus, PerfMetric, PerfSample},
+};
+use crate::services::{cache::ReportCache, service_checker::load_checks_file, users::lookup_user};
+use chrono::Utc;
+use futures_util::future::join_all;
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    process::Stdio,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{process::Command, sync::Semaphore, time::timeout};
+
+/// Script de supervision compatible Nagios déclaré dans `services.toml` (`[[commands]]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandCheck {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Délai maximal en secondes ; 0 est remplacé par la valeur par défaut.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Utilisateur sous lequel lancer le script (nécessite que le serveur tourne en root).
+    pub user: Option<String>,
+    /// Variables d'environnement ; l'environnement du serveur n'est pas transmis.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub working_dir: Option<String>,
+}
+
+impl CommandCheck {
+    /// Délai effectif : un délai nul ferait expirer le script immédiatement.
+    fn timeout(&self) -> Duration {
+        match self.timeout_secs {
+            0 => Duration::from_secs(default_timeout_secs()),
+            secs => Duration::from_secs(secs),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CommandChecksFile {
+    #[serde(default)]
+    commands: Vec<CommandCheck>,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Nombre de valeurs conservées par métrique de perfdata.
+const PERF_HISTORY_LEN: usize = 288;
+
+/// Valeurs des perfdata par script puis par label, de la plus ancienne à la plus récente.
+type PerfHistory = HashMap<String, HashMap<String, VecDeque<PerfSample>>>;
+
+static PERF_HISTORY: LazyLock<Mutex<PerfHistory>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Dernière exécution des scripts, rafraîchie en tâche de fond par [`watch_command_checks`].
+pub type CommandCheckCache = ReportCache<Vec<CommandCheckStatus>>;
+
+/// Réglages des scripts de supervision, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct CommandCheckSettings {
+    pub services_path: String,
+    pub concurrency: usize,
+    pub interval: Duration,
+}
+
+impl CommandCheckSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            services_path: config.services_path.clone(),
+            concurrency: config.command_check_concurrency,
+            interval: Duration::from_secs(config.command_check_interval_secs),
+        }
+    }
+}
+
+pub fn load_command_checks(path: &str) -> Vec<CommandCheck> {
+    let checks = load_checks_file::<CommandChecksFile>(path)
+        .map(|file| file.commands)
+        .unwrap_or_default();
+    for check in checks.iter().filter(|check| check.timeout_secs == 0) {
+        warn!(
+            "Command check {}: timeout_secs = 0, using {} s",
+            check.name,
+            default_timeout_secs()
+        );
+    }
+    checks
+}
+
+/// Exécute périodiquement les scripts de `services.toml` et conserve leurs perfdata ;
+/// seule cette boucle alimente l'historique, à intervalle régulier.
+pub async fn watch_command_checks(cache: Arc<CommandCheckCache>, settings: CommandCheckSettings) {
+    if settings.interval.is_zero() {
+        info!("Command checks disabled");
+        return;
+    }
+    loop {
+        let statuses = run_command_checks(&settings.services_path, settings.concurrency).await;
+        debug!("Command checks run: {}", statuses.len());
+        record_perfdata(
+            &mut PERF_HISTORY
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            &statuses,
+            Utc::now().timestamp(),
+        );
+        cache.set(statuses);
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Exécute les scripts déclarés, au plus `concurrency` à la fois.
+pub async fn run_command_checks(config_path: &str, concurrency: usize) -> Vec<CommandCheckStatus> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let checks = load_command_checks(config_path);
+
+    join_all(checks.iter().map(|check| {
+        let semaphore = Arc::clone(&semaphore);
+        async move {
+            let _permit = semaphore.acquire().await;
+            run_command_check(check).await
+        }
+    }))
+    .await
+}
+
+/// Ajoute les perfdata d'une exécution ; les scripts retirés de `services.toml`
+/// sont oubliés.
+fn record_perfdata(history: &mut PerfHistory, statuses: &[CommandCheckStatus], timestamp: i64) {
+    history.retain(|name, _| statuses.iter().any(|status| status.name == *name));
+    for status in statuses {
+        let series = history.entry(status.name.clone()).or_default();
+        for metric in &status.metrics {
+            let samples = series.entry(metric.label.clone()).or_default();
+            samples.push_back(PerfSample {
+                timestamp,
+                value: metric.value,
+            });
+            while samples.len() > PERF_HISTORY_LEN {
+                samples.pop_front();
+            }
+        }
+    }
+}
+
+/// Historique des perfdata, par script puis par label.
+pub fn perfdata_history() -> BTreeMap<String, BTreeMap<String, Vec<PerfSample>>> {
+    PERF_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .map(|(name, series)| {
+            let series = series
+                .iter()
+                .map(|(label, samples)| (label.clone(), samples.iter().cloned().collect()))
+                .collect();
+            (name.clone(), series)
+        })
+        .collect()
+}
+
+pub async fn run_command_check(check: &CommandCheck) -> CommandCheckStatus {
+    let started = Instant::now();
+    let mut status = CommandCheckStatus {
+        name: check.name.clone(),
+        state: CheckState::Unknown,
+        exit_code: None,
+        output: String::new(),
+        long_output: String::new(),
+        metrics: vec![],
+        duration_ms: 0,
+    };
+
+    match execute(check).await {
+        Ok((exit_code, stdout)) => {
+            let (output, long_output, metrics) = parse_plugin_output(&stdout);
+            status.state = state_from_exit_code(exit_code);
+            status.exit_code = exit_code;
+            status.output = output;
+            status.long_output = long_output;
+            status.metrics = metrics;
+        }
+        Err(e) => {
+            error!(
+                "{} ({}): {}",
+                SystemError::CommandCheckFailed.message(),
+                check.name,
+                e
+            );
+            status.output = e;
+        }
+    }
+
+    status.duration_ms = started.elapsed().as_millis() as u64;
+    debug!(
+        "Command check {} finished with state {} in {} ms",
+        check.name,
+        status.state.label(),
+        status.duration_ms
+    );
+    status
+}
+
+/// Alertes pour les scripts qui ne renvoient pas OK.
+pub fn command_check_alerts(checks: &[CommandCheckStatus]) -> Vec<Alert> {
+    checks
+        .iter()
+        .filter(|check| check.state != CheckState::Ok)
+        .map(|check| {
+            Alert::new(
+                &format!("command:{}", check.name),
+                check.state,
+                check.output.clone(),
+            )
+        })
+        .collect()
+}
+
+async fn execute(check: &CommandCheck) -> Result<(Option<i32>, String), String> {
+    let mut command = Command::new(&check.command);
+    command
+        .args(&check.args)
+        .env_clear()
+        .env("PATH", DEFAULT_PATH)
+        .envs(&check.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Groupe de processus propre au script : à l'expiration du délai, ses
+        // sous-processus sont tués avec lui.
+        .process_group(0)
+        .kill_on_drop(true);
+
+    if let Some(dir) = &check.working_dir {
+        command.current_dir(dir);
+    }
+
+    if let Some(user) = &check.user {
+        let (uid, gid) = lookup_user(user).ok_or_else(|| format!("unknown user '{}'", user))?;
+        command.uid(uid).gid(gid);
+    }
+
+    let child = command
+        .spawn()
+        .map_err(|e| format!("failed to execute {}: {}", check.command, e))?;
+    let pid = child.id();
+    let limit = check.timeout();
+
+    match timeout(limit, child.wait_with_output()).await {
+        Ok(Ok(output)) => {
+            let mut stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+            if stdout.trim().is_empty() {
+                stdout = String::from_utf8_lossy(&output.stderr).into_owned();
+            }
+            Ok((output.status.code(), stdout))
+        }
+        Ok(Err(e)) => Err(format!("failed to wait for {}: {}", check.command, e)),
+        Err(_) => {
+            warn!(
+                "Command check {} timed out after {} s",
+                check.name,
+                limit.as_secs()
+            );
+            if let Some(pid) = pid {
+                // Le groupe porte le PID du script, qui en est le leader
+                unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+            }
+            Err(format!(
+                "plugin timed out after {} seconds",
+                limit.as_secs()
+            ))
+        }
+    }
+}
+
+fn state_from_exit_code(code: Option<i32>) -> CheckState {
+    match code {
+        Some(0) => CheckState::Ok,
+        Some(1) => CheckState::Warning,
+        Some(2) => CheckState::Critical,
+        _ => CheckState::Unknown,
+    }
+}
+
+/// Sépare la sortie d'un plugin en texte court, sortie longue et perfdata.
+///
+/// Format : `TEXTE | perfdata` sur la première ligne, puis une sortie longue
+/// optionnelle dont la partie après `|` contient des perfdata supplémentaires.
+pub fn parse_plugin_output(stdout: &str) -> (String, String, Vec<PerfMetric>) {
+    let mut lines = stdout.lines();
+    let first = lines.next().unwrap_or_default();
+    let (output, mut perfdata) = match first.split_once('|') {
+        Some((text, perf)) => (text.trim().to_string(), perf.trim().to_string()),
+        None => (first.trim().to_string(), String::new()),
+    };
+
+    let mut long_output = vec![];
+    let mut in_perfdata = false;
+    for line in lines {
+        if in_perfdata {
+            perfdata.push(' ');
+            perfdata.push_str(line.trim());
+        } else if let Some((text, perf)) = line.split_once('|') {
+            long_output.push(text.trim_end());
+            perfdata.push(' ');
+            perfdata.push_str(perf.trim());
+            in_perfdata = true;
+        } else {
+            long_output.push(line);
+        }
+    }
+
+    (
+        output,
+        long_output.join("\n").trim().to_string(),
+        parse_perfdata(&perfdata),
+    )
+}
+
+/// Décode les perfdata `'label'=valeur[UOM];[warn];[crit];[min];[max]`.
+pub fn parse_perfdata(perfdata: &str) -> Vec<PerfMetric> {
+    split_perfdata(perfdata)
+        .iter()
+        .filter_map(|item| {
+            let (label, data) = item.rsplit_once('=')?;
+            let label = unquote_label(label);
+            let mut fields = data.split(';');
+
+            let raw_value = fields.next()?;
+            let unit_start = raw_value
+                .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+                .unwrap_or(raw_value.len());
+            let value = raw_value[..unit_start].parse::<f64>().ok().or_else(|| {
+                warn!("Invalid perfdata value for {}: {}", label, raw_value);
+                None
+            })?;
+
+            let mut next_field = || {
+                fields
+                    .next()
+                    .map(str::trim)
+                    .filter(|field| !field.is_empty())
+            };
+            let warning = next_field().map(str::to_string);
+            let critical = next_field().map(str::to_string);
+            let min = next_field().and_then(|v| v.parse::<f64>().ok());
+            let max = next_field().and_then(|v| v.parse::<f64>().ok());
+
+            Some(PerfMetric {
+                label,
+                value,
+                unit: raw_value[unit_start..].to_string(),
+                warning,
+                critical,
+                min,
+                max,
+            })
+        })
+        .collect()
+}
+
+/// Découpe les perfdata sur les espaces en respectant les libellés entre apostrophes.
+fn split_perfdata(perfdata: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = perfdata.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if quoted && chars.peek() == Some(&'\'') => {
+                current.push_str("''");
+                chars.next();
+            }
+            '\'' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    items.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        items.push(current);
+    }
+    items
+}
+
+fn unquote_label(label: &str) -> String {
+    label
+        .strip_prefix('\'')
+        .and_then(|l| l.strip_suffix('\''))
+        .map(|l| l.replace("''", "'"))
+        .unwrap_or_else(|| label.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plugin_output_with_long_output() {
+        let stdout = "DISK OK - free space: / 3326 MB (56%); | /=2643MB;5948;5958;0;5968\n\
+                      / 15272 MB (77%);\n\
+                      /boot 68 MB (69%); | /boot=68MB;88;93;0;98\n\
+                      /home=69357MB;253404;253409;0;253414";
+        let (output, long_output, metrics) = parse_plugin_output(stdout);
+
+        assert_eq!(output, "DISK OK - free space: / 3326 MB (56%);");
+        assert_eq!(long_output, "/ 15272 MB (77%);\n/boot 68 MB (69%);");
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[0].label, "/");
+        assert_eq!(metrics[0].value, 2643.0);
+        assert_eq!(metrics[0].unit, "MB");
+        assert_eq!(metrics[0].warning.as_deref(), Some("5948"));
+        assert_eq!(metrics[0].max, Some(5968.0));
+        assert_eq!(metrics[2].label, "/home");
+    }
+
+    #[test]
+    fn test_parse_perfdata_quoted_labels_and_empty_fields() {
+        let metrics = parse_perfdata("'disk usage'=50%;80:;@90;; 'it''s'=1 time=0.012s");
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[0].label, "disk usage");
+        assert_eq!(metrics[0].unit, "%");
+        assert_eq!(metrics[0].warning.as_deref(), Some("80:"));
+        assert_eq!(metrics[0].critical.as_deref(), Some("@90"));
+        assert_eq!(metrics[0].min, None);
+        assert_eq!(metrics[1].label, "it's");
+        assert_eq!(metrics[2].value, 0.012);
+        assert_eq!(metrics[2].unit, "s");
+    }
+
+    #[test]
+    fn test_exit_codes_map_to_states() {
+        assert_eq!(state_from_exit_code(Some(0)), CheckState::Ok);
+        assert_eq!(state_from_exit_code(Some(1)), CheckState::Warning);
+        assert_eq!(state_from_exit_code(Some(2)), CheckState::Critical);
+        assert_eq!(state_from_exit_code(Some(3)), CheckState::Unknown);
+        assert_eq!(state_from_exit_code(Some(127)), CheckState::Unknown);
+        assert_eq!(state_from_exit_code(None), CheckState::Unknown);
+    }
+
+    #[test]
+    fn test_record_perfdata_caps_and_forgets_removed_checks() {
+        let status = |name: &str, value: f64| CommandCheckStatus {
+            name: name.to_string(),
+            state: CheckState::Ok,
+            exit_code: Some(0),
+            output: String::new(),
+            long_output: String::new(),
+            metrics: parse_perfdata(&format!("load={}", value)),
+            duration_ms: 0,
+        };
+        let mut history = PerfHistory::new();
+        record_perfdata(
+            &mut history,
+            &[status("load", 0.5), status("old", 1.0)],
+            100,
+        );
+        for i in 0..PERF_HISTORY_LEN as i64 {
+            record_perfdata(&mut history, &[status("load", i as f64)], 200 + i);
+        }
+
+        assert!(!history.contains_key("old"));
+        let samples = &history["load"]["load"];
+        assert_eq!(samples.len(), PERF_HISTORY_LEN);
+        assert_eq!(
+            samples.front(),
+            Some(&PerfSample {
+                timestamp: 200,
+                value: 0.0
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_command_check_with_timeout() {
+        let check = CommandCheck {
+            name: "sleep".to_string(),
+            command: "sleep".to_string(),
+            args: vec!["5".to_string()],
+            timeout_secs: 1,
+            user: None,
+            env: HashMap::new(),
+            working_dir: None,
+        };
+        let status = run_command_check(&check).await;
+        assert_eq!(status.state, CheckState::Unknown);
+        assert!(status.output.contains("timed out"));
+        let zero = CommandCheck {
+            timeout_secs: 0,
+            ..check.clone()
+        };
+        assert_eq!(zero.timeout(), Duration::from_secs(10));
+
+        // Un sous-processus du script ne survit pas au délai
+        let pid_file =
+            std::env::temp_dir().join(format!("monitor_server_check_{}", std::process::id()));
+        let check = CommandCheck {
+            name: "forking".to_string(),
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+            ],
+            ..check
+        };
+        let status = run_command_check(&check).await;
+        assert!(status.output.contains("timed out"));
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        std::fs::remove_file(&pid_file).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // Absent, ou zombie en attendant d'être réclamé par init
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        assert!(stat.map_or(true, |stat| stat.contains(") Z ")));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::{ComposeProject, ComposeService, ContainerStatus},
+};
+use crate::services::service_checker::load_checks_file;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Services attendus d'un projet Compose, déclarés dans `services.toml` (`[[compose]]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComposeExpectation {
+    pub project: String,
+    #[serde(default)]
+    pub services: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ComposeFile {
+    #[serde(default)]
+    compose: Vec<ComposeExpectation>,
+}
+
+pub fn load_compose_expectations(path: &str) -> Vec<ComposeExpectation> {
+    load_checks_file::<ComposeFile>(path)
+        .map(|file| file.compose)
+        .unwrap_or_default()
+}
+
+/// Regroupe les conteneurs par projet puis par service Compose (labels
+/// `com.docker.compose.project` / `com.docker.compose.service`).
+///
+/// Les services attendus mais sans conteneur apparaissent vides, donc à l'arrêt.
+/// Renvoie les projets et les conteneurs hors Compose.
+pub fn group_by_project(
+    containers: Vec<ContainerStatus>,
+    expected: &[ComposeExpectation],
+) -> (Vec<ComposeProject>, Vec<ContainerStatus>) {
+    let mut projects: BTreeMap<String, BTreeMap<String, Vec<ContainerStatus>>> = BTreeMap::new();
+    let mut standalone = vec![];
+
+    for expectation in expected {
+        let services = projects.entry(expectation.project.clone()).or_default();
+        for service in &expectation.services {
+            services.entry(service.clone()).or_default();
+        }
+    }
+
+    for container in containers {
+        match (&container.compose_project, &container.compose_service) {
+            (Some(project), Some(service)) => projects
+                .entry(project.clone())
+                .or_default()
+                .entry(service.clone())
+                .or_default()
+                .push(container),
+            _ => standalone.push(container),
+        }
+    }
+
+    let projects = projects
+        .into_iter()
+        .map(|(name, services)| {
+            let services: Vec<ComposeService> = services
+                .into_iter()
+                .map(|(name, containers)| ComposeService {
+                    state: service_state(&containers),
+                    name,
+                    containers,
+                })
+                .collect();
+            let state = rollup(services.iter().map(|service| service.state));
+            ComposeProject {
+                status: match state {
+                    CheckState::Ok => "all running",
+                    CheckState::Warning => "degraded",
+                    _ => "down",
+                }
+                .to_string(),
+                running: services
+                    .iter()
+                    .filter(|service| service.state == CheckState::Ok)
+                    .count(),
+                name,
+                state,
+                services,
+            }
+        })
+        .collect();
+
+    (projects, standalone)
+}
+
+/// Alertes pour les projets dégradés ou à l'arrêt.
+pub fn compose_alerts(projects: &[ComposeProject]) -> Vec<Alert> {
+    projects
+        .iter()
+        .filter(|project| project.state != CheckState::Ok)
+        .map(|project| {
+            let failing: Vec<String> = project
+                .services
+                .iter()
+                .filter(|service| service.state != CheckState::Ok)
+                .map(|service| match service.containers.is_empty() {
+                    true => format!("{} (missing)", service.name),
+                    false => service.name.clone(),
+                })
+                .collect();
+            Alert::new(
+                &format!("compose:{}", project.name),
+                project.state,
+                format!("project {}: {}", project.status, failing.join(", ")),
+            )
+        })
+        .collect()
+}
+
+/// OK si tous les conteneurs du service tournent sans problème ; un conteneur
+/// sorti avec le code 0 (tâche ponctuelle, migration...) est considéré comme terminé.
+fn service_state(containers: &[ContainerStatus]) -> CheckState {
+    let healthy = containers
+        .iter()
+        .filter(|container| is_healthy(container))
+        .count();
+    match healthy {
+        0 => CheckState::Critical,
+        n if n == containers.len() => CheckState::Ok,
+        _ => CheckState::Warning,
+    }
+}
+
+fn is_healthy(container: &ContainerStatus) -> bool {
+    let running = container.state == "running"
+        && container.health.as_deref() != Some("unhealthy")
+        && !container.crash_loop;
+    running || (container.state == "exited" && container.exit_code == Some(0))
+}
+
+fn rollup(states: impl Iterator<Item = CheckState>) -> CheckState {
+    let (mut ok, mut total) = (0, 0);
+    for state in states {
+        total += 1;
+        if state == CheckState::Ok {
+            ok += 1;
+        } else if state == CheckState::Warning {
+            return CheckState::Warning;
+        }
+    }
+    // Un projet déclaré sans aucun conteneur est lui aussi à l'arrêt
+    match ok {
+        0 => CheckState::Critical,
+        n if n == total => CheckState::Ok,
+        _ => CheckState::Warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(project: &str, service: &str, state: &str) -> ContainerStatus {
+        ContainerStatus {
+            name: format!("{}-{}-1", project, service),
+            state: state.to_string(),
+            compose_project: Some(project.to_string()),
+            compose_service: Some(service.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_project_rollup_and_missing_services() {
+        let containers = vec![
+            container("blog", "web", "running"),
+            container("blog", "db", "running"),
+            container("shop", "web", "running"),
+            container("shop", "worker", "exited"),
+            ContainerStatus {
+                name: "portainer".to_string(),
+                state: "running".to_string(),
+                ..Default::default()
+            },
+        ];
+        let expected = vec![
+            ComposeExpectation {
+                project: "blog".to_string(),
+                services: vec!["web".to_string(), "db".to_string()],
+            },
+            ComposeExpectation {
+                project: "wiki".to_string(),
+                services: vec!["app".to_string()],
+            },
+        ];
+
+        let (projects, standalone) = group_by_project(containers, &expected);
+        assert_eq!(standalone.len(), 1);
+        assert_eq!(projects.len(), 3);
+
+        assert_eq!(projects[0].name, "blog");
+        assert_eq!(projects[0].state, CheckState::Ok);
+        assert_eq!(projects[1].name, "shop");
+        assert_eq!(projects[1].state, CheckState::Warning);
+        assert_eq!(projects[1].running, 1);
+        assert_eq!(projects[2].name, "wiki");
+        assert_eq!(projects[2].state, CheckState::Critical);
+        assert!(projects[2].services[0].containers.is_empty());
+
+        let alerts = compose_alerts(&projects);
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[1].message, "project down: app (missing)");
+    }
+}
@@ -0,0 +1,319 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::DiskForecast,
+};
+use crate::services::{
+    hardware::{filesystem_stats, read_mounts},
+    state_file::write_state_file,
+};
+use chrono::Utc;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// Nombre minimal de relevés, et durée minimale qu'ils doivent couvrir, avant toute prévision.
+const MIN_SAMPLES: usize = 3;
+const MIN_SPAN_SECS: i64 = 1800;
+
+/// Au-delà d'un an, la tendance n'est plus significative : pas de prévision.
+const MAX_FORECAST_HOURS: f64 = 365.0 * 24.0;
+
+/// Systèmes de fichiers qui ne peuvent pas se remplir.
+const IGNORED_FSTYPES: &[&str] = &["squashfs", "iso9660", "udf"];
+
+/// Réglages de l'historique d'occupation, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct ForecastSettings {
+    pub interval: Duration,
+    /// Durée d'historique conservée et utilisée pour la tendance.
+    pub history: Duration,
+    /// Fichier JSON de l'historique ; vide : conservé en mémoire seulement.
+    pub history_file: String,
+}
+
+impl ForecastSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            interval: Duration::from_secs(config.disk_sample_interval_secs),
+            history: Duration::from_secs(config.disk_history_hours * 3600),
+            history_file: config.disk_history_file.clone(),
+        }
+    }
+}
+
+/// Relevé de l'occupation d'un point de montage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UsageSample {
+    /// Horodatage Unix, en secondes.
+    timestamp: i64,
+    device: String,
+    total: u64,
+    /// Espace disponible pour les utilisateurs non privilégiés (`f_bavail`), comme `df`.
+    available: u64,
+    inodes_total: u64,
+    inodes_available: u64,
+}
+
+/// Historique d'occupation par point de montage, alimenté par [`watch_disk_usage`].
+#[derive(Default)]
+pub struct DiskHistory {
+    samples: RwLock<BTreeMap<String, Vec<UsageSample>>>,
+}
+
+impl DiskHistory {
+    /// Reprend l'historique enregistré par une exécution précédente.
+    pub fn load(history_file: &str) -> Self {
+        let samples = match fs::read_to_string(history_file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable disk history {}: {}", history_file, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self {
+            samples: RwLock::new(samples),
+        }
+    }
+
+    /// Prévision de remplissage de chaque point de montage relevé.
+    pub fn forecasts(&self) -> Vec<DiskForecast> {
+        self.samples
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .filter_map(|(mount_point, samples)| forecast(mount_point, samples))
+            .collect()
+    }
+
+    /// Ajoute les relevés, oublie ceux plus vieux que `history` et les points de montage disparus.
+    fn record(&self, current: Vec<(String, UsageSample)>, history: Duration) -> String {
+        let mut samples = self
+            .samples
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let oldest = Utc::now().timestamp() - history.as_secs() as i64;
+        let mounted: HashSet<&String> =
+            current.iter().map(|(mount_point, _)| mount_point).collect();
+        samples.retain(|mount_point, _| mounted.contains(mount_point));
+        for (mount_point, sample) in current {
+            let history = samples.entry(mount_point).or_default();
+            // Un autre périphérique monté au même endroit : l'ancienne tendance ne vaut plus.
+            if history
+                .last()
+                .is_some_and(|last| last.device != sample.device)
+            {
+                history.clear();
+            }
+            history.retain(|sample| sample.timestamp >= oldest);
+            history.push(sample);
+        }
+        serde_json::to_string(&*samples).unwrap_or_default()
+    }
+}
+
+/// Relève l'occupation toutes les `interval` ; un intervalle nul désactive la collecte.
+pub async fn watch_disk_usage(history: Arc<DiskHistory>, settings: ForecastSettings) {
+    if settings.interval.is_zero() {
+        info!("Disk usage history disabled");
+        return;
+    }
+    loop {
+        let current = sample_filesystems();
+        debug!("Disk usage sampled for {} filesystem(s)", current.len());
+        let json = history.record(current, settings.history);
+        if !settings.history_file.is_empty() {
+            if let Err(e) = write_state_file(&settings.history_file, &json) {
+                warn!(
+                    "Failed to save disk history to {}: {}",
+                    settings.history_file, e
+                );
+            }
+        }
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Occupation des systèmes de fichiers montés en lecture-écriture sur un périphérique
+/// (un seul point de montage par périphérique : les montages `bind` sont ignorés).
+fn sample_filesystems() -> Vec<(String, UsageSample)> {
+    let timestamp = Utc::now().timestamp();
+    let mut seen = HashSet::new();
+    read_mounts()
+        .into_iter()
+        .filter_map(|mount| {
+            if !(mount.device.starts_with("/dev/") || mount.fstype == "zfs")
+                || IGNORED_FSTYPES.contains(&mount.fstype.as_str())
+                || mount.is_read_only()
+                || !seen.insert(mount.device.clone())
+            {
+                return None;
+            }
+            let stat = filesystem_stats(&mount.mount_point)?;
+            let block_size = stat.f_frsize;
+            (stat.f_blocks > 0).then(|| {
+                (
+                    mount.mount_point,
+                    UsageSample {
+                        timestamp,
+                        device: mount.device,
+                        total: stat.f_blocks * block_size,
+                        available: stat.f_bavail * block_size,
+                        inodes_total: stat.f_files,
+                        inodes_available: stat.f_favail,
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+fn forecast(mount_point: &str, samples: &[UsageSample]) -> Option<DiskForecast> {
+    let last = samples.last()?;
+    let points = |value: fn(&UsageSample) -> u64| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .map(|sample| {
+                (
+                    (sample.timestamp - last.timestamp) as f64,
+                    value(sample) as f64,
+                )
+            })
+            .collect()
+    };
+    let enough =
+        samples.len() >= MIN_SAMPLES && last.timestamp - samples[0].timestamp >= MIN_SPAN_SECS;
+    let bytes_slope = enough.then(|| slope(&points(|s| s.available))).flatten();
+    let inodes_slope = enough
+        .then(|| slope(&points(|s| s.inodes_available)))
+        .flatten();
+
+    Some(DiskForecast {
+        mount_point: mount_point.to_string(),
+        device: last.device.clone(),
+        total: last.total,
+        available: last.available,
+        inodes_total: last.inodes_total,
+        inodes_available: last.inodes_available,
+        samples: samples.len(),
+        // Pente en octets libres par seconde : négative quand le disque se remplit.
+        growth_per_day: bytes_slope.map(|slope| -slope * 86400.0),
+        hours_until_full: bytes_slope.and_then(|slope| hours_until_zero(last.available, slope)),
+        inode_hours_until_full: inodes_slope
+            .and_then(|slope| hours_until_zero(last.inodes_available, slope)),
+        health: CheckState::Ok,
+    })
+}
+
+/// Pente de la droite des moindres carrés.
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// Heures avant que `available` n'atteigne zéro au rythme `slope` (par seconde).
+fn hours_until_zero(available: u64, slope: f64) -> Option<f64> {
+    if slope >= 0.0 {
+        return None;
+    }
+    let hours = available as f64 / -slope / 3600.0;
+    (hours <= MAX_FORECAST_HOURS).then_some(hours)
+}
+
+/// Renseigne l'état de chaque prévision et lève une alerte si le disque (ou ses
+/// inodes) sera plein dans moins de `warning_hours` / `critical_hours`.
+pub fn forecast_alerts(
+    forecasts: &mut [DiskForecast],
+    warning_hours: f64,
+    critical_hours: f64,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for forecast in forecasts.iter_mut() {
+        for (resource, hours) in [
+            ("disk", forecast.hours_until_full),
+            ("inodes", forecast.inode_hours_until_full),
+        ] {
+            let Some(hours) = hours else {
+                continue;
+            };
+            let state = if hours < critical_hours {
+                CheckState::Critical
+            } else if hours < warning_hours {
+                CheckState::Warning
+            } else {
+                continue;
+            };
+            if forecast.health == CheckState::Ok || state == CheckState::Critical {
+                forecast.health = state;
+            }
+            alerts.push(Alert::new(
+                &format!("disk:{}", forecast.mount_point),
+                state,
+                format!("{} predicted full in {}", resource, format_eta(hours)),
+            ));
+        }
+    }
+    alerts
+}
+
+/// Délai approximatif : `~45 min`, `~5 h`, `~3 days`.
+pub fn format_eta(hours: f64) -> String {
+    if hours < 1.0 {
+        format!("~{} min", (hours * 60.0).round() as u64)
+    } else if hours < 48.0 {
+        format!("~{} h", hours.round() as u64)
+    } else {
+        format!("~{} days", (hours / 24.0).round() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forecast_disk_full() {
+        const GB: u64 = 1 << 30;
+        // 2 Go consommés par heure, 48 Go libres au dernier relevé : plein dans 24 h.
+        let samples: Vec<UsageSample> = (0..6)
+            .map(|hour| UsageSample {
+                timestamp: 1_700_000_000 + hour * 3600,
+                device: "/dev/sda1".to_string(),
+                total: 100 * GB,
+                available: 58 * GB - hour as u64 * 2 * GB,
+                inodes_total: 1000,
+                inodes_available: 500,
+            })
+            .collect();
+
+        let mut forecasts = vec![forecast("/srv", &samples).unwrap()];
+        assert_eq!(forecasts[0].hours_until_full, Some(24.0));
+        assert_eq!(forecasts[0].growth_per_day, Some(48.0 * GB as f64));
+        assert_eq!(forecasts[0].inode_hours_until_full, None);
+
+        // Pas assez d'historique pour une tendance
+        assert_eq!(
+            forecast("/srv", &samples[..2]).unwrap().hours_until_full,
+            None
+        );
+
+        let alerts = forecast_alerts(&mut forecasts, 72.0, 12.0);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].state, CheckState::Warning);
+        assert_eq!(forecasts[0].health, CheckState::Warning);
+        assert_eq!(format_eta(24.0), "~24 h");
+        assert_eq!(format_eta(72.4), "~3 days");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::models::{errors::SystemError, templates::DiskIoStats};
+use crate::services::hardware::read_mounts;
+use log::error;
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+/// Intervalle entre les deux relevés de `/proc/diskstats`.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Taille d'un secteur dans `/proc/diskstats`, quelle que soit celle du disque.
+const SECTOR_SIZE: u64 = 512;
+
+/// Compteurs cumulés d'un périphérique bloc (`Documentation/admin-guide/iostats.rst`).
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DiskSample {
+    reads: u64,
+    sectors_read: u64,
+    read_ms: u64,
+    writes: u64,
+    sectors_written: u64,
+    write_ms: u64,
+    in_flight: u64,
+    io_ms: u64,
+    weighted_io_ms: u64,
+}
+
+/// Débit, IOPS et latence de chaque périphérique bloc, calculés entre deux relevés.
+///
+/// Les périphériques dont le nom commence par un des `ignored_prefixes`
+/// (`loop`, `ram` par défaut) sont écartés.
+pub async fn measure_disk_io(ignored_prefixes: &[String]) -> Result<Vec<DiskIoStats>, SystemError> {
+    let before = read_diskstats()?;
+    tokio::time::sleep(SAMPLE_INTERVAL).await;
+    let after = read_diskstats()?;
+    let mounts = mount_points();
+
+    let mut stats: Vec<DiskIoStats> = after
+        .into_iter()
+        .filter(|(device, _)| {
+            !ignored_prefixes
+                .iter()
+                .any(|prefix| device.starts_with(prefix.as_str()))
+        })
+        .filter_map(|(device, after)| {
+            let before = before.get(&device)?;
+            Some(disk_io_stats(
+                &device,
+                before,
+                &after,
+                SAMPLE_INTERVAL,
+                mounts.get(&device).cloned().unwrap_or_default(),
+            ))
+        })
+        .collect();
+    stats.sort_by(|a, b| a.device.cmp(&b.device));
+    Ok(stats)
+}
+
+fn read_diskstats() -> Result<HashMap<String, DiskSample>, SystemError> {
+    let content = fs::read_to_string("/proc/diskstats").map_err(|_| {
+        error!("{}", SystemError::DiskStatsUnavailable.message());
+        SystemError::DiskStatsUnavailable
+    })?;
+    Ok(parse_diskstats(&content))
+}
+
+/// `major minor nom lectures fusions secteurs ms écritures fusions secteurs ms en_cours ms ms_pondérées ...`
+fn parse_diskstats(content: &str) -> HashMap<String, DiskSample> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+            Some((
+                fields.get(2)?.to_string(),
+                DiskSample {
+                    reads: field(3)?,
+                    sectors_read: field(5)?,
+                    read_ms: field(6)?,
+                    writes: field(7)?,
+                    sectors_written: field(9)?,
+                    write_ms: field(10)?,
+                    in_flight: field(11)?,
+                    io_ms: field(12)?,
+                    weighted_io_ms: field(13)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Mêmes calculs que `iostat -x` : `r/s`, `rkB/s`, `r_await`, `aqu-sz`, `%util`.
+fn disk_io_stats(
+    device: &str,
+    before: &DiskSample,
+    after: &DiskSample,
+    elapsed: Duration,
+    mount_points: Vec<String>,
+) -> DiskIoStats {
+    let seconds = elapsed.as_secs_f64();
+    let delta = |field: fn(&DiskSample) -> u64| field(after).saturating_sub(field(before));
+    let reads = delta(|s| s.reads);
+    let writes = delta(|s| s.writes);
+    let await_ms = |ms: u64, ios: u64| {
+        if ios == 0 {
+            0.0
+        } else {
+            ms as f64 / ios as f64
+        }
+    };
+
+    DiskIoStats {
+        device: device.to_string(),
+        mount_points,
+        read_iops: reads as f64 / seconds,
+        write_iops: writes as f64 / seconds,
+        read_bytes_per_sec: (delta(|s| s.sectors_read) * SECTOR_SIZE) as f64 / seconds,
+        write_bytes_per_sec: (delta(|s| s.sectors_written) * SECTOR_SIZE) as f64 / seconds,
+        read_await_ms: await_ms(delta(|s| s.read_ms), reads),
+        write_await_ms: await_ms(delta(|s| s.write_ms), writes),
+        await_ms: await_ms(delta(|s| s.read_ms) + delta(|s| s.write_ms), reads + writes),
+        utilization: (delta(|s| s.io_ms) as f64 / (seconds * 1000.0) * 100.0).min(100.0),
+        queue_depth: delta(|s| s.weighted_io_ms) as f64 / (seconds * 1000.0),
+        in_flight: after.in_flight,
+    }
+}
+
+/// Points de montage par nom de périphérique (`sda1`, `nvme0n1p2`, `dm-0`...).
+/// Les chemins `/dev/mapper/*` et `/dev/disk/by-*` sont résolus vers leur nœud réel.
+fn mount_points() -> HashMap<String, Vec<String>> {
+    let mut mounts: HashMap<String, Vec<String>> = HashMap::new();
+    for mount in read_mounts() {
+        if !mount.device.starts_with("/dev/") {
+            continue;
+        }
+        let device = fs::canonicalize(&mount.device)
+            .unwrap_or_else(|_| Path::new(&mount.device).to_path_buf());
+        if let Some(name) = device.file_name().and_then(|name| name.to_str()) {
+            mounts
+                .entry(name.to_string())
+                .or_default()
+                .push(mount.mount_point);
+        }
+    }
+    mounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_io_stats_between_samples() {
+        let before = parse_diskstats(
+            "   8       0 sda 1000 10 80000 2000 500 20 40000 3000 0 4000 5000 0 0 0 0\n\
+             259       0 nvme0n1 5 0 40 1 0 0 0 0 0 1 1\n",
+        );
+        let after = parse_diskstats(
+            "   8       0 sda 1100 10 88192 2400 600 20 48192 4000 2 4250 5700 0 0 0 0\n",
+        );
+        assert_eq!(before["nvme0n1"].reads, 5);
+
+        let stats = disk_io_stats(
+            "sda",
+            &before["sda"],
+            &after["sda"],
+            Duration::from_secs(1),
+            vec!["/".to_string()],
+        );
+        assert_eq!(stats.read_iops, 100.0);
+        assert_eq!(stats.write_iops, 100.0);
+        assert_eq!(stats.read_bytes_per_sec, 8192.0 * 512.0);
+        assert_eq!(stats.read_await_ms, 4.0);
+        assert_eq!(stats.write_await_ms, 10.0);
+        assert_eq!(stats.await_ms, 7.0);
+        assert_eq!(stats.utilization, 25.0);
+        assert_eq!(stats.queue_depth, 0.7);
+        assert_eq!(stats.in_flight, 2);
+    }
+}
@@ -0,0 +1,581 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::DnsProbeStatus,
+};
+use crate::services::{cache::ReportCache, service_checker::load_checks_file};
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Dernier relevé des sondes DNS, rafraîchi en tâche de fond par [`watch_dns`].
+pub type DnsCache = ReportCache<Vec<DnsProbeStatus>>;
+
+/// Réglages des sondes DNS, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct DnsSettings {
+    pub services_path: String,
+    pub interval: Duration,
+}
+
+impl DnsSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            services_path: config.services_path.clone(),
+            interval: Duration::from_secs(config.dns_interval_secs),
+        }
+    }
+}
+
+/// Sonde DNS déclarée dans `services.toml` (`[[dns]]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct DnsCheck {
+    pub name: String,
+    pub query: String,
+    #[serde(default = "default_record_type")]
+    pub record_type: String,
+    /// `ip` ou `ip:port` ; à défaut, premier `nameserver` de `/etc/resolv.conf`.
+    pub resolver: Option<String>,
+    /// Réponses attendues (ordre indifférent) ; vide = toute réponse NOERROR est acceptée.
+    #[serde(default)]
+    pub expected: Vec<String>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Latence au-delà de laquelle la sonde passe en WARNING.
+    pub warning_latency_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct DnsChecksFile {
+    #[serde(default)]
+    dns: Vec<DnsCheck>,
+}
+
+fn default_record_type() -> String {
+    "A".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+    2000
+}
+
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+const RCODE_NOERROR: u8 = 0;
+const RCODE_SERVFAIL: u8 = 2;
+const RCODE_NXDOMAIN: u8 = 3;
+
+pub fn load_dns_checks(path: &str) -> Vec<DnsCheck> {
+    load_checks_file::<DnsChecksFile>(path)
+        .map(|file| file.dns)
+        .unwrap_or_default()
+}
+
+/// Exécute les sondes toutes les `interval` ; un intervalle nul désactive les sondes.
+///
+/// Les sockets sont bloquantes : les sondes tournent hors de l'exécuteur, et
+/// `services.toml` est relu à chaque passe.
+pub async fn watch_dns(cache: Arc<DnsCache>, settings: DnsSettings) {
+    if settings.interval.is_zero() {
+        info!("DNS probes disabled");
+        return;
+    }
+    loop {
+        let services_path = settings.services_path.clone();
+        match tokio::task::spawn_blocking(move || check_dns(&services_path)).await {
+            Ok(probes) => {
+                debug!("DNS probes run: {}", probes.len());
+                cache.set(probes);
+            }
+            Err(e) => warn!("DNS probes aborted: {}", e),
+        }
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Exécute toutes les sondes DNS déclarées dans `services.toml`.
+pub fn check_dns(config_path: &str) -> Vec<DnsProbeStatus> {
+    load_dns_checks(config_path)
+        .iter()
+        .map(run_dns_check)
+        .collect()
+}
+
+pub fn run_dns_check(check: &DnsCheck) -> DnsProbeStatus {
+    let mut status = DnsProbeStatus {
+        name: check.name.clone(),
+        query: check.query.clone(),
+        record_type: check.record_type.to_uppercase(),
+        resolver: String::new(),
+        answers: vec![],
+        latency_ms: None,
+        rcode: String::new(),
+        state: CheckState::Unknown,
+        error: None,
+    };
+
+    let result = record_type_code(&check.record_type).and_then(|qtype| {
+        let resolver = resolver_address(check.resolver.as_deref())?;
+        status.resolver = resolver.to_string();
+        query(
+            resolver,
+            &check.query,
+            qtype,
+            Duration::from_millis(check.timeout_ms),
+        )
+    });
+
+    match result {
+        Ok(response) => {
+            status.latency_ms = Some(response.latency.as_secs_f64() * 1000.0);
+            status.rcode = rcode_name(response.rcode);
+            status.answers = response.answers;
+            status.state = evaluate(check, &status);
+        }
+        Err(e) => {
+            error!(
+                "{} ({}): {}",
+                SystemError::DnsProbeFailed.message(),
+                check.name,
+                e
+            );
+            status.state = CheckState::Critical;
+            status.error = Some(e);
+        }
+    }
+    status
+}
+
+/// Alertes pour les sondes DNS en échec ou trop lentes.
+pub fn dns_alerts(probes: &[DnsProbeStatus]) -> Vec<Alert> {
+    probes
+        .iter()
+        .filter(|probe| probe.state != CheckState::Ok)
+        .map(|probe| {
+            let message = match &probe.error {
+                Some(e) => format!(
+                    "{} {} via {}: {}",
+                    probe.record_type, probe.query, probe.resolver, e
+                ),
+                None => format!(
+                    "{} {} via {}: {} [{}] in {:.1} ms",
+                    probe.record_type,
+                    probe.query,
+                    probe.resolver,
+                    probe.rcode,
+                    probe.answers.join(", "),
+                    probe.latency_ms.unwrap_or_default()
+                ),
+            };
+            Alert::new(&format!("dns:{}", probe.name), probe.state, message)
+        })
+        .collect()
+}
+
+fn evaluate(check: &DnsCheck, status: &DnsProbeStatus) -> CheckState {
+    if status.rcode != rcode_name(RCODE_NOERROR) {
+        return CheckState::Critical;
+    }
+
+    if !check.expected.is_empty() {
+        let mut expected: Vec<String> = check.expected.iter().map(|a| normalize(a)).collect();
+        let mut answers: Vec<String> = status.answers.iter().map(|a| normalize(a)).collect();
+        expected.sort();
+        answers.sort();
+        if expected != answers {
+            return CheckState::Critical;
+        }
+    }
+
+    match (check.warning_latency_ms, status.latency_ms) {
+        (Some(limit), Some(latency)) if latency > limit as f64 => CheckState::Warning,
+        _ => CheckState::Ok,
+    }
+}
+
+fn normalize(answer: &str) -> String {
+    answer.trim().trim_end_matches('.').to_lowercase()
+}
+
+fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        RCODE_NOERROR => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        RCODE_SERVFAIL => "SERVFAIL".to_string(),
+        RCODE_NXDOMAIN => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        other => format!("RCODE{}", other),
+    }
+}
+
+fn record_type_code(record_type: &str) -> Result<u16, String> {
+    match record_type.to_uppercase().as_str() {
+        "A" => Ok(1),
+        "CNAME" => Ok(5),
+        "MX" => Ok(15),
+        "TXT" => Ok(16),
+        "AAAA" => Ok(28),
+        other => Err(format!("unsupported record type '{}'", other)),
+    }
+}
+
+fn resolver_address(resolver: Option<&str>) -> Result<SocketAddr, String> {
+    let resolver = match resolver {
+        Some(resolver) => resolver.to_string(),
+        None => {
+            system_resolver().ok_or_else(|| format!("no nameserver found in {}", RESOLV_CONF))?
+        }
+    };
+
+    resolver
+        .parse::<SocketAddr>()
+        .or_else(|_| resolver.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| format!("invalid resolver address '{}'", resolver))
+}
+
+/// Premier `nameserver` déclaré dans `/etc/resolv.conf`.
+fn system_resolver() -> Option<String> {
+    fs::read_to_string(RESOLV_CONF)
+        .map_err(|_| warn!("Failed to read {}", RESOLV_CONF))
+        .ok()?
+        .lines()
+        .filter_map(|line| line.strip_prefix("nameserver"))
+        .map(|value| value.trim().to_string())
+        .next()
+}
+
+struct DnsResponse {
+    rcode: u8,
+    answers: Vec<String>,
+    latency: Duration,
+}
+
+fn query(
+    resolver: SocketAddr,
+    name: &str,
+    qtype: u16,
+    timeout: Duration,
+) -> Result<DnsResponse, String> {
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u16)
+        .unwrap_or(0x4d53);
+    let request = build_query(id, name, qtype)?;
+
+    let bind_address = if resolver.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_address).map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    // Échéance de toute la sonde : les paquets étrangers ne relancent pas l'attente.
+    let deadline = started + timeout;
+    let timed_out = || format!("timeout after {} ms", timeout.as_millis());
+    let remaining = || {
+        Some(deadline.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+            .ok_or_else(timed_out)
+    };
+    socket
+        .send_to(&request, resolver)
+        .map_err(|e| e.to_string())?;
+
+    let mut buffer = [0u8; 4096];
+    let message = loop {
+        socket
+            .set_read_timeout(Some(remaining()?))
+            .map_err(|e| e.to_string())?;
+        let (size, from) = socket.recv_from(&mut buffer).map_err(|e| match e.kind() {
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => timed_out(),
+            _ => e.to_string(),
+        })?;
+        if from == resolver && size >= 2 && u16::from_be_bytes([buffer[0], buffer[1]]) == id {
+            break buffer[..size].to_vec();
+        }
+    };
+
+    // Réponse tronquée : on rejoue la requête en TCP, dans le temps restant.
+    let message = if message.len() > 2 && message[2] & 0x02 != 0 {
+        query_tcp(resolver, &request, remaining()?)?
+    } else {
+        message
+    };
+    let latency = started.elapsed();
+
+    let (rcode, answers) = parse_response(&message, qtype)?;
+    Ok(DnsResponse {
+        rcode,
+        answers,
+        latency,
+    })
+}
+
+fn query_tcp(resolver: SocketAddr, request: &[u8], timeout: Duration) -> Result<Vec<u8>, String> {
+    let mut stream = TcpStream::connect_timeout(&resolver, timeout).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+
+    let mut framed = (request.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(request);
+    stream.write_all(&framed).map_err(|e| e.to_string())?;
+
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length).map_err(|e| e.to_string())?;
+    let mut message = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut message).map_err(|e| e.to_string())?;
+    Ok(message)
+}
+
+fn build_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>, String> {
+    let mut message = Vec::with_capacity(512);
+    message.extend_from_slice(&id.to_be_bytes());
+    // RD=1, une question.
+    message.extend_from_slice(&[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    encode_name(&mut message, name)?;
+    message.extend_from_slice(&qtype.to_be_bytes());
+    message.extend_from_slice(&1u16.to_be_bytes());
+    Ok(message)
+}
+
+fn encode_name(message: &mut Vec<u8>, name: &str) -> Result<(), String> {
+    for label in name
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+    {
+        if label.len() > 63 {
+            return Err(format!("label too long in '{}'", name));
+        }
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    Ok(())
+}
+
+/// Décode l'en-tête et les réponses du type demandé.
+fn parse_response(message: &[u8], qtype: u16) -> Result<(u8, Vec<String>), String> {
+    if message.len() < 12 {
+        return Err("truncated DNS header".to_string());
+    }
+    let rcode = message[3] & 0x0F;
+    let questions = u16::from_be_bytes([message[4], message[5]]);
+    let answer_count = u16::from_be_bytes([message[6], message[7]]);
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(message, offset)?.1 + 4;
+    }
+
+    let mut answers = vec![];
+    for _ in 0..answer_count {
+        let (_, next) = read_name(message, offset)?;
+        let header = message
+            .get(next..next + 10)
+            .ok_or_else(|| "truncated resource record".to_string())?;
+        let rtype = u16::from_be_bytes([header[0], header[1]]);
+        let length = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let start = next + 10;
+        let rdata = message
+            .get(start..start + length)
+            .ok_or_else(|| "truncated record data".to_string())?;
+
+        if rtype == qtype {
+            answers.push(decode_rdata(message, rtype, start, rdata)?);
+        }
+        offset = start + length;
+    }
+
+    Ok((rcode, answers))
+}
+
+fn decode_rdata(message: &[u8], rtype: u16, start: usize, rdata: &[u8]) -> Result<String, String> {
+    match rtype {
+        1 => <[u8; 4]>::try_from(rdata)
+            .map(|octets| Ipv4Addr::from(octets).to_string())
+            .map_err(|_| "invalid A record".to_string()),
+        28 => <[u8; 16]>::try_from(rdata)
+            .map(|octets| Ipv6Addr::from(octets).to_string())
+            .map_err(|_| "invalid AAAA record".to_string()),
+        5 => read_name(message, start).map(|(name, _)| name),
+        15 => {
+            let preference = rdata
+                .get(..2)
+                .map(|p| u16::from_be_bytes([p[0], p[1]]))
+                .ok_or_else(|| "invalid MX record".to_string())?;
+            read_name(message, start + 2)
+                .map(|(exchange, _)| format!("{} {}", preference, exchange))
+        }
+        16 => {
+            let mut text = String::new();
+            let mut position = 0;
+            while let Some(&length) = rdata.get(position) {
+                let chunk = rdata
+                    .get(position + 1..position + 1 + length as usize)
+                    .ok_or_else(|| "invalid TXT record".to_string())?;
+                text.push_str(&String::from_utf8_lossy(chunk));
+                position += 1 + length as usize;
+            }
+            Ok(text)
+        }
+        other => Ok(format!("TYPE{}", other)),
+    }
+}
+
+/// Lit un nom de domaine en suivant les pointeurs de compression.
+fn read_name(message: &[u8], mut offset: usize) -> Result<(String, usize), String> {
+    let mut labels = vec![];
+    let mut end = None;
+    for _ in 0..128 {
+        let length = *message
+            .get(offset)
+            .ok_or_else(|| "truncated domain name".to_string())?;
+        match length {
+            0 => {
+                let name = if labels.is_empty() {
+                    ".".to_string()
+                } else {
+                    labels.join(".")
+                };
+                return Ok((name, end.unwrap_or(offset + 1)));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                let low = *message
+                    .get(offset + 1)
+                    .ok_or_else(|| "truncated compression pointer".to_string())?;
+                end.get_or_insert(offset + 2);
+                offset = (((l & 0x3F) as usize) << 8) | low as usize;
+            }
+            l => {
+                let label = message
+                    .get(offset + 1..offset + 1 + l as usize)
+                    .ok_or_else(|| "truncated label".to_string())?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + l as usize;
+            }
+        }
+    }
+    Err("too many compression pointers".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// Serveur DNS minimal répondant une seule fois avec le code et les enregistrements fournis.
+    fn stub_server(rcode: u8, records: Vec<(u16, Vec<u8>)>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 512];
+            let (size, client) = socket.recv_from(&mut buffer).unwrap();
+            let query = &buffer[..size];
+
+            let mut response = query[..2].to_vec();
+            response.extend_from_slice(&[0x81, 0x80 | rcode, 0x00, 0x01]);
+            response.extend_from_slice(&(records.len() as u16).to_be_bytes());
+            response.extend_from_slice(&[0, 0, 0, 0]);
+            response.extend_from_slice(&query[12..]);
+            for (rtype, rdata) in records {
+                // Pointeur vers le nom de la question (offset 12).
+                response.extend_from_slice(&[0xC0, 0x0C]);
+                response.extend_from_slice(&rtype.to_be_bytes());
+                response.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x01, 0x2C]);
+                response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+                response.extend_from_slice(&rdata);
+            }
+            socket.send_to(&response, client).unwrap();
+        });
+        address
+    }
+
+    fn dns_check(resolver: SocketAddr, record_type: &str, expected: &[&str]) -> DnsCheck {
+        DnsCheck {
+            name: "test".to_string(),
+            query: "intranet.example.com".to_string(),
+            record_type: record_type.to_string(),
+            resolver: Some(resolver.to_string()),
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+            timeout_ms: 1000,
+            warning_latency_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_a_record_matches_expected() {
+        let resolver = stub_server(0, vec![(1, vec![10, 0, 0, 5]), (1, vec![10, 0, 0, 6])]);
+        let status = run_dns_check(&dns_check(resolver, "A", &["10.0.0.6", "10.0.0.5"]));
+        assert_eq!(status.rcode, "NOERROR");
+        assert_eq!(status.answers, vec!["10.0.0.5", "10.0.0.6"]);
+        assert!(status.latency_ms.is_some());
+        assert_eq!(status.state, CheckState::Ok);
+    }
+
+    #[test]
+    fn test_unexpected_answer_is_critical() {
+        let resolver = stub_server(0, vec![(1, vec![192, 0, 2, 1])]);
+        let status = run_dns_check(&dns_check(resolver, "A", &["10.0.0.5"]));
+        assert_eq!(status.state, CheckState::Critical);
+    }
+
+    #[test]
+    fn test_mx_record_with_compressed_name() {
+        let mut rdata = vec![0x00, 0x0A, 4];
+        rdata.extend_from_slice(b"mail");
+        rdata.extend_from_slice(&[0xC0, 0x0C]);
+        let resolver = stub_server(0, vec![(15, rdata)]);
+        let status = run_dns_check(&dns_check(
+            resolver,
+            "MX",
+            &["10 mail.intranet.example.com."],
+        ));
+        assert_eq!(status.answers, vec!["10 mail.intranet.example.com"]);
+        assert_eq!(status.state, CheckState::Ok);
+    }
+
+    #[test]
+    fn test_foreign_packets_do_not_extend_the_timeout() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 512];
+            let (_, client) = socket.recv_from(&mut buffer).unwrap();
+            // Réponses à un autre identifiant, plus rapprochées que le délai de la sonde.
+            let foreign = [!buffer[0], buffer[1], 0x81, 0x80];
+            for _ in 0..100 {
+                let _ = socket.send_to(&foreign, client);
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        let mut check = dns_check(resolver, "A", &[]);
+        check.timeout_ms = 200;
+        let started = Instant::now();
+        let status = run_dns_check(&check);
+        assert_eq!(status.error.as_deref(), Some("timeout after 200 ms"));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_nxdomain_and_servfail_are_distinct() {
+        let nxdomain = run_dns_check(&dns_check(stub_server(3, vec![]), "A", &[]));
+        assert_eq!(nxdomain.rcode, "NXDOMAIN");
+        assert_eq!(nxdomain.state, CheckState::Critical);
+
+        let servfail = run_dns_check(&dns_check(stub_server(2, vec![]), "AAAA", &[]));
+        assert_eq!(servfail.rcode, "SERVFAIL");
+        assert_eq!(servfail.state, CheckState::Critical);
+    }
+}
@@ -1,37 +1,627 @@
-use bollard::{container::ListContainersOptions, Docker};
-use crate::models::templates::ContainerStatus;
-use log::error;
-
-pub async fn get_containers() -> Vec<ContainerStatus> {
-    match Docker::connect_with_local_defaults() {
-        Ok(docker) => match docker
-            .list_containers(Some(ListContainersOptions::<String> {
-                all: true,
-                ..Default::default()
-            }))
-            .await
-        {
-            Ok(containers) => containers
-                .into_iter()
-                .map(|c| ContainerStatus {
-                    image: c.image.unwrap_or_default(),
-                    state: c.state.unwrap_or_default(),
-                })
-                .collect(),
-            Err(_) => {
-                error!(
-                    "{}",
-                    crate::models::errors::SystemError::DockerListContainersFailed.message()
-                );
-                vec![]
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{ContainerStats, ContainerStatus},
+};
+use crate::services::docker_events::DockerInventory;
+use bollard::{
+    container::{ListContainersOptions, MemoryStatsStats, Stats, StatsOptions},
+    models::{
+        ContainerInspectResponse, ContainerSummary, HealthStatusEnum, Port, RestartPolicyNameEnum,
+    },
+    Docker,
+};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use futures_util::{future::join_all, StreamExt};
+use log::{error, warn};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Politique de détection des boucles de redémarrage.
+#[derive(Debug, Clone, Copy)]
+pub struct CrashLoopPolicy {
+    pub restarts: i64,
+    pub window: Duration,
+}
+
+impl CrashLoopPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            restarts: config.docker_crash_loop_restarts,
+            window: Duration::from_secs(config.docker_crash_loop_window_secs),
+        }
+    }
+}
+
+/// Relevés `(date, RestartCount)` d'un conteneur.
+type RestartSamples = VecDeque<(Instant, i64)>;
+
+/// Historique des `RestartCount` observés par conteneur, indexé par `(endpoint, ID)`.
+static RESTART_HISTORY: LazyLock<Mutex<HashMap<(String, String), RestartSamples>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Liste complète des conteneurs, inspection comprise (sans statistiques).
+pub async fn fetch_containers(
+    docker: &Docker,
+    endpoint: &str,
+    policy: CrashLoopPolicy,
+) -> Result<Vec<ContainerStatus>, SystemError> {
+    let containers = docker
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            ..Default::default()
+        }))
+        .await
+        .map_err(|_| {
+            error!("{}", SystemError::DockerListContainersFailed.message());
+            SystemError::DockerListContainersFailed
+        })?;
+
+    Ok(join_all(
+        containers
+            .into_iter()
+            .map(|summary| container_status(docker, endpoint, summary, policy)),
+    )
+    .await)
+}
+
+/// Relit un seul conteneur ; `None` s'il n'existe plus.
+pub async fn fetch_container(
+    docker: &Docker,
+    endpoint: &str,
+    id: &str,
+    policy: CrashLoopPolicy,
+) -> Result<Option<ContainerStatus>, SystemError> {
+    let containers = docker
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            filters: HashMap::from([("id".to_string(), vec![id.to_string()])]),
+            ..Default::default()
+        }))
+        .await
+        .map_err(|_| {
+            error!("{}", SystemError::DockerListContainersFailed.message());
+            SystemError::DockerListContainersFailed
+        })?;
+
+    match containers.into_iter().next() {
+        Some(summary) => Ok(Some(
+            container_status(docker, endpoint, summary, policy).await,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Ajoute les statistiques des conteneurs en cours d'exécution, via l'endpoint de chacun.
+pub async fn attach_stats(inventory: &DockerInventory, containers: &mut [ContainerStatus]) {
+    let mut clients: HashMap<String, Option<Docker>> = HashMap::new();
+    for container in containers.iter().filter(|c| c.state == "running") {
+        if !clients.contains_key(&container.endpoint) {
+            clients.insert(
+                container.endpoint.clone(),
+                inventory.docker(&container.endpoint).ok(),
+            );
+        }
+    }
+
+    let stats = join_all(containers.iter().map(|container| {
+        let docker = clients.get(&container.endpoint).and_then(Option::as_ref);
+        async move {
+            match docker {
+                Some(docker) if container.state == "running" => {
+                    get_container_stats(docker, &container.id).await
+                }
+                _ => None,
             }
-        },
-        Err(_) => {
-            error!(
-                "{}",
-                crate::models::errors::SystemError::DockerConnectionFailed.message()
+        }
+    }))
+    .await;
+
+    for (container, stats) in containers.iter_mut().zip(stats) {
+        container.stats = stats;
+    }
+}
+
+/// Complète le résumé de `list_containers` avec l'inspection.
+async fn container_status(
+    docker: &Docker,
+    endpoint: &str,
+    summary: ContainerSummary,
+    policy: CrashLoopPolicy,
+) -> ContainerStatus {
+    let id = summary.id.unwrap_or_default();
+    let state = summary.state.unwrap_or_default();
+
+    let inspect = match docker.inspect_container(&id, None).await {
+        Ok(inspect) => Some(inspect),
+        Err(e) => {
+            warn!(
+                "{} ({}): {}",
+                SystemError::DockerInspectFailed.message(),
+                id,
+                e
             );
-            vec![]
+            None
+        }
+    };
+    let health = inspect
+        .as_ref()
+        .map(ContainerHealth::from)
+        .unwrap_or_default();
+    let restart_count = inspect
+        .as_ref()
+        .and_then(|inspect| inspect.restart_count)
+        .unwrap_or(0);
+    let recent_restarts = record_restart_count(endpoint, &id, restart_count, policy.window);
+    let crash_loop = state == "restarting" || recent_restarts >= policy.restarts;
+
+    let labels = summary.labels.unwrap_or_default();
+
+    let mut container = ContainerStatus {
+        endpoint: endpoint.to_string(),
+        id: id.chars().take(12).collect(),
+        name: summary
+            .names
+            .and_then(|names| names.into_iter().next())
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default(),
+        image: summary.image.unwrap_or_default(),
+        state,
+        status: summary.status.unwrap_or_default(),
+        created: summary
+            .created
+            .and_then(|ts| Local.timestamp_opt(ts, 0).single())
+            .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default(),
+        ports: summary
+            .ports
+            .unwrap_or_default()
+            .iter()
+            .map(format_port)
+            .collect(),
+        restart_count,
+        recent_restarts,
+        crash_loop,
+        health: health.status,
+        health_output: health.output,
+        exit_code: health.exit_code,
+        oom_killed: health.oom_killed,
+        restart_policy: health.restart_policy,
+        started_at: health.started_at,
+        finished_at: health.finished_at,
+        stats: None,
+        compose_project: labels.get("com.docker.compose.project").cloned(),
+        compose_service: labels.get("com.docker.compose.service").cloned(),
+    };
+    container.status = describe_status(&container, Utc::now());
+    container
+}
+
+/// Texte de statut façon `docker ps` (« Up 2 hours (healthy) », « Exited (1) 5 minutes ago »),
+/// recalculé à chaque affichage pour rester à jour.
+pub fn describe_status(container: &ContainerStatus, now: DateTime<Utc>) -> String {
+    let since = |timestamp: Option<i64>| {
+        timestamp
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|date| human_duration((now - date).num_seconds()))
+    };
+    let health = container
+        .health
+        .as_ref()
+        .map(|health| format!(" ({})", health))
+        .unwrap_or_default();
+    let exit_code = container.exit_code.unwrap_or_default();
+
+    match (container.state.as_str(), since(container.started_at), since(container.finished_at)) {
+        ("running", Some(up), _) => format!("Up {}{}", up, health),
+        ("paused", Some(up), _) => format!("Up {} (Paused)", up),
+        ("restarting", _, Some(ago)) => format!("Restarting ({}) {} ago", exit_code, ago),
+        ("exited", _, Some(ago)) => format!("Exited ({}) {} ago", exit_code, ago),
+        ("created", _, _) => "Created".to_string(),
+        ("dead", _, _) => "Dead".to_string(),
+        _ => container.status.clone(),
+    }
+}
+
+/// Durée à la façon de Docker : « 5 minutes », « About an hour », « 3 days ».
+pub fn human_duration(seconds: i64) -> String {
+    match seconds.max(0) {
+        0 => "Less than a second".to_string(),
+        s if s < 60 => format!("{} seconds", s),
+        s if s < 120 => "About a minute".to_string(),
+        s if s < 3600 => format!("{} minutes", s / 60),
+        s if s < 7200 => "About an hour".to_string(),
+        s if s < 48 * 3600 => format!("{} hours", s / 3600),
+        s => format!("{} days", s / 86400),
+    }
+}
+
+/// Date RFC 3339 renvoyée par Docker ; la date nulle (`0001-01-01`) signifie « jamais ».
+fn parse_docker_date(date: Option<&String>) -> Option<i64> {
+    date.and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.timestamp())
+        .filter(|ts| *ts > 0)
+}
+
+/// État de santé et de sortie extrait de l'inspection d'un conteneur.
+#[derive(Default)]
+struct ContainerHealth {
+    status: Option<String>,
+    output: Option<String>,
+    exit_code: Option<i64>,
+    oom_killed: bool,
+    restart_policy: String,
+    started_at: Option<i64>,
+    finished_at: Option<i64>,
+}
+
+impl From<&ContainerInspectResponse> for ContainerHealth {
+    fn from(inspect: &ContainerInspectResponse) -> Self {
+        let state = inspect.state.as_ref();
+        let health = state.and_then(|state| state.health.as_ref());
+        Self {
+            status: health
+                .and_then(|health| health.status)
+                .filter(|status| {
+                    !matches!(status, HealthStatusEnum::EMPTY | HealthStatusEnum::NONE)
+                })
+                .map(|status| status.to_string()),
+            output: health
+                .and_then(|health| health.log.as_ref())
+                .and_then(|log| log.last())
+                .and_then(|probe| probe.output.as_ref())
+                .map(|output| output.trim().to_string()),
+            exit_code: state.and_then(|state| state.exit_code),
+            oom_killed: state.and_then(|state| state.oom_killed).unwrap_or(false),
+            restart_policy: inspect
+                .host_config
+                .as_ref()
+                .and_then(|host_config| host_config.restart_policy.as_ref())
+                .and_then(|policy| policy.name)
+                .unwrap_or(RestartPolicyNameEnum::NO)
+                .to_string(),
+            started_at: parse_docker_date(state.and_then(|state| state.started_at.as_ref())),
+            finished_at: parse_docker_date(state.and_then(|state| state.finished_at.as_ref())),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Enregistre le `RestartCount` courant et renvoie le nombre de redémarrages dans la fenêtre.
+fn record_restart_count(endpoint: &str, id: &str, restart_count: i64, window: Duration) -> i64 {
+    let mut history = RESTART_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let samples = history
+        .entry((endpoint.to_string(), id.to_string()))
+        .or_default();
+    restarts_in_window(samples, Instant::now(), restart_count, window)
+}
+
+/// Oublie l'historique des conteneurs que `keep` (appelée avec l'endpoint et l'ID
+/// complet) écarte, c'est-à-dire ceux qui ne figurent plus dans l'inventaire.
+pub fn retain_restart_history(keep: impl Fn(&str, &str) -> bool) {
+    RESTART_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .retain(|(endpoint, id), _| keep(endpoint, id));
+}
+
+fn restarts_in_window(
+    samples: &mut RestartSamples,
+    now: Instant,
+    restart_count: i64,
+    window: Duration,
+) -> i64 {
+    samples.push_back((now, restart_count));
+    // On conserve le dernier relevé antérieur à la fenêtre comme référence.
+    while samples
+        .get(1)
+        .is_some_and(|(seen, _)| now.duration_since(*seen) > window)
+    {
+        samples.pop_front();
+    }
+    samples
+        .front()
+        .map(|(_, oldest)| restart_count.saturating_sub(*oldest).max(0))
+        .unwrap_or(0)
+}
+
+/// Alertes Docker : conteneur `unhealthy`, boucle de redémarrage, arrêt par OOM ou sortie en erreur.
+pub fn container_alerts(containers: &[ContainerStatus]) -> Vec<Alert> {
+    let mut alerts = vec![];
+    for container in containers {
+        let source = format!("docker:{}", container.name);
+        if container.health.as_deref() == Some("unhealthy") {
+            alerts.push(Alert::new(
+                &source,
+                CheckState::Critical,
+                format!(
+                    "container is unhealthy: {}",
+                    container
+                        .health_output
+                        .as_deref()
+                        .unwrap_or("no probe output")
+                ),
+            ));
+        }
+        if container.crash_loop {
+            alerts.push(Alert::new(
+                &source,
+                CheckState::Critical,
+                format!(
+                    "crash loop: {} restarts recently ({} total), state {}",
+                    container.recent_restarts, container.restart_count, container.state
+                ),
+            ));
+        }
+        if container.oom_killed {
+            alerts.push(Alert::new(
+                &source,
+                CheckState::Critical,
+                format!(
+                    "killed by the OOM killer (exit code {})",
+                    container.exit_code.unwrap_or_default()
+                ),
+            ));
+        } else if container.state == "exited"
+            && container.exit_code.is_some_and(|code| code != 0)
+            && !stopped_on_purpose(container)
+        {
+            alerts.push(Alert::new(
+                &source,
+                CheckState::Warning,
+                format!(
+                    "exited with code {}",
+                    container.exit_code.unwrap_or_default()
+                ),
+            ));
+        }
+    }
+    alerts
+}
+
+/// Arrêt volontaire (`docker stop`, SIGKILL ou SIGTERM) d'un conteneur que sa politique
+/// de redémarrage laisse arrêté : ce n'est pas une panne.
+fn stopped_on_purpose(container: &ContainerStatus) -> bool {
+    matches!(container.exit_code, Some(137 | 143))
+        && !container.oom_killed
+        && matches!(
+            container.restart_policy.as_str(),
+            "" | "no" | "unless-stopped"
+        )
+}
+
+/// Statistiques ponctuelles d'un conteneur (un seul échantillon, `precpu` inclus).
+async fn get_container_stats(docker: &Docker, id: &str) -> Option<ContainerStats> {
+    let mut stream = docker.stats(
+        id,
+        Some(StatsOptions {
+            stream: false,
+            one_shot: false,
+        }),
+    );
+
+    match stream.next().await {
+        Some(Ok(stats)) => Some(compute_stats(&stats)),
+        Some(Err(e)) => {
+            warn!(
+                "{} ({}): {}",
+                SystemError::DockerStatsFailed.message(),
+                id,
+                e
+            );
+            None
+        }
+        None => None,
+    }
+}
+
+/// Reprend les calculs de `docker stats` : %CPU relatif à l'hôte, mémoire hors cache.
+fn compute_stats(stats: &Stats) -> ContainerStats {
+    let cpu_delta = stats
+        .cpu_stats
+        .cpu_usage
+        .total_usage
+        .saturating_sub(stats.precpu_stats.cpu_usage.total_usage) as f64;
+    let system_delta = stats
+        .cpu_stats
+        .system_cpu_usage
+        .unwrap_or(0)
+        .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or(0))
+        as f64;
+    let online_cpus = stats.cpu_stats.online_cpus.unwrap_or_else(|| {
+        stats
+            .cpu_stats
+            .cpu_usage
+            .percpu_usage
+            .as_ref()
+            .map(|cpus| cpus.len() as u64)
+            .unwrap_or(1)
+    }) as f64;
+    let cpu_percent = if system_delta > 0.0 {
+        cpu_delta / system_delta * online_cpus * 100.0
+    } else {
+        0.0
+    };
+
+    let inactive_file = match stats.memory_stats.stats {
+        Some(MemoryStatsStats::V1(v1)) => v1.total_inactive_file,
+        Some(MemoryStatsStats::V2(v2)) => v2.inactive_file,
+        None => 0,
+    };
+    let memory_usage = stats
+        .memory_stats
+        .usage
+        .unwrap_or(0)
+        .saturating_sub(inactive_file);
+
+    let (network_rx, network_tx) = stats
+        .networks
+        .as_ref()
+        .map(|networks| {
+            networks.values().fold((0, 0), |(rx, tx), network| {
+                (rx + network.rx_bytes, tx + network.tx_bytes)
+            })
+        })
+        .unwrap_or((0, 0));
+
+    let (block_read, block_write) = stats
+        .blkio_stats
+        .io_service_bytes_recursive
+        .as_ref()
+        .map(|entries| {
+            entries.iter().fold((0, 0), |(read, write), entry| {
+                match entry.op.to_lowercase().as_str() {
+                    "read" => (read + entry.value, write),
+                    "write" => (read, write + entry.value),
+                    _ => (read, write),
+                }
+            })
+        })
+        .unwrap_or((0, 0));
+
+    ContainerStats {
+        cpu_percent,
+        memory_usage,
+        memory_limit: stats.memory_stats.limit.unwrap_or(0),
+        network_rx,
+        network_tx,
+        block_read,
+        block_write,
+    }
+}
+
+/// Formate un port à la manière de `docker ps` : `0.0.0.0:8080->80/tcp`.
+fn format_port(port: &Port) -> String {
+    let protocol = port
+        .typ
+        .map(|typ| typ.to_string())
+        .unwrap_or_else(|| "tcp".to_string());
+    match (&port.ip, port.public_port) {
+        (Some(ip), Some(public)) => {
+            format!("{}:{}->{}/{}", ip, public, port.private_port, protocol)
+        }
+        (None, Some(public)) => format!("{}->{}/{}", public, port.private_port, protocol),
+        _ => format!("{}/{}", port.private_port, protocol),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::PortTypeEnum;
+
+    #[test]
+    fn test_restarts_in_window() {
+        let window = Duration::from_secs(300);
+        let start = Instant::now();
+        let mut samples = VecDeque::new();
+
+        assert_eq!(restarts_in_window(&mut samples, start, 2, window), 0);
+        let later = start + Duration::from_secs(60);
+        assert_eq!(restarts_in_window(&mut samples, later, 5, window), 3);
+
+        // Seul le dernier relevé antérieur à la fenêtre est conservé comme référence.
+        let much_later = start + Duration::from_secs(400);
+        assert_eq!(restarts_in_window(&mut samples, much_later, 6, window), 1);
+        assert_eq!(samples.len(), 2);
+    }
+
+    #[test]
+    fn test_restart_history_is_pruned() {
+        let kept = "0123456789ab".repeat(5) + "cdef";
+        let removed = "ba9876543210".repeat(5) + "fedc";
+        let window = Duration::from_secs(600);
+        record_restart_count("local", &kept, 1, window);
+        record_restart_count("local", &removed, 1, window);
+        // Même ID sur un autre endpoint : historique distinct
+        record_restart_count("remote", &removed, 1, window);
+
+        retain_restart_history(|endpoint, id| endpoint == "remote" || id != removed);
+        let history = RESTART_HISTORY.lock().unwrap();
+        assert!(history.contains_key(&("local".to_string(), kept)));
+        assert!(!history.contains_key(&("local".to_string(), removed.clone())));
+        assert!(history.contains_key(&("remote".to_string(), removed)));
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let mut stats: Stats = serde_json::from_str(include_str!(
+            "../../tests/fixtures/docker/stats_cgroup_v2.json"
+        ))
+        .unwrap();
+        let computed = compute_stats(&stats);
+        assert_eq!(computed.cpu_percent, 40.0);
+        // usage - inactive_file
+        assert_eq!(computed.memory_usage, 100_663_296);
+        assert_eq!(computed.memory_limit, 2_147_483_648);
+        assert_eq!((computed.network_rx, computed.network_tx), (1500, 2100));
+        assert_eq!((computed.block_read, computed.block_write), (5120, 8192));
+
+        // Sans online_cpus, le nombre de CPU est celui de percpu_usage
+        stats.cpu_stats.online_cpus = None;
+        stats.cpu_stats.cpu_usage.percpu_usage = Some(vec![0, 0]);
+        assert_eq!(compute_stats(&stats).cpu_percent, 20.0);
+        stats.precpu_stats.system_cpu_usage = stats.cpu_stats.system_cpu_usage;
+        assert_eq!(compute_stats(&stats).cpu_percent, 0.0);
+    }
+
+    #[test]
+    fn test_format_port() {
+        let port = |ip: Option<&str>, public_port, typ| Port {
+            ip: ip.map(str::to_string),
+            private_port: 80,
+            public_port,
+            typ,
+        };
+        assert_eq!(
+            format_port(&port(Some("0.0.0.0"), Some(8080), Some(PortTypeEnum::TCP))),
+            "0.0.0.0:8080->80/tcp"
+        );
+        assert_eq!(
+            format_port(&port(None, Some(8080), Some(PortTypeEnum::UDP))),
+            "8080->80/udp"
+        );
+        assert_eq!(format_port(&port(None, None, None)), "80/tcp");
+    }
+
+    #[test]
+    fn test_describe_status() {
+        let now = Utc::now();
+        let mut container = ContainerStatus {
+            state: "running".to_string(),
+            health: Some("healthy".to_string()),
+            started_at: Some(now.timestamp() - 3 * 3600),
+            ..Default::default()
+        };
+        assert_eq!(describe_status(&container, now), "Up 3 hours (healthy)");
+
+        container.state = "exited".to_string();
+        container.exit_code = Some(137);
+        container.finished_at = Some(now.timestamp() - 300);
+        assert_eq!(describe_status(&container, now), "Exited (137) 5 minutes ago");
+    }
+
+    #[test]
+    fn test_container_alerts_skip_deliberate_stop() {
+        let mut container = ContainerStatus {
+            name: "web".to_string(),
+            state: "exited".to_string(),
+            exit_code: Some(143),
+            restart_policy: "unless-stopped".to_string(),
+            ..Default::default()
+        };
+        assert!(container_alerts(std::slice::from_ref(&container)).is_empty());
+
+        // Un conteneur censé tourner, ou une vraie erreur, reste signalé
+        container.restart_policy = "always".to_string();
+        assert_eq!(container_alerts(std::slice::from_ref(&container)).len(), 1);
+        container.restart_policy = "no".to_string();
+        container.exit_code = Some(1);
+        assert_eq!(container_alerts(std::slice::from_ref(&container)).len(), 1);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::models::errors::SystemError;
+use bollard::{
+    container::{
+        Config as ContainerCreateConfig, CreateContainerOptions, NetworkingConfig,
+        RemoveContainerOptions, RenameContainerOptions, RestartContainerOptions,
+        StartContainerOptions, StopContainerOptions,
+    },
+    errors::Error as DockerError,
+    image::CreateImageOptions,
+    models::{
+        ContainerInspectResponse, EndpointSettings, Mount, MountPointTypeEnum, MountTypeEnum,
+    },
+    network::ConnectNetworkOptions,
+    Docker,
+};
+use futures_util::StreamExt;
+use log::{error, info, warn};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+/// Délai laissé au conteneur pour s'arrêter proprement avant SIGKILL.
+const STOP_TIMEOUT_SECS: i64 = 10;
+
+/// Action de cycle de vie déclenchée depuis le tableau de bord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerAction {
+    Start,
+    Stop,
+    Restart,
+    /// `docker pull` de l'image puis recréation du conteneur avec la même configuration.
+    Recreate,
+}
+
+impl fmt::Display for ContainerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+            Self::Recreate => "recreate",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+/// Exécute l'action sur le conteneur ; renvoie l'ID du conteneur (nouveau en cas de recréation).
+pub async fn run_container_action(
+    docker: &Docker,
+    id: &str,
+    action: ContainerAction,
+) -> Result<String, SystemError> {
+    let result = match action {
+        ContainerAction::Start => docker
+            .start_container(id, None::<StartContainerOptions<String>>)
+            .await
+            .map(|_| id.to_string()),
+        ContainerAction::Stop => docker
+            .stop_container(
+                id,
+                Some(StopContainerOptions {
+                    t: STOP_TIMEOUT_SECS,
+                }),
+            )
+            .await
+            .map(|_| id.to_string()),
+        ContainerAction::Restart => docker
+            .restart_container(
+                id,
+                Some(RestartContainerOptions {
+                    t: STOP_TIMEOUT_SECS as isize,
+                }),
+            )
+            .await
+            .map(|_| id.to_string()),
+        ContainerAction::Recreate => recreate_container(docker, id).await,
+    };
+
+    result.map_err(|e| match e {
+        DockerError::DockerResponseServerError {
+            status_code: 404, ..
+        } => SystemError::DockerContainerNotFound,
+        e => {
+            error!(
+                "{} ({} {}): {}",
+                SystemError::DockerActionFailed.message(),
+                action,
+                id,
+                e
+            );
+            SystemError::DockerActionFailed
+        }
+    })
+}
+
+/// Télécharge la dernière version de l'image puis remplace le conteneur par un
+/// conteneur de même nom, même configuration, mêmes réseaux et mêmes volumes.
+///
+/// L'ancien conteneur est renommé de côté et n'est supprimé (sans ses volumes)
+/// qu'une fois le nouveau démarré ; en cas d'échec, il reprend son nom et son état.
+async fn recreate_container(docker: &Docker, id: &str) -> Result<String, DockerError> {
+    let inspect = docker.inspect_container(id, None).await?;
+    let name = inspect
+        .name
+        .as_deref()
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string();
+    let was_running = inspect
+        .state
+        .as_ref()
+        .and_then(|state| state.running)
+        .unwrap_or(false);
+    let (config, networks) = recreate_config(&inspect);
+    let image = config.image.clone().unwrap_or_default();
+
+    info!("Pulling image {} to recreate container {}", image, name);
+    let mut pull = docker.create_image(
+        Some(CreateImageOptions {
+            from_image: image.clone(),
+            ..Default::default()
+        }),
+        None,
+        None,
+    );
+    while let Some(progress) = pull.next().await {
+        progress?;
+    }
+
+    docker
+        .stop_container(
+            id,
+            Some(StopContainerOptions {
+                t: STOP_TIMEOUT_SECS,
+            }),
+        )
+        .await
+        .or_else(|e| match e {
+            // 304 : conteneur déjà arrêté
+            DockerError::DockerResponseServerError {
+                status_code: 304, ..
+            } => Ok(()),
+            e => Err(e),
+        })?;
+    let aside = format!(
+        "{}-recreate-{}",
+        name,
+        id.chars().take(12).collect::<String>()
+    );
+    if let Err(e) = docker
+        .rename_container(
+            id,
+            RenameContainerOptions {
+                name: aside.as_str(),
+            },
+        )
+        .await
+    {
+        restore_container(docker, id, None, was_running).await;
+        return Err(e);
+    }
+
+    match create_and_start(docker, &name, config, networks).await {
+        Ok(new_id) => {
+            docker
+                .remove_container(
+                    id,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .unwrap_or_else(|e| warn!("Failed to remove old container {}: {}", aside, e));
+            info!("Container {} recreated as {}", name, new_id);
+            Ok(new_id)
+        }
+        Err(e) => {
+            error!("Failed to recreate container {}, restoring it: {}", name, e);
+            restore_container(docker, id, Some(&name), was_running).await;
+            Err(e)
+        }
+    }
+}
+
+/// Crée le nouveau conteneur, le connecte aux réseaux supplémentaires puis le démarre ;
+/// en cas d'échec, il est supprimé pour libérer le nom.
+async fn create_and_start(
+    docker: &Docker,
+    name: &str,
+    config: ContainerCreateConfig<String>,
+    networks: Vec<(String, EndpointSettings)>,
+) -> Result<String, DockerError> {
+    let created = docker
+        .create_container(
+            Some(CreateContainerOptions {
+                name: name.to_string(),
+                platform: None,
+            }),
+            config,
+        )
+        .await?;
+    let started = async {
+        for (network, endpoint_config) in networks {
+            docker
+                .connect_network(
+                    &network,
+                    ConnectNetworkOptions {
+                        container: created.id.as_str(),
+                        endpoint_config,
+                    },
+                )
+                .await?;
+        }
+        docker
+            .start_container(&created.id, None::<StartContainerOptions<String>>)
+            .await
+    };
+    if let Err(e) = started.await {
+        docker
+            .remove_container(
+                &created.id,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap_or_else(|e| warn!("Failed to remove container {}: {}", created.id, e));
+        return Err(e);
+    }
+    Ok(created.id)
+}
+
+/// Rend son nom à l'ancien conteneur s'il a été renommé, et le redémarre s'il tournait.
+async fn restore_container(docker: &Docker, id: &str, name: Option<&str>, was_running: bool) {
+    if let Some(name) = name {
+        if let Err(e) = docker
+            .rename_container(id, RenameContainerOptions { name })
+            .await
+        {
+            error!("Failed to restore the name of container {}: {}", name, e);
+        }
+    }
+    if was_running {
+        if let Err(e) = docker
+            .start_container(id, None::<StartContainerOptions<String>>)
+            .await
+        {
+            error!("Failed to restart container {}: {}", id, e);
+        }
+    }
+}
+
+/// Configuration de création reconstruite depuis l'inspection, et réseaux à connecter
+/// après la création. Seuls les réglages déclaratifs des réseaux sont repris (alias,
+/// IPAM, liens), pas leur état courant.
+///
+/// Avant l'API 1.44 (et avec Podman), la création n'accepte qu'un réseau : le réseau
+/// principal (`NetworkMode`, à défaut le premier par ordre alphabétique) est donné à
+/// la création, les autres sont connectés avant le démarrage.
+///
+/// Les volumes absents de `Binds` et `Mounts` (volumes anonymes, `VOLUME` de l'image)
+/// sont rattachés explicitement, sans quoi le nouveau conteneur partirait de volumes vides.
+fn recreate_config(
+    inspect: &ContainerInspectResponse,
+) -> (
+    ContainerCreateConfig<String>,
+    Vec<(String, EndpointSettings)>,
+) {
+    let mut config: ContainerCreateConfig<String> =
+        inspect.config.clone().unwrap_or_default().into();
+    // Docker prend les 12 premiers caractères de l'ID comme nom d'hôte par défaut.
+    let id = inspect.id.as_deref().unwrap_or_default();
+    let short_id: String = id.chars().take(12).collect();
+    if config
+        .hostname
+        .as_deref()
+        .is_some_and(|hostname| !id.is_empty() && id.starts_with(hostname))
+    {
+        config.hostname = None;
+    }
+
+    let mut host_config = inspect.host_config.clone().unwrap_or_default();
+    let mut declared: Vec<String> = host_config
+        .binds
+        .iter()
+        .flatten()
+        .filter_map(|bind| bind.split(':').nth(1).map(str::to_string))
+        .collect();
+    declared.extend(
+        host_config
+            .mounts
+            .iter()
+            .flatten()
+            .filter_map(|mount| mount.target.clone()),
+    );
+    let volumes: Vec<Mount> = inspect
+        .mounts
+        .iter()
+        .flatten()
+        .filter(|mount| mount.typ == Some(MountPointTypeEnum::VOLUME))
+        .filter(|mount| {
+            mount
+                .destination
+                .as_ref()
+                .is_some_and(|destination| !declared.contains(destination))
+        })
+        .map(|mount| Mount {
+            target: mount.destination.clone(),
+            source: mount.name.clone(),
+            typ: Some(MountTypeEnum::VOLUME),
+            read_only: mount.rw.map(|rw| !rw),
+            ..Default::default()
+        })
+        .collect();
+    if !volumes.is_empty() {
+        host_config
+            .mounts
+            .get_or_insert_with(Vec::new)
+            .extend(volumes);
+    }
+    let network_mode = host_config.network_mode.clone();
+    config.host_config = Some(host_config);
+
+    let mut networks: Vec<(String, EndpointSettings)> = inspect
+        .network_settings
+        .as_ref()
+        .and_then(|settings| settings.networks.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(network, endpoint)| {
+            // Docker ajoute l'ID court de l'ancien conteneur aux alias
+            let aliases = endpoint
+                .aliases
+                .map(|aliases| {
+                    aliases
+                        .into_iter()
+                        .filter(|alias| short_id.is_empty() || *alias != short_id)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|aliases| !aliases.is_empty());
+            (
+                network,
+                EndpointSettings {
+                    aliases,
+                    ipam_config: endpoint.ipam_config,
+                    links: endpoint.links,
+                    driver_opts: endpoint.driver_opts,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    networks.sort_by(|(a, _), (b, _)| {
+        let secondary = |network: &String| network_mode.as_ref() != Some(network);
+        (secondary(a), a).cmp(&(secondary(b), b))
+    });
+    if !networks.is_empty() {
+        let primary = networks.remove(0);
+        config.networking_config = Some(NetworkingConfig {
+            endpoints_config: HashMap::from([primary]),
+        });
+    }
+    (config, networks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::{ContainerConfig, HostConfig, MountPoint, NetworkSettings};
+
+    #[test]
+    fn test_recreate_config_keeps_only_declarative_network_settings() {
+        let volume = |name: &str, destination: &str| MountPoint {
+            typ: Some(MountPointTypeEnum::VOLUME),
+            name: Some(name.to_string()),
+            destination: Some(destination.to_string()),
+            rw: Some(true),
+            ..Default::default()
+        };
+        let inspect = ContainerInspectResponse {
+            id: Some("3f2a9c1b7d4e8f6a5b0c".to_string()),
+            config: Some(ContainerConfig {
+                image: Some("nginx:1.27".to_string()),
+                hostname: Some("3f2a9c1b7d4e".to_string()),
+                env: Some(vec!["TZ=Europe/Paris".to_string()]),
+                ..Default::default()
+            }),
+            host_config: Some(HostConfig {
+                binds: Some(vec!["blog_conf:/etc/nginx/conf.d:ro".to_string()]),
+                network_mode: Some("blog_default".to_string()),
+                ..Default::default()
+            }),
+            mounts: Some(vec![
+                volume("blog_conf", "/etc/nginx/conf.d"),
+                volume("8d1e0c4f", "/var/cache/nginx"),
+            ]),
+            network_settings: Some(NetworkSettings {
+                networks: Some(HashMap::from([
+                    (
+                        "blog_default".to_string(),
+                        EndpointSettings {
+                            aliases: Some(vec!["web".to_string(), "3f2a9c1b7d4e".to_string()]),
+                            ip_address: Some("172.18.0.2".to_string()),
+                            endpoint_id: Some("abc".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                    (
+                        "backend".to_string(),
+                        EndpointSettings {
+                            aliases: Some(vec!["3f2a9c1b7d4e".to_string()]),
+                            ip_address: Some("172.19.0.4".to_string()),
+                            ..Default::default()
+                        },
+                    ),
+                ])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let (config, networks) = recreate_config(&inspect);
+        assert_eq!(config.image.as_deref(), Some("nginx:1.27"));
+        assert_eq!(config.env, Some(vec!["TZ=Europe/Paris".to_string()]));
+        assert_eq!(config.hostname, None);
+        let mounts = config.host_config.as_ref().unwrap().mounts.clone().unwrap();
+        assert_eq!(mounts.len(), 1);
+        assert_eq!(mounts[0].source.as_deref(), Some("8d1e0c4f"));
+        assert_eq!(mounts[0].target.as_deref(), Some("/var/cache/nginx"));
+        assert_eq!(mounts[0].read_only, Some(false));
+        // Un seul réseau à la création, le principal ; les autres sont connectés ensuite
+        let endpoints = config.networking_config.unwrap().endpoints_config;
+        assert_eq!(endpoints.len(), 1);
+        let endpoint = &endpoints["blog_default"];
+        assert_eq!(endpoint.aliases, Some(vec!["web".to_string()]));
+        assert_eq!(endpoint.ip_address, None);
+        assert_eq!(endpoint.endpoint_id, None);
+        assert_eq!(networks.len(), 1);
+        assert_eq!(networks[0].0, "backend");
+        assert_eq!(networks[0].1.aliases, None);
+        assert_eq!(networks[0].1.ip_address, None);
+    }
+}
@@ -0,0 +1,544 @@
+use crate::handlers::status::format_size;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{DockerDiskUsage, ImageUsage, PruneReport, VolumeUsage},
+};
+use crate::services::{
+    cache::ReportCache,
+    docker_endpoints::{ContainerEndpoint, ContainerRuntime, EndpointTransport},
+    docker_events::DockerInventory,
+};
+use actix_web::http::uri::Authority;
+use bollard::{
+    container::PruneContainersOptions, image::PruneImagesOptions, models::SystemDataUsageResponse,
+    volume::PruneVolumesOptions, Docker,
+};
+use futures_util::future::join_all;
+use log::{debug, error, info};
+use openssl::ssl::{SslConnector, SslFiletype, SslMethod};
+use serde::Deserialize;
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    os::unix::net::UnixStream,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Délai entre deux vérifications des endpoints connectés : un endpoint qui (re)devient
+/// joignable est relevé sans attendre `docker_df_interval_secs`.
+const ENDPOINT_POLL: Duration = Duration::from_secs(10);
+
+/// Nombre d'images listées dans le rapport, des plus volumineuses aux plus petites.
+const LARGEST_IMAGES: usize = 10;
+
+/// Première version de l'API où `/volumes/prune` épargne les volumes nommés.
+const ANONYMOUS_VOLUME_PRUNE_API: (u32, u32) = (1, 42);
+
+/// Ce que `POST /api/v1/containers/prune` doit nettoyer.
+#[derive(Debug, Deserialize)]
+pub struct PruneQuery {
+    pub endpoint: String,
+    /// Nom de l'endpoint, à ressaisir pour confirmer.
+    pub confirm: Option<String>,
+    /// Images orphelines (`<none>`).
+    #[serde(default = "default_true")]
+    pub images: bool,
+    /// Conteneurs arrêtés.
+    #[serde(default)]
+    pub containers: bool,
+    /// Volumes anonymes inutilisés. Avant l'API 1.42 et sur Podman, `/volumes/prune`
+    /// supprime aussi les volumes nommés : la demande est alors refusée.
+    #[serde(default)]
+    pub volumes: bool,
+    /// Cache de build inutilisé (`docker builder prune`).
+    #[serde(default)]
+    pub build_cache: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Dernier `docker system df` de chaque endpoint, rafraîchi par [`watch_docker_disk`].
+pub type DockerDiskCache = ReportCache<Vec<DockerDiskUsage>>;
+
+/// Relève l'occupation disque toutes les `interval`, ou dès que la liste des endpoints
+/// connectés change ; un intervalle nul désactive le relevé.
+///
+/// `/system/df` calcule la taille de chaque image, conteneur et volume : il n'est
+/// jamais appelé pendant une requête.
+pub async fn watch_docker_disk(
+    cache: Arc<DockerDiskCache>,
+    inventory: Arc<DockerInventory>,
+    interval: Duration,
+) {
+    if interval.is_zero() {
+        info!("Docker disk usage collection disabled");
+        return;
+    }
+    let mut collected: Option<(Instant, Vec<String>)> = None;
+    loop {
+        let connected = connected_endpoints(&inventory);
+        let due = collected
+            .as_ref()
+            .is_none_or(|(at, endpoints)| at.elapsed() >= interval || *endpoints != connected);
+        if due {
+            let usage = docker_disk_usage(&inventory).await;
+            debug!(
+                "Docker disk usage refreshed for {} endpoint(s)",
+                usage.len()
+            );
+            cache.set(usage);
+            collected = Some((Instant::now(), connected));
+        }
+        tokio::time::sleep(ENDPOINT_POLL).await;
+    }
+}
+
+fn connected_endpoints(inventory: &DockerInventory) -> Vec<String> {
+    inventory
+        .endpoints()
+        .into_iter()
+        .filter(|endpoint| endpoint.connected)
+        .map(|endpoint| endpoint.name)
+        .collect()
+}
+
+/// Occupation disque de chaque endpoint connecté (`docker system df`).
+pub async fn docker_disk_usage(inventory: &DockerInventory) -> Vec<DockerDiskUsage> {
+    join_all(
+        connected_endpoints(inventory)
+            .into_iter()
+            .map(|endpoint| async move {
+                let df = match inventory.docker(&endpoint) {
+                    Ok(docker) => docker.df().await.map_err(|e| {
+                        error!(
+                            "{} ({}): {}",
+                            SystemError::DockerDiskUsageFailed.message(),
+                            endpoint,
+                            e
+                        );
+                        SystemError::DockerDiskUsageFailed
+                    }),
+                    Err(e) => Err(e),
+                };
+                match df {
+                    Ok(df) => summarize_disk_usage(&endpoint, df),
+                    Err(e) => DockerDiskUsage {
+                        endpoint,
+                        error: Some(e.message().to_string()),
+                        ..Default::default()
+                    },
+                }
+            }),
+    )
+    .await
+}
+
+/// Agrège la réponse de `/system/df` : tailles totales et espace récupérable.
+pub fn summarize_disk_usage(endpoint: &str, df: SystemDataUsageResponse) -> DockerDiskUsage {
+    let size = |bytes: i64| bytes.max(0) as u64;
+    let mut usage = DockerDiskUsage {
+        endpoint: endpoint.to_string(),
+        ..Default::default()
+    };
+
+    let mut images = df.images.unwrap_or_default();
+    usage.images_count = images.len();
+    for image in &images {
+        let dangling = is_dangling(&image.repo_tags);
+        usage.images_size += size(image.size);
+        usage.dangling_images += dangling as usize;
+        if image.containers == 0 {
+            // Seules les couches propres à l'image sont libérées
+            usage.images_reclaimable += size(image.size - image.shared_size.max(0));
+        }
+    }
+    images.sort_by_key(|image| std::cmp::Reverse(image.size));
+    usage.largest_images = images
+        .into_iter()
+        .take(LARGEST_IMAGES)
+        .map(|image| ImageUsage {
+            id: image
+                .id
+                .trim_start_matches("sha256:")
+                .chars()
+                .take(12)
+                .collect(),
+            dangling: is_dangling(&image.repo_tags),
+            tags: image
+                .repo_tags
+                .into_iter()
+                .filter(|tag| tag != "<none>:<none>")
+                .collect(),
+            size: size(image.size),
+            containers: image.containers.max(0) as u64,
+        })
+        .collect();
+
+    for container in df.containers.unwrap_or_default() {
+        let writable = size(container.size_rw.unwrap_or_default());
+        usage.containers_size += writable;
+        if container.state.as_deref() != Some("running") {
+            usage.containers_reclaimable += writable;
+        }
+    }
+
+    for volume in df.volumes.unwrap_or_default() {
+        let data = volume
+            .usage_data
+            .unwrap_or(bollard::models::VolumeUsageData {
+                size: -1,
+                ref_count: -1,
+            });
+        usage.volumes_count += 1;
+        usage.volumes_size += size(data.size);
+        if data.ref_count == 0 {
+            usage.volumes_reclaimable += size(data.size);
+            usage.unused_volumes.push(VolumeUsage {
+                name: volume.name,
+                driver: volume.driver,
+                size: size(data.size),
+            });
+        }
+    }
+    usage
+        .unused_volumes
+        .sort_by_key(|volume| std::cmp::Reverse(volume.size));
+
+    for cache in df.build_cache.unwrap_or_default() {
+        let bytes = size(cache.size.unwrap_or_default());
+        usage.build_cache_size += bytes;
+        if !cache.in_use.unwrap_or(false) && !cache.shared.unwrap_or(false) {
+            usage.build_cache_reclaimable += bytes;
+        }
+    }
+
+    usage.reclaimable = usage.images_reclaimable
+        + usage.containers_reclaimable
+        + usage.volumes_reclaimable
+        + usage.build_cache_reclaimable;
+    usage
+}
+
+/// Alerte lorsque l'espace récupérable d'un endpoint dépasse `threshold` octets.
+pub fn docker_disk_alerts(usages: &[DockerDiskUsage], threshold: u64) -> Vec<Alert> {
+    usages
+        .iter()
+        .filter(|usage| usage.error.is_none() && usage.reclaimable >= threshold)
+        .map(|usage| {
+            Alert::new(
+                &format!("docker-disk:{}", usage.endpoint),
+                CheckState::Warning,
+                format!(
+                    "{} reclaimable: images {}, stopped containers {}, unused volumes {}, build cache {}",
+                    format_size(usage.reclaimable),
+                    format_size(usage.images_reclaimable),
+                    format_size(usage.containers_reclaimable),
+                    format_size(usage.volumes_reclaimable),
+                    format_size(usage.build_cache_reclaimable)
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Supprime les ressources demandées.
+pub async fn prune(
+    docker: &Docker,
+    endpoint: &ContainerEndpoint,
+    query: &PruneQuery,
+) -> Result<PruneReport, SystemError> {
+    let failed = |e: bollard::errors::Error| {
+        error!("{}: {}", SystemError::DockerPruneFailed.message(), e);
+        SystemError::DockerPruneFailed
+    };
+    let mut report = PruneReport::default();
+
+    if query.volumes {
+        let version = docker.version().await.map_err(failed)?;
+        let podman = endpoint.runtime == ContainerRuntime::Podman
+            || version
+                .components
+                .unwrap_or_default()
+                .iter()
+                .any(|component| component.name.contains("Podman"));
+        let api = version.api_version.as_deref().and_then(parse_api_version);
+        if podman || api.is_none_or(|api| api < ANONYMOUS_VOLUME_PRUNE_API) {
+            return Err(SystemError::DockerVolumePruneUnsafe);
+        }
+    }
+
+    if query.containers {
+        let pruned = docker
+            .prune_containers(None::<PruneContainersOptions<String>>)
+            .await
+            .map_err(failed)?;
+        report.containers_deleted = pruned.containers_deleted.unwrap_or_default().len();
+        report.space_reclaimed += pruned.space_reclaimed.unwrap_or_default().max(0) as u64;
+    }
+    if query.images {
+        let pruned = docker
+            .prune_images(None::<PruneImagesOptions<String>>)
+            .await
+            .map_err(failed)?;
+        report.images_deleted = pruned.images_deleted.unwrap_or_default().len();
+        report.space_reclaimed += pruned.space_reclaimed.unwrap_or_default().max(0) as u64;
+    }
+    if query.volumes {
+        let pruned = docker
+            .prune_volumes(None::<PruneVolumesOptions<String>>)
+            .await
+            .map_err(failed)?;
+        report.volumes_deleted = pruned.volumes_deleted.unwrap_or_default().len();
+        report.space_reclaimed += pruned.space_reclaimed.unwrap_or_default().max(0) as u64;
+    }
+    if query.build_cache {
+        let endpoint = endpoint.clone();
+        let (deleted, reclaimed) =
+            actix_web::rt::task::spawn_blocking(move || prune_build_cache(&endpoint))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result)
+                .map_err(|e| {
+                    error!(
+                        "{} (build cache): {}",
+                        SystemError::DockerPruneFailed.message(),
+                        e
+                    );
+                    SystemError::DockerPruneFailed
+                })?;
+        info!(
+            "Build cache pruned: {} records, {} bytes",
+            deleted, reclaimed
+        );
+        report.build_cache_deleted = deleted;
+        report.space_reclaimed += reclaimed;
+    }
+    Ok(report)
+}
+
+/// `1.41` -> `(1, 41)`.
+fn parse_api_version(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// `POST /build/prune`, absent de `bollard` : la requête HTTP/1.0 est envoyée
+/// directement sur le socket de l'endpoint. Renvoie le nombre d'entrées supprimées
+/// et l'espace libéré.
+fn prune_build_cache(endpoint: &ContainerEndpoint) -> Result<(usize, u64), String> {
+    let timeout = Duration::from_secs(endpoint.timeout_secs);
+    let response = match endpoint.transport() {
+        EndpointTransport::Unix(path) => {
+            let stream = UnixStream::connect(&path).map_err(|e| format!("{}: {}", path, e))?;
+            stream
+                .set_read_timeout(Some(timeout))
+                .and_then(|_| stream.set_write_timeout(Some(timeout)))
+                .map_err(|e| e.to_string())?;
+            exchange(stream)
+        }
+        EndpointTransport::Tcp { address, tls } => {
+            let stream =
+                connect_tcp(&address, timeout).map_err(|e| format!("{}: {}", address, e))?;
+            stream
+                .set_read_timeout(Some(timeout))
+                .and_then(|_| stream.set_write_timeout(Some(timeout)))
+                .map_err(|e| e.to_string())?;
+            match tls {
+                Some((ca, cert, key)) => {
+                    let mut connector = SslConnector::builder(SslMethod::tls_client())
+                        .map_err(|e| e.to_string())?;
+                    connector.set_ca_file(&ca).map_err(|e| e.to_string())?;
+                    connector
+                        .set_certificate_file(&cert, SslFiletype::PEM)
+                        .map_err(|e| e.to_string())?;
+                    connector
+                        .set_private_key_file(&key, SslFiletype::PEM)
+                        .map_err(|e| e.to_string())?;
+                    let host = server_name(&address)?;
+                    exchange(
+                        connector
+                            .build()
+                            .connect(&host, stream)
+                            .map_err(|e| e.to_string())?,
+                    )
+                }
+                None => exchange(stream),
+            }
+        }
+    };
+    parse_build_prune_response(&response.map_err(|e| e.to_string())?)
+}
+
+/// Connexion limitée à `timeout` pour chacune des adresses résolues.
+fn connect_tcp(address: &str, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "no address resolved")
+    }))
+}
+
+/// Nom présenté au serveur TLS : l'hôte de `hôte:port`, sans les crochets d'une IPv6.
+fn server_name(address: &str) -> Result<String, String> {
+    let authority: Authority = address.parse().map_err(|e| format!("{}: {}", address, e))?;
+    Ok(authority
+        .host()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string())
+}
+
+/// Envoie la requête de `/build/prune` et lit la réponse jusqu'à la fermeture.
+fn exchange(mut stream: impl Read + Write) -> std::io::Result<Vec<u8>> {
+    stream.write_all(b"POST /build/prune HTTP/1.0\r\nHost: docker\r\nContent-Length: 0\r\n\r\n")?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BuildPruneResponse {
+    #[serde(default)]
+    caches_deleted: Option<Vec<String>>,
+    #[serde(default)]
+    space_reclaimed: u64,
+}
+
+/// Réponse HTTP brute de `/build/prune` (HTTP/1.0 : corps non découpé).
+fn parse_build_prune_response(response: &[u8]) -> Result<(usize, u64), String> {
+    let response = String::from_utf8_lossy(response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "truncated response".to_string())?;
+    let status = head.split_whitespace().nth(1).unwrap_or_default();
+    if !status.starts_with('2') {
+        return Err(format!("HTTP {}: {}", status, body.trim()));
+    }
+    let pruned: BuildPruneResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    Ok((
+        pruned.caches_deleted.unwrap_or_default().len(),
+        pruned.space_reclaimed,
+    ))
+}
+
+fn is_dangling(tags: &[String]) -> bool {
+    tags.iter().all(|tag| tag == "<none>:<none>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bollard::models::{BuildCache, ContainerSummary, ImageSummary, Volume, VolumeUsageData};
+
+    fn image(tags: &[&str], size: i64, shared_size: i64, containers: i64) -> ImageSummary {
+        ImageSummary {
+            id: format!("sha256:{}", "ab".repeat(32)),
+            repo_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            size,
+            shared_size,
+            containers,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summarize_disk_usage_reclaimable_space() {
+        let df = SystemDataUsageResponse {
+            images: Some(vec![
+                image(&["nginx:1.27"], 200, 50, 1),
+                image(&["nginx:1.25"], 180, 50, 0),
+                image(&[], 30, 0, 0),
+            ]),
+            containers: Some(vec![
+                ContainerSummary {
+                    size_rw: Some(10),
+                    state: Some("running".to_string()),
+                    ..Default::default()
+                },
+                ContainerSummary {
+                    size_rw: Some(5),
+                    state: Some("exited".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            volumes: Some(vec![
+                Volume {
+                    name: "db".to_string(),
+                    usage_data: Some(VolumeUsageData {
+                        size: 1000,
+                        ref_count: 1,
+                    }),
+                    ..Default::default()
+                },
+                Volume {
+                    name: "3f1c".to_string(),
+                    usage_data: Some(VolumeUsageData {
+                        size: 400,
+                        ref_count: 0,
+                    }),
+                    ..Default::default()
+                },
+            ]),
+            build_cache: Some(vec![
+                BuildCache {
+                    size: Some(70),
+                    in_use: Some(false),
+                    shared: Some(false),
+                    ..Default::default()
+                },
+                BuildCache {
+                    size: Some(20),
+                    in_use: Some(true),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let usage = summarize_disk_usage("local", df);
+        assert_eq!(usage.images_size, 410);
+        assert_eq!(usage.dangling_images, 1);
+        assert_eq!(usage.images_reclaimable, 130 + 30);
+        assert_eq!(usage.containers_reclaimable, 5);
+        assert_eq!(usage.volumes_reclaimable, 400);
+        assert_eq!(usage.unused_volumes[0].name, "3f1c");
+        assert_eq!(usage.build_cache_size, 90);
+        assert_eq!(usage.build_cache_reclaimable, 70);
+        assert_eq!(usage.reclaimable, 160 + 5 + 400 + 70);
+        assert_eq!(usage.largest_images[0].tags, vec!["nginx:1.27".to_string()]);
+        assert!(usage.largest_images[2].dangling);
+
+        let alerts = docker_disk_alerts(&[usage], 600);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].state, CheckState::Warning);
+    }
+
+    #[test]
+    fn test_build_prune_response_and_api_version() {
+        let ok = b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{\"CachesDeleted\":[\"a\",\"b\"],\"SpaceReclaimed\":4096}\n";
+        assert_eq!(parse_build_prune_response(ok), Ok((2, 4096)));
+        let empty = b"HTTP/1.0 200 OK\r\n\r\n{\"CachesDeleted\":null,\"SpaceReclaimed\":0}";
+        assert_eq!(parse_build_prune_response(empty), Ok((0, 0)));
+        let denied = b"HTTP/1.0 404 Not Found\r\n\r\n{\"message\":\"page not found\"}";
+        assert!(parse_build_prune_response(denied)
+            .unwrap_err()
+            .contains("404"));
+
+        assert_eq!(parse_api_version("1.41"), Some((1, 41)));
+        assert!(parse_api_version("1.41").unwrap() < ANONYMOUS_VOLUME_PRUNE_API);
+        assert!(parse_api_version("1.47").unwrap() >= ANONYMOUS_VOLUME_PRUNE_API);
+
+        assert_eq!(server_name("docker.lan:2376").as_deref(), Ok("docker.lan"));
+        assert_eq!(server_name("[fd00::12]:2376").as_deref(), Ok("fd00::12"));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::EndpointStatus,
+};
+use crate::services::service_checker::load_checks_file;
+use bollard::{Docker, API_DEFAULT_VERSION};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Socket de l'API compatible Docker de Podman (service `podman.socket` en root).
+const PODMAN_SOCKET: &str = "/run/podman/podman.sock";
+
+/// Socket Docker par défaut, en l'absence de `DOCKER_HOST`.
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+/// Connexion brute à un endpoint, pour les routes de l'API absentes de `bollard`.
+#[derive(Debug, PartialEq)]
+pub enum EndpointTransport {
+    Unix(String),
+    /// `hôte:port`, et les chemins CA / certificat / clé en TLS.
+    Tcp {
+        address: String,
+        tls: Option<(String, String, String)>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    #[default]
+    Docker,
+    Podman,
+}
+
+impl ContainerRuntime {
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+        }
+    }
+}
+
+/// Moteur de conteneurs déclaré dans `services.toml` (`[[container_endpoints]]`).
+///
+/// Sans `socket` ni `url`, on utilise le socket par défaut du runtime
+/// (`DOCKER_HOST` ou `/var/run/docker.sock` pour Docker).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerEndpoint {
+    /// Libellé affiché sur le tableau de bord.
+    pub name: String,
+    #[serde(default)]
+    pub runtime: ContainerRuntime,
+    /// Socket Unix (`/var/run/docker.sock`, `/run/user/1000/podman/podman.sock`...).
+    pub socket: Option<String>,
+    /// Démon distant (`tcp://10.0.0.5:2376`) ; TLS si les trois chemins ci-dessous sont fournis.
+    pub url: Option<String>,
+    pub tls_ca: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Deserialize)]
+struct EndpointsFile {
+    #[serde(default)]
+    container_endpoints: Vec<ContainerEndpoint>,
+}
+
+fn default_timeout_secs() -> u64 {
+    120
+}
+
+/// Endpoints déclarés, ou le Docker local si aucun ne l'est.
+pub fn load_container_endpoints(path: &str) -> Vec<ContainerEndpoint> {
+    let endpoints = load_checks_file::<EndpointsFile>(path)
+        .map(|file| file.container_endpoints)
+        .unwrap_or_default();
+    if endpoints.is_empty() {
+        vec![ContainerEndpoint::local()]
+    } else {
+        endpoints
+    }
+}
+
+impl ContainerEndpoint {
+    pub fn local() -> Self {
+        Self {
+            name: "local".to_string(),
+            runtime: ContainerRuntime::Docker,
+            socket: None,
+            url: None,
+            tls_ca: None,
+            tls_cert: None,
+            tls_key: None,
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+
+    /// Adresse affichée à côté du libellé.
+    pub fn address(&self) -> String {
+        match (&self.url, &self.socket, self.runtime) {
+            (Some(url), _, _) => url.clone(),
+            (None, Some(socket), _) => format!("unix://{}", socket.trim_start_matches("unix://")),
+            (None, None, ContainerRuntime::Podman) => format!("unix://{}", PODMAN_SOCKET),
+            (None, None, ContainerRuntime::Docker) => "default socket".to_string(),
+        }
+    }
+
+    /// Client pour cet endpoint ; l'erreur décrit la configuration ou le socket en cause.
+    pub fn connect(&self) -> Result<Docker, String> {
+        let docker = match (&self.url, &self.socket) {
+            (Some(url), _) => match (&self.tls_ca, &self.tls_cert, &self.tls_key) {
+                (Some(ca), Some(cert), Some(key)) => Docker::connect_with_ssl(
+                    url,
+                    Path::new(key),
+                    Path::new(cert),
+                    Path::new(ca),
+                    self.timeout_secs,
+                    API_DEFAULT_VERSION,
+                ),
+                (None, None, None) => {
+                    Docker::connect_with_http(url, self.timeout_secs, API_DEFAULT_VERSION)
+                }
+                _ => return Err("tls_ca, tls_cert and tls_key must be set together".to_string()),
+            },
+            (None, Some(socket)) => {
+                Docker::connect_with_unix(socket, self.timeout_secs, API_DEFAULT_VERSION)
+            }
+            (None, None) if self.runtime == ContainerRuntime::Podman => {
+                Docker::connect_with_unix(PODMAN_SOCKET, self.timeout_secs, API_DEFAULT_VERSION)
+            }
+            (None, None) => Docker::connect_with_local_defaults(),
+        };
+        docker.map_err(|e| e.to_string())
+    }
+
+    /// Socket ou adresse TCP de l'endpoint, résolus comme dans [`Self::connect`].
+    pub fn transport(&self) -> EndpointTransport {
+        let url = match (&self.url, &self.socket, self.runtime) {
+            (Some(url), _, _) => url.clone(),
+            (None, Some(socket), _) => socket.clone(),
+            (None, None, ContainerRuntime::Podman) => PODMAN_SOCKET.to_string(),
+            (None, None, ContainerRuntime::Docker) => {
+                std::env::var("DOCKER_HOST").unwrap_or_else(|_| DOCKER_SOCKET.to_string())
+            }
+        };
+        match url.split_once("://") {
+            Some(("tcp" | "http" | "https", address)) => EndpointTransport::Tcp {
+                address: address.trim_end_matches('/').to_string(),
+                tls: match (&self.tls_ca, &self.tls_cert, &self.tls_key) {
+                    (Some(ca), Some(cert), Some(key)) => {
+                        Some((ca.clone(), cert.clone(), key.clone()))
+                    }
+                    _ => None,
+                },
+            },
+            Some((_, path)) => EndpointTransport::Unix(path.to_string()),
+            None => EndpointTransport::Unix(url),
+        }
+    }
+}
+
+/// Alertes pour les endpoints injoignables.
+pub fn endpoint_alerts(endpoints: &[EndpointStatus]) -> Vec<Alert> {
+    endpoints
+        .iter()
+        .filter(|endpoint| !endpoint.connected)
+        .map(|endpoint| {
+            Alert::new(
+                &format!("{}:{}", endpoint.runtime, endpoint.name),
+                CheckState::Critical,
+                format!(
+                    "endpoint {} unreachable: {}",
+                    endpoint.address,
+                    endpoint.error.as_deref().unwrap_or("not connected yet")
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incomplete_tls_settings_are_rejected() {
+        let endpoint = ContainerEndpoint {
+            name: "remote".to_string(),
+            url: Some("tcp://10.0.0.5:2376".to_string()),
+            tls_ca: Some("/etc/monitor_server/ca.pem".to_string()),
+            ..ContainerEndpoint::local()
+        };
+        assert!(endpoint
+            .connect()
+            .unwrap_err()
+            .contains("tls_ca, tls_cert and tls_key"));
+
+        let podman = ContainerEndpoint {
+            name: "podman".to_string(),
+            runtime: ContainerRuntime::Podman,
+            ..ContainerEndpoint::local()
+        };
+        assert_eq!(podman.address(), "unix:///run/podman/podman.sock");
+        assert_eq!(
+            podman.transport(),
+            EndpointTransport::Unix(PODMAN_SOCKET.to_string())
+        );
+        assert_eq!(
+            endpoint.transport(),
+            EndpointTransport::Tcp {
+                address: "10.0.0.5:2376".to_string(),
+                tls: None
+            }
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use crate::models::{
+    errors::SystemError,
+    templates::{ContainerEvent, ContainerStatus, EndpointStatus},
+};
+use crate::services::{
+    docker::{
+        describe_status, fetch_container, fetch_containers, retain_restart_history, CrashLoopPolicy,
+    },
+    docker_endpoints::ContainerEndpoint,
+};
+use bollard::{models::EventMessage, system::EventsOptions, Docker};
+use chrono::{Local, TimeZone, Utc};
+use futures_util::StreamExt;
+use log::{debug, error, info, warn};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+/// Nombre d'événements conservés pour la frise du tableau de bord.
+const MAX_EVENTS: usize = 100;
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Actions Docker qui modifient l'inventaire et sont affichées dans la frise.
+const TRACKED_ACTIONS: &[&str] = &[
+    "create", "start", "restart", "stop", "die", "kill", "oom", "pause", "unpause", "destroy",
+    "rename", "update",
+];
+
+/// État d'un endpoint : connexion et conteneurs connus.
+struct EndpointState {
+    endpoint: ContainerEndpoint,
+    connected: bool,
+    error: Option<String>,
+    containers: HashMap<String, ContainerStatus>,
+    /// Client partagé par les requêtes du tableau de bord et de l'API, recréé après
+    /// une déconnexion.
+    client: Option<Docker>,
+}
+
+/// Inventaire des conteneurs de chaque endpoint, tenu à jour par leur flux `/events`.
+#[derive(Default)]
+pub struct DockerInventory {
+    endpoints: RwLock<Vec<EndpointState>>,
+    events: Mutex<VecDeque<ContainerEvent>>,
+}
+
+impl DockerInventory {
+    pub fn new(endpoints: Vec<ContainerEndpoint>) -> Self {
+        Self {
+            endpoints: RwLock::new(
+                endpoints
+                    .into_iter()
+                    .map(|endpoint| EndpointState {
+                        endpoint,
+                        connected: false,
+                        error: None,
+                        containers: HashMap::new(),
+                        client: None,
+                    })
+                    .collect(),
+            ),
+            events: Mutex::default(),
+        }
+    }
+
+    /// Conteneurs connus de tous les endpoints, triés par nom, avec un texte de statut recalculé.
+    pub fn containers(&self) -> Vec<ContainerStatus> {
+        let now = Utc::now();
+        let mut containers: Vec<ContainerStatus> = self
+            .endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .flat_map(|state| state.containers.values().cloned())
+            .map(|mut container| {
+                container.status = describe_status(&container, now);
+                container
+            })
+            .collect();
+        containers.sort_by(|a, b| a.name.cmp(&b.name).then(a.endpoint.cmp(&b.endpoint)));
+        containers
+    }
+
+    /// Conteneur d'un endpoint, recherché par ID court ou par nom : deux endpoints
+    /// peuvent héberger des conteneurs de même nom.
+    pub fn container(&self, endpoint: &str, id_or_name: &str) -> Option<ContainerStatus> {
+        let endpoints = self
+            .endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let containers = || {
+            endpoints
+                .iter()
+                .filter(|state| state.endpoint.name == endpoint)
+                .flat_map(|state| state.containers.values())
+        };
+        containers()
+            .find(|c| c.id == id_or_name)
+            .or_else(|| containers().find(|c| c.name == id_or_name))
+            .cloned()
+            .map(|mut container| {
+                container.status = describe_status(&container, Utc::now());
+                container
+            })
+    }
+
+    /// État de connexion de chaque endpoint, dans l'ordre de la configuration.
+    pub fn endpoints(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|state| EndpointStatus {
+                name: state.endpoint.name.clone(),
+                runtime: state.endpoint.runtime.label().to_string(),
+                address: state.endpoint.address(),
+                connected: state.connected,
+                error: state.error.clone(),
+                containers: state.containers.len(),
+            })
+            .collect()
+    }
+
+    /// Configuration d'un endpoint d'après son libellé.
+    pub fn endpoint(&self, name: &str) -> Option<ContainerEndpoint> {
+        self.endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .find(|state| state.endpoint.name == name)
+            .map(|state| state.endpoint.clone())
+    }
+
+    /// Client pour l'endpoint d'un conteneur, créé à la première demande puis réutilisé.
+    pub fn docker(&self, endpoint: &str) -> Result<Docker, SystemError> {
+        let mut endpoints = self
+            .endpoints
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let state = endpoints
+            .iter_mut()
+            .find(|state| state.endpoint.name == endpoint)
+            .ok_or(SystemError::DockerConnectionFailed)?;
+        if let Some(client) = &state.client {
+            return Ok(client.clone());
+        }
+        let client = state.endpoint.connect().map_err(|e| {
+            error!(
+                "{} ({}): {}",
+                SystemError::DockerConnectionFailed.message(),
+                endpoint,
+                e
+            );
+            SystemError::DockerConnectionFailed
+        })?;
+        state.client = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Derniers événements, du plus récent au plus ancien.
+    pub fn recent_events(&self, limit: usize) -> Vec<ContainerEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn with_endpoint(&self, endpoint: &str, update: impl FnOnce(&mut EndpointState)) {
+        let mut endpoints = self
+            .endpoints
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(state) = endpoints
+            .iter_mut()
+            .find(|state| state.endpoint.name == endpoint)
+        {
+            update(state);
+        }
+    }
+
+    fn set_disconnected(&self, endpoint: &str, error: String) {
+        self.with_endpoint(endpoint, |state| {
+            state.connected = false;
+            state.error = Some(error);
+            state.containers.clear();
+            state.client = None;
+        });
+    }
+
+    fn replace_all(&self, endpoint: &str, containers: Vec<ContainerStatus>) {
+        self.with_endpoint(endpoint, |state| {
+            state.connected = true;
+            state.error = None;
+            state.containers = containers
+                .into_iter()
+                .map(|mut container| {
+                    container.endpoint = endpoint.to_string();
+                    (container.id.clone(), container)
+                })
+                .collect();
+        });
+        self.prune_restart_history();
+    }
+
+    fn upsert(&self, endpoint: &str, mut container: ContainerStatus) {
+        container.endpoint = endpoint.to_string();
+        self.with_endpoint(endpoint, |state| {
+            state.containers.insert(container.id.clone(), container);
+        });
+    }
+
+    fn remove(&self, endpoint: &str, id: &str) {
+        self.with_endpoint(endpoint, |state| {
+            state.containers.remove(id);
+        });
+        self.prune_restart_history();
+    }
+
+    /// Limite l'historique des redémarrages aux conteneurs encore inventoriés, tous
+    /// endpoints confondus.
+    fn prune_restart_history(&self) {
+        let known: HashSet<(String, String)> = self
+            .endpoints
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .flat_map(|state| {
+                state
+                    .containers
+                    .keys()
+                    .map(|id| (state.endpoint.name.clone(), id.clone()))
+            })
+            .collect();
+        retain_restart_history(|endpoint, id| {
+            known.contains(&(endpoint.to_string(), id.chars().take(12).collect()))
+        });
+    }
+
+    fn push_event(&self, event: ContainerEvent) {
+        let mut events = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        events.push_back(event);
+        while events.len() > MAX_EVENTS {
+            events.pop_front();
+        }
+    }
+}
+
+/// Tâche de fond d'un endpoint : synchronise l'inventaire puis suit `/events`, avec
+/// reconnexion automatique (délai exponentiel) si le démon redémarre. Le délai repart
+/// du minimum dès qu'une connexion a reçu des événements.
+pub async fn watch_docker_events(
+    inventory: Arc<DockerInventory>,
+    endpoint: ContainerEndpoint,
+    policy: CrashLoopPolicy,
+) {
+    let mut delay = RECONNECT_MIN_DELAY;
+    loop {
+        let error = match follow_events(&inventory, &endpoint, policy, &mut delay).await {
+            Ok(()) => {
+                warn!("Event stream of {} closed, reconnecting", endpoint.name);
+                delay = RECONNECT_MIN_DELAY;
+                "event stream closed".to_string()
+            }
+            Err(e) => {
+                debug!(
+                    "{} ({}) Retrying in {} s",
+                    e,
+                    endpoint.name,
+                    delay.as_secs()
+                );
+                e
+            }
+        };
+        inventory.set_disconnected(&endpoint.name, error);
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+    }
+}
+
+async fn follow_events(
+    inventory: &DockerInventory,
+    endpoint: &ContainerEndpoint,
+    policy: CrashLoopPolicy,
+    delay: &mut Duration,
+) -> Result<(), String> {
+    let connection_failed =
+        |e: String| format!("{} {}", SystemError::DockerConnectionFailed.message(), e);
+    let docker = endpoint.connect().map_err(connection_failed)?;
+    docker
+        .ping()
+        .await
+        .map_err(|e| connection_failed(e.to_string()))?;
+
+    // Les événements survenus pendant la synchronisation initiale seront rejoués.
+    let since = Utc::now().timestamp().to_string();
+    let containers = fetch_containers(&docker, &endpoint.name, policy)
+        .await
+        .map_err(|e| e.message().to_string())?;
+    inventory.replace_all(&endpoint.name, containers);
+    info!(
+        "Container inventory of {} synchronised, following events",
+        endpoint.name
+    );
+
+    let mut events = docker.events(Some(EventsOptions::<String> {
+        since: Some(since),
+        until: None,
+        filters: HashMap::from([("type".to_string(), vec!["container".to_string()])]),
+    }));
+
+    while let Some(event) = events.next().await {
+        let event = event.map_err(|e| {
+            error!(
+                "{} ({}): {}",
+                SystemError::DockerEventStreamFailed.message(),
+                endpoint.name,
+                e
+            );
+            format!("{} {}", SystemError::DockerEventStreamFailed.message(), e)
+        })?;
+        // Connexion opérationnelle : une coupure ultérieure est retentée rapidement
+        *delay = RECONNECT_MIN_DELAY;
+        handle_event(&docker, inventory, &endpoint.name, policy, event).await;
+    }
+    Ok(())
+}
+
+async fn handle_event(
+    docker: &Docker,
+    inventory: &DockerInventory,
+    endpoint: &str,
+    policy: CrashLoopPolicy,
+    event: EventMessage,
+) {
+    let Some(action) = event.action.as_deref() else {
+        return;
+    };
+    let is_health = action.starts_with("health_status");
+    if !is_health && !TRACKED_ACTIONS.contains(&action) {
+        return;
+    }
+
+    let actor = event.actor.unwrap_or_default();
+    let id: String = actor.id.unwrap_or_default().chars().take(12).collect();
+    let attributes = actor.attributes.unwrap_or_default();
+    debug!("Event {} for container {} on {}", action, id, endpoint);
+
+    let timestamp = event.time.unwrap_or_else(|| Utc::now().timestamp());
+    inventory.push_event(ContainerEvent {
+        time: Local
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default(),
+        timestamp,
+        endpoint: endpoint.to_string(),
+        id: id.clone(),
+        name: attributes
+            .get("name")
+            .cloned()
+            .unwrap_or_else(|| id.clone()),
+        action: action.to_string(),
+        exit_code: attributes.get("exitCode").cloned(),
+    });
+
+    if action == "destroy" {
+        inventory.remove(endpoint, &id);
+        return;
+    }
+    match fetch_container(docker, endpoint, &id, policy).await {
+        Ok(Some(container)) => inventory.upsert(endpoint, container),
+        Ok(None) => inventory.remove(endpoint, &id),
+        Err(e) => warn!("{} ({} on {})", e.message(), id, endpoint),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_events_are_capped_and_newest_first() {
+        let inventory = DockerInventory::default();
+        for i in 0..(MAX_EVENTS as i64 + 5) {
+            inventory.push_event(ContainerEvent {
+                time: String::new(),
+                timestamp: i,
+                endpoint: "local".to_string(),
+                id: "abc".to_string(),
+                name: "web".to_string(),
+                action: "start".to_string(),
+                exit_code: None,
+            });
+        }
+
+        let events = inventory.recent_events(usize::MAX);
+        assert_eq!(events.len(), MAX_EVENTS);
+        assert_eq!(events[0].timestamp, MAX_EVENTS as i64 + 4);
+        assert_eq!(events[MAX_EVENTS - 1].timestamp, 5);
+    }
+
+    #[test]
+    fn test_containers_are_tagged_with_their_endpoint() {
+        let remote = ContainerEndpoint {
+            name: "remote".to_string(),
+            ..ContainerEndpoint::local()
+        };
+        let inventory = DockerInventory::new(vec![ContainerEndpoint::local(), remote]);
+        let web = ContainerStatus {
+            id: "0123456789ab".to_string(),
+            name: "web".to_string(),
+            ..Default::default()
+        };
+        inventory.replace_all("remote", vec![web]);
+        inventory.set_disconnected("local", "socket not found".to_string());
+
+        let container = inventory.container("remote", "web").unwrap();
+        assert_eq!(container.endpoint, "remote");
+        assert!(inventory.container("local", "web").is_none());
+
+        let endpoints = inventory.endpoints();
+        assert!(!endpoints[0].connected);
+        assert_eq!(endpoints[0].error.as_deref(), Some("socket not found"));
+        assert!(endpoints[1].connected);
+        assert_eq!(endpoints[1].containers, 1);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::models::{errors::SystemError, templates::LogLine};
+use bollard::{
+    container::{LogOutput, LogsOptions},
+    errors::Error as DockerError,
+    Docker,
+};
+use futures_util::{stream, Stream, StreamExt};
+use log::{error, warn};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Nombre maximal de lignes renvoyées sans `follow` (`tail=all` compris).
+const MAX_TAIL: usize = 5000;
+
+/// Paramètres de `/api/v1/containers/{endpoint}/{id}/logs`, calqués sur `docker logs`.
+#[derive(Debug, Deserialize)]
+pub struct LogQuery {
+    /// Nombre de lignes depuis la fin, ou `all`.
+    #[serde(default = "default_tail")]
+    pub tail: String,
+    /// Horodatage Unix à partir duquel lire les journaux.
+    pub since: Option<i64>,
+    #[serde(default = "default_true")]
+    pub stdout: bool,
+    #[serde(default = "default_true")]
+    pub stderr: bool,
+    #[serde(default)]
+    pub timestamps: bool,
+    /// Suivi en continu (Server-Sent Events).
+    #[serde(default)]
+    pub follow: bool,
+}
+
+impl Default for LogQuery {
+    fn default() -> Self {
+        Self {
+            tail: default_tail(),
+            since: None,
+            stdout: true,
+            stderr: true,
+            timestamps: false,
+            follow: false,
+        }
+    }
+}
+
+fn default_tail() -> String {
+    "100".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Dernières lignes de journal d'un conteneur.
+pub async fn fetch_logs(
+    docker: &Docker,
+    id: &str,
+    query: &LogQuery,
+) -> Result<Vec<LogLine>, SystemError> {
+    let mut output = docker.logs(id, Some(logs_options(query, false)));
+
+    let mut splitter = LogSplitter::new(query.timestamps);
+    let mut lines = vec![];
+    while let Some(chunk) = output.next().await {
+        let chunk = chunk.map_err(|e| log_error(id, e))?;
+        lines.extend(splitter.push(chunk));
+    }
+    lines.extend(splitter.finish());
+    Ok(lines)
+}
+
+/// Flux continu des journaux d'un conteneur, qui se termine avec le conteneur.
+pub fn follow_logs(docker: &Docker, id: &str, query: &LogQuery) -> impl Stream<Item = LogLine> {
+    let output = Box::pin(docker.logs(id, Some(logs_options(query, true))));
+    let splitter = LogSplitter::new(query.timestamps);
+    let id = id.to_string();
+
+    stream::unfold(Some((output, splitter)), move |state| {
+        let id = id.clone();
+        async move {
+            let (mut output, mut splitter) = state?;
+            match output.next().await {
+                Some(Ok(chunk)) => Some((splitter.push(chunk), Some((output, splitter)))),
+                Some(Err(e)) => {
+                    warn!("Log stream for container {} interrupted: {}", id, e);
+                    Some((splitter.finish(), None))
+                }
+                None => Some((splitter.finish(), None)),
+            }
+        }
+    })
+    .flat_map(stream::iter)
+}
+
+fn log_error(id: &str, e: DockerError) -> SystemError {
+    match e {
+        DockerError::DockerResponseServerError {
+            status_code: 404, ..
+        } => SystemError::DockerContainerNotFound,
+        e => {
+            error!(
+                "{} ({}): {}",
+                SystemError::DockerLogsFailed.message(),
+                id,
+                e
+            );
+            SystemError::DockerLogsFailed
+        }
+    }
+}
+
+fn logs_options(query: &LogQuery, follow: bool) -> LogsOptions<String> {
+    LogsOptions {
+        follow,
+        stdout: query.stdout,
+        stderr: query.stderr,
+        since: query.since.unwrap_or_default(),
+        until: 0,
+        timestamps: query.timestamps,
+        tail: normalize_tail(&query.tail, follow),
+    }
+}
+
+/// Borne `tail` à `MAX_TAIL` ; `all` n'est accepté tel quel qu'en mode suivi.
+fn normalize_tail(tail: &str, follow: bool) -> String {
+    match tail.trim().parse::<usize>() {
+        Ok(lines) => lines.min(MAX_TAIL).to_string(),
+        Err(_) if tail == "all" && follow => tail.to_string(),
+        Err(_) if tail == "all" => MAX_TAIL.to_string(),
+        Err(_) => default_tail(),
+    }
+}
+
+/// Découpe la sortie Docker en lignes, en séparant l'horodatage si demandé.
+///
+/// Les blocs reçus ne s'arrêtent pas forcément en fin de ligne : la ligne inachevée
+/// de chaque flux est reportée sur le bloc suivant. `\n`, `\r\n` et `\r` seul
+/// terminent une ligne.
+struct LogSplitter {
+    timestamps: bool,
+    pending: BTreeMap<&'static str, Vec<u8>>,
+}
+
+impl LogSplitter {
+    fn new(timestamps: bool) -> Self {
+        Self {
+            timestamps,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Lignes terminées par ce bloc.
+    fn push(&mut self, output: LogOutput) -> Vec<LogLine> {
+        let (stream, message) = match output {
+            LogOutput::StdOut { message } => ("stdout", message),
+            LogOutput::StdErr { message } => ("stderr", message),
+            LogOutput::StdIn { message } => ("stdin", message),
+            LogOutput::Console { message } => ("console", message),
+        };
+        let buffer = self.pending.entry(stream).or_default();
+        buffer.extend_from_slice(&message);
+
+        let mut raw_lines = vec![];
+        let mut start = 0;
+        let mut i = 0;
+        while i < buffer.len() {
+            match buffer[i] {
+                b'\n' => {
+                    raw_lines.push(buffer[start..i].to_vec());
+                    start = i + 1;
+                }
+                // `\r` en fin de bloc : peut-être suivi d'un `\n` dans le bloc suivant
+                b'\r' if i + 1 == buffer.len() => break,
+                b'\r' => {
+                    raw_lines.push(buffer[start..i].to_vec());
+                    if buffer[i + 1] == b'\n' {
+                        i += 1;
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        buffer.drain(..start);
+
+        raw_lines
+            .iter()
+            .map(|line| self.log_line(stream, line))
+            .collect()
+    }
+
+    /// Lignes inachevées restant à la fin du flux.
+    fn finish(self) -> Vec<LogLine> {
+        self.pending
+            .iter()
+            .map(|(stream, rest)| (stream, rest.strip_suffix(b"\r").unwrap_or(rest)))
+            .filter(|(_, rest)| !rest.is_empty())
+            .map(|(stream, rest)| self.log_line(stream, rest))
+            .collect()
+    }
+
+    fn log_line(&self, stream: &str, line: &[u8]) -> LogLine {
+        let line = String::from_utf8_lossy(line);
+        let (timestamp, message) = match line.split_once(' ') {
+            Some((timestamp, message)) if self.timestamps => {
+                (Some(timestamp.to_string()), message.to_string())
+            }
+            _ => (None, line.to_string()),
+        };
+        LogLine {
+            stream: stream.to_string(),
+            timestamp,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_lines_split_chunks_and_timestamps() {
+        let chunk = LogOutput::StdErr {
+            message: "2024-05-01T10:00:00.000000000Z first\r\n2024-05-01T10:00:01.000000000Z second line\n"
+                .into(),
+        };
+        let mut splitter = LogSplitter::new(true);
+        let lines = splitter.push(chunk);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].stream, "stderr");
+        assert_eq!(
+            lines[0].timestamp.as_deref(),
+            Some("2024-05-01T10:00:00.000000000Z")
+        );
+        assert_eq!(lines[0].message, "first");
+        assert_eq!(lines[1].message, "second line");
+
+        // Ligne coupée entre deux blocs, `\r` seul et `\r\n` à cheval sur deux blocs
+        let mut splitter = LogSplitter::new(false);
+        let stdout = |message: &str| LogOutput::StdOut {
+            message: message.to_string().into(),
+        };
+        assert!(splitter.push(stdout("hel")).is_empty());
+        let lines = splitter.push(stdout("lo\r50%\r100%\r"));
+        let messages: Vec<_> = lines.iter().map(|line| line.message.as_str()).collect();
+        assert_eq!(messages, ["hello", "50%"]);
+        let lines = splitter.push(stdout("\ndone"));
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].message, "100%");
+        let lines = splitter.finish();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].message, "done");
+
+        assert_eq!(normalize_tail("all", false), "5000");
+        assert_eq!(normalize_tail("all", true), "all");
+        assert_eq!(normalize_tail("20", false), "20");
+        assert_eq!(normalize_tail("abc", false), "100");
+    }
+}
@@ -0,0 +1,73 @@
+use log::{debug, warn};
+use std::{io::ErrorKind, process::Stdio, time::Duration};
+use tokio::{process::Command, time::timeout};
+
+/// Sortie standard d'un outil système (`nft`, `zpool`, `wg`...) interrompu au-delà
+/// de `limit` ; `None` si l'outil n'est pas installé.
+///
+/// Un code de sortie non nul est une erreur, décrite par la sortie d'erreur.
+pub async fn run_tool(
+    program: &str,
+    args: &[&str],
+    limit: Duration,
+) -> Option<Result<String, String>> {
+    let child = match Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("{} not installed, skipping", program);
+            return None;
+        }
+        Err(e) => return Some(Err(format!("failed to execute {}: {}", program, e))),
+    };
+
+    Some(match timeout(limit, child.wait_with_output()).await {
+        Ok(Ok(output)) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        Ok(Ok(output)) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            warn!("{} {} failed: {}", program, args.join(" "), stderr.trim());
+            Err(format!("{} failed: {}", program, stderr.trim()))
+        }
+        Ok(Err(e)) => Err(format!("failed to wait for {}: {}", program, e)),
+        Err(_) => Err(format!(
+            "{} timed out after {} seconds",
+            program,
+            limit.as_secs()
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_tool() {
+        let limit = Duration::from_secs(5);
+        assert_eq!(
+            run_tool("sh", &["-c", "echo ok"], limit).await,
+            Some(Ok("ok\n".to_string()))
+        );
+        assert_eq!(
+            run_tool("sh", &["-c", "echo boom >&2; exit 3"], limit).await,
+            Some(Err("sh failed: boom".to_string()))
+        );
+        assert!(
+            run_tool("sh", &["-c", "sleep 5"], Duration::from_millis(100))
+                .await
+                .is_some_and(|result| result.is_err())
+        );
+        assert_eq!(
+            run_tool("monitor-server-missing-tool", &[], limit).await,
+            None
+        );
+    }
+}
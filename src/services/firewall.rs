@@ -0,0 +1,674 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::{FirewallChain, FirewallReport, FirewallRule},
+};
+use crate::services::{cache::ReportCache, exec::run_tool, state_file::write_state_file};
+use chrono::Local;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path, sync::Arc, time::Duration};
+
+/// Délai maximal de `nft` / `iptables-save`.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Dernier relevé du pare-feu, rafraîchi en tâche de fond par [`watch_firewall`] ;
+/// `None` si aucun outil n'est installé.
+pub type FirewallCache = ReportCache<Option<FirewallReport>>;
+
+/// Réglages du relevé du pare-feu, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct FirewallSettings {
+    pub baseline_file: String,
+    pub interval: Duration,
+}
+
+impl FirewallSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            baseline_file: config.firewall_baseline_file.clone(),
+            interval: Duration::from_secs(config.firewall_interval_secs),
+        }
+    }
+}
+
+/// Jeu de règles de référence, enregistré dans `firewall_baseline_file`.
+#[derive(Serialize, Deserialize)]
+struct FirewallBaseline {
+    created: String,
+    /// Règles et politiques, une par ligne : `famille table chaîne: règle`.
+    rules: Vec<String>,
+}
+
+/// Crée la référence si besoin, puis relève périodiquement le jeu de règles dans `cache`.
+pub async fn watch_firewall(cache: Arc<FirewallCache>, settings: FirewallSettings) {
+    init_baseline(&settings.baseline_file).await;
+    if settings.interval.is_zero() {
+        info!("Firewall monitoring disabled");
+        return;
+    }
+    loop {
+        let report = firewall_report(&settings.baseline_file).await;
+        if let Some(report) = &report {
+            debug!("Firewall chains listed: {}", report.chains.len());
+        }
+        cache.set(report);
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Jeu de règles actif comparé à la référence ; `None` si aucun outil n'est installé.
+///
+/// La consultation ne modifie jamais la référence : elle est créée au démarrage
+/// (`init_baseline`) puis remplacée sur demande (`save_baseline`).
+async fn firewall_report(baseline_file: &str) -> Option<FirewallReport> {
+    let mut report = collect_ruleset().await?;
+    if report.error.is_none() {
+        compare_with_baseline(&mut report, baseline_file);
+    }
+    Some(report)
+}
+
+/// Enregistre le jeu de règles courant comme référence s'il n'en existe pas encore.
+async fn init_baseline(baseline_file: &str) {
+    if baseline_file.is_empty() || Path::new(baseline_file).exists() {
+        return;
+    }
+    match collect_ruleset().await {
+        Some(FirewallReport { error: Some(e), .. }) => {
+            warn!("Cannot create firewall baseline {}: {}", baseline_file, e)
+        }
+        Some(report) => match write_baseline(&report, baseline_file) {
+            Ok(_) => info!("Firewall baseline saved to {}", baseline_file),
+            Err(e) => warn!(
+                "Failed to save firewall baseline to {}: {}",
+                baseline_file, e
+            ),
+        },
+        None => {}
+    }
+}
+
+/// Enregistre `report`, relevé par `collect_ruleset`, comme nouvelle référence.
+pub fn save_baseline(report: &mut FirewallReport, baseline_file: &str) -> std::io::Result<()> {
+    let baseline = write_baseline(report, baseline_file)?;
+    apply_baseline(report, &baseline);
+    Ok(())
+}
+
+/// Jeu de règles actif (`nft -j list ruleset`, sinon `iptables-save -c` et
+/// `ip6tables-save -c`) et son empreinte ; `None` si aucun outil n'est installé.
+pub async fn collect_ruleset() -> Option<FirewallReport> {
+    let mut report = read_ruleset().await?;
+    if report.error.is_none() {
+        report.fingerprint = fingerprint(&ruleset_lines(&report.chains));
+    }
+    Some(report)
+}
+
+async fn read_ruleset() -> Option<FirewallReport> {
+    let nft = match run_tool("nft", &["-j", "list", "ruleset"], COMMAND_TIMEOUT).await {
+        Some(Ok(ruleset)) => Some(parse_nft(&ruleset)),
+        Some(Err(e)) => Some(Err(e)),
+        None => None,
+    };
+    Some(match nft {
+        Some(Ok(chains)) if !chains.is_empty() => FirewallReport {
+            backend: "nftables".to_string(),
+            chains,
+            ..Default::default()
+        },
+        nft => {
+            let mut report = FirewallReport {
+                backend: "iptables".to_string(),
+                ..Default::default()
+            };
+            let mut installed = false;
+            for (family, program) in [("ip", "iptables-save"), ("ip6", "ip6tables-save")] {
+                match run_tool(program, &["-c"], COMMAND_TIMEOUT).await {
+                    Some(Ok(saved)) => report.chains.extend(parse_iptables_save(family, &saved)),
+                    Some(Err(e)) => report.error = Some(e),
+                    None => continue,
+                }
+                installed = true;
+            }
+            match nft {
+                // nft installé mais vide, sans iptables : aucune règle
+                Some(Ok(_)) if !installed => report.backend = "nftables".to_string(),
+                Some(Err(e)) if !installed => report.error = Some(e),
+                None if !installed => return None,
+                _ => {}
+            }
+            report
+        }
+    })
+}
+
+/// Décode `nft -j list ruleset` : chaînes de chaque table, avec leur politique, et
+/// règles rendues en syntaxe nft, compteurs `counter` extraits.
+/// Les ensembles, maps et flowtables sont ignorés.
+fn parse_nft(ruleset: &str) -> Result<Vec<FirewallChain>, String> {
+    #[derive(Deserialize)]
+    struct Ruleset {
+        #[serde(default)]
+        nftables: Vec<HashMap<String, Value>>,
+    }
+    #[derive(Deserialize)]
+    struct Chain {
+        family: String,
+        table: String,
+        name: String,
+        policy: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Rule {
+        family: String,
+        table: String,
+        chain: String,
+        #[serde(default)]
+        expr: Vec<Value>,
+        comment: Option<String>,
+    }
+
+    let ruleset: Ruleset =
+        serde_json::from_str(ruleset).map_err(|e| format!("unreadable nft -j output: {}", e))?;
+    let mut chains: Vec<FirewallChain> = Vec::new();
+    for object in ruleset.nftables {
+        if let Some(Ok(chain)) = object
+            .get("chain")
+            .cloned()
+            .map(serde_json::from_value::<Chain>)
+        {
+            chains.push(FirewallChain {
+                family: chain.family,
+                table: chain.table,
+                name: chain.name,
+                policy: chain.policy,
+                ..Default::default()
+            });
+        } else if let Some(Ok(rule)) = object
+            .get("rule")
+            .cloned()
+            .map(serde_json::from_value::<Rule>)
+        {
+            let Some(chain) = chains.iter_mut().rev().find(|chain| {
+                chain.family == rule.family && chain.table == rule.table && chain.name == rule.chain
+            }) else {
+                continue;
+            };
+            let mut statements = Vec::new();
+            let (mut packets, mut bytes) = (None, None);
+            for statement in &rule.expr {
+                match statement.get("counter") {
+                    Some(counter) if counter.is_object() => {
+                        packets = counter["packets"].as_u64();
+                        bytes = counter["bytes"].as_u64();
+                    }
+                    _ => statements.push(render_statement(statement)),
+                }
+            }
+            if let Some(comment) = rule.comment {
+                statements.push(format!("comment \"{}\"", comment));
+            }
+            chain.rules.push(FirewallRule {
+                rule: statements.join(" "),
+                packets,
+                bytes,
+                added: false,
+            });
+        }
+    }
+    Ok(chains)
+}
+
+/// Instruction d'une règle nft (`match`, verdict, `log`, `limit`, NAT...) ; les formes
+/// non reconnues restent en JSON compact, ce qui suffit à la comparaison avec la référence.
+fn render_statement(statement: &Value) -> String {
+    let Some((key, value)) = statement
+        .as_object()
+        .and_then(|object| object.iter().next())
+    else {
+        return statement.to_string();
+    };
+    let text = |value: &Value| value.as_str().map(str::to_string);
+    let parts = match key.as_str() {
+        "match" => {
+            let left = render_expr(&value["left"]);
+            // Les noms d'interface sont des chaînes entre guillemets dans la syntaxe nft.
+            let right = match &value["right"] {
+                Value::String(name) if left.ends_with("ifname") => format!("\"{}\"", name),
+                right => render_expr(right),
+            };
+            match value["op"].as_str() {
+                None | Some("==") | Some("in") => vec![left, right],
+                Some(op) => vec![left, op.to_string(), right],
+            }
+        }
+        "jump" | "goto" => vec![key.clone(), text(&value["target"]).unwrap_or_default()],
+        "counter" => vec!["counter name".to_string(), render_expr(value)],
+        "log" => {
+            let mut parts = vec!["log".to_string()];
+            if let Some(prefix) = text(&value["prefix"]) {
+                parts.push(format!("prefix \"{}\"", prefix));
+            }
+            if let Some(level) = text(&value["level"]) {
+                parts.push(format!("level {}", level));
+            }
+            if let Some(group) = value["group"].as_u64() {
+                parts.push(format!("group {}", group));
+            }
+            parts
+        }
+        "limit" => {
+            let mut parts = vec![
+                "limit rate".to_string(),
+                format!(
+                    "{}{}{}/{}",
+                    if value["inv"].as_bool() == Some(true) {
+                        "over "
+                    } else {
+                        ""
+                    },
+                    render_expr(&value["rate"]),
+                    text(&value["rate_unit"])
+                        .map(|unit| format!(" {}", unit))
+                        .unwrap_or_default(),
+                    text(&value["per"]).unwrap_or_default()
+                ),
+            ];
+            if let Some(burst) = value["burst"].as_u64().filter(|burst| *burst > 0) {
+                let unit = text(&value["burst_unit"]).unwrap_or_else(|| "packets".to_string());
+                parts.push(format!("burst {} {}", burst, unit));
+            }
+            parts
+        }
+        "reject" => match text(&value["type"]) {
+            Some(kind) if kind == "tcp reset" => vec!["reject with tcp reset".to_string()],
+            Some(kind) => vec![format!(
+                "reject with {} {}",
+                kind,
+                text(&value["expr"]).unwrap_or_default()
+            )
+            .trim_end()
+            .to_string()],
+            None => vec!["reject".to_string()],
+        },
+        "snat" | "dnat" | "masquerade" | "redirect" => {
+            let mut target = value.get("addr").map(render_expr).unwrap_or_default();
+            if let Some(port) = value.get("port") {
+                target = format!("{}:{}", target, render_expr(port));
+            }
+            match target.is_empty() {
+                true => vec![key.clone()],
+                false => vec![key.clone(), "to".to_string(), target],
+            }
+        }
+        "mangle" => vec![
+            render_expr(&value["key"]),
+            "set".to_string(),
+            render_expr(&value["value"]),
+        ],
+        _ if value.is_null() => vec![key.clone()],
+        _ => return statement.to_string(),
+    };
+    parts.join(" ")
+}
+
+/// Expression nft : sélecteur (`tcp dport`, `ct state`, `iifname`...) ou valeur
+/// (adresse, préfixe, intervalle, ensemble anonyme `{ 22, 443 }`).
+fn render_expr(expr: &Value) -> String {
+    let Some((key, value)) = expr.as_object().and_then(|object| object.iter().next()) else {
+        return match expr {
+            Value::String(text) => text.clone(),
+            // Plusieurs drapeaux : `ct state established,related`
+            Value::Array(flags) => flags.iter().map(render_expr).collect::<Vec<_>>().join(","),
+            other => other.to_string(),
+        };
+    };
+    let field = |name: &str| value[name].as_str().unwrap_or_default().to_string();
+    match key.as_str() {
+        "payload" if value.get("protocol").is_some() => {
+            format!("{} {}", field("protocol"), field("field"))
+        }
+        "payload" => format!(
+            "@{},{},{}",
+            field("base"),
+            render_expr(&value["offset"]),
+            render_expr(&value["len"])
+        ),
+        // Clés `meta` que nft affiche sans préfixe.
+        "meta" => match field("key").as_str() {
+            key @ ("iif" | "oif" | "iifname" | "oifname" | "iiftype" | "oiftype" | "mark"
+            | "skuid" | "skgid" | "rtclassid" | "cpu" | "cgroup") => key.to_string(),
+            key => format!("meta {}", key),
+        },
+        "ct" => match value["dir"].as_str() {
+            Some(dir) => format!("ct {} {}", dir, field("key")),
+            None => format!("ct {}", field("key")),
+        },
+        "set" => match value {
+            Value::Array(items) => format!(
+                "{{ {} }}",
+                items.iter().map(render_expr).collect::<Vec<_>>().join(", ")
+            ),
+            item => format!("{{ {} }}", render_expr(item)),
+        },
+        "prefix" => format!(
+            "{}/{}",
+            render_expr(&value["addr"]),
+            render_expr(&value["len"])
+        ),
+        "range" => format!("{}-{}", render_expr(&value[0]), render_expr(&value[1])),
+        "concat" => value
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(render_expr)
+                    .collect::<Vec<_>>()
+                    .join(" . ")
+            })
+            .unwrap_or_default(),
+        "&" | "|" | "^" | "<<" | ">>" => format!(
+            "{} {} {}",
+            render_expr(&value[0]),
+            key,
+            render_expr(&value[1])
+        ),
+        _ => expr.to_string(),
+    }
+}
+
+/// Décode `iptables-save -c` : `*table`, `:CHAÎNE POLITIQUE [paquets:octets]`
+/// puis `[paquets:octets] -A CHAÎNE règle`.
+fn parse_iptables_save(family: &str, saved: &str) -> Vec<FirewallChain> {
+    let counters = |field: &str| -> Option<(u64, u64)> {
+        let (packets, bytes) = field
+            .strip_prefix('[')?
+            .strip_suffix(']')?
+            .split_once(':')?;
+        Some((packets.parse().ok()?, bytes.parse().ok()?))
+    };
+
+    let mut chains: Vec<FirewallChain> = Vec::new();
+    let mut table = String::new();
+    for line in saved.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('*') {
+            table = name.to_string();
+        } else if let Some(declaration) = line.strip_prefix(':') {
+            let mut fields = declaration.split_whitespace();
+            chains.push(FirewallChain {
+                family: family.to_string(),
+                table: table.clone(),
+                name: fields.next().unwrap_or_default().to_string(),
+                // Les chaînes utilisateur n'ont pas de politique (`-`).
+                policy: fields
+                    .next()
+                    .filter(|policy| *policy != "-")
+                    .map(|policy| policy.to_lowercase()),
+                ..Default::default()
+            });
+        } else {
+            let (counted, rule) = match line.split_once(' ') {
+                Some((field, rule)) if field.starts_with('[') => (counters(field), rule),
+                _ => (None, line),
+            };
+            let Some((name, rule)) = rule
+                .strip_prefix("-A ")
+                .and_then(|rule| rule.split_once(' '))
+            else {
+                continue;
+            };
+            if let Some(chain) = chains
+                .iter_mut()
+                .rev()
+                .find(|chain| chain.table == table && chain.name == name)
+            {
+                chain.rules.push(FirewallRule {
+                    rule: rule.to_string(),
+                    packets: counted.map(|(packets, _)| packets),
+                    bytes: counted.map(|(_, bytes)| bytes),
+                    added: false,
+                });
+            }
+        }
+    }
+    chains
+}
+
+/// Politiques et règles du jeu courant, sans compteurs, une par ligne.
+fn ruleset_lines(chains: &[FirewallChain]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for chain in chains {
+        let prefix = format!("{} {} {}", chain.family, chain.table, chain.name);
+        if let Some(policy) = &chain.policy {
+            lines.push(format!("{}: policy {}", prefix, policy));
+        }
+        for rule in &chain.rules {
+            lines.push(format!("{}: {}", prefix, rule.rule));
+        }
+    }
+    lines
+}
+
+fn write_baseline(
+    report: &FirewallReport,
+    baseline_file: &str,
+) -> std::io::Result<FirewallBaseline> {
+    let baseline = FirewallBaseline {
+        created: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        rules: ruleset_lines(&report.chains),
+    };
+    write_state_file(
+        baseline_file,
+        &serde_json::to_string_pretty(&baseline).unwrap_or_default(),
+    )?;
+    Ok(baseline)
+}
+
+fn compare_with_baseline(report: &mut FirewallReport, baseline_file: &str) {
+    let baseline = match fs::read_to_string(baseline_file) {
+        Ok(content) => match serde_json::from_str::<FirewallBaseline>(&content) {
+            Ok(baseline) => baseline,
+            Err(e) => {
+                warn!(
+                    "Ignoring unreadable firewall baseline {}: {}",
+                    baseline_file, e
+                );
+                return;
+            }
+        },
+        Err(e) => {
+            debug!("No firewall baseline in {}: {}", baseline_file, e);
+            return;
+        }
+    };
+    apply_baseline(report, &baseline);
+}
+
+/// Empreinte du jeu de règles (12 premiers caractères hexadécimaux de son SHA-256),
+/// exigée pour confirmer le remplacement de la référence.
+fn fingerprint(lines: &[String]) -> String {
+    openssl::sha::sha256(lines.join("\n").as_bytes())
+        .iter()
+        .take(6)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Marque les règles et politiques absentes de la référence et liste celles qui ont disparu
+/// (une règle présente deux fois doit l'être deux fois dans la référence).
+fn apply_baseline(report: &mut FirewallReport, baseline: &FirewallBaseline) {
+    let mut expected: HashMap<&str, usize> = HashMap::new();
+    for line in &baseline.rules {
+        *expected.entry(line.as_str()).or_default() += 1;
+    }
+    let mut added = Vec::new();
+    for line in ruleset_lines(&report.chains) {
+        match expected.get_mut(line.as_str()) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added.push(line),
+        }
+    }
+    report.removed = baseline
+        .rules
+        .iter()
+        .filter(|line| {
+            expected.get_mut(line.as_str()).is_some_and(|count| {
+                let missing = *count > 0;
+                *count = count.saturating_sub(1);
+                missing
+            })
+        })
+        .cloned()
+        .collect();
+
+    for chain in &mut report.chains {
+        let prefix = format!("{} {} {}", chain.family, chain.table, chain.name);
+        if let Some(policy) = &chain.policy {
+            chain.policy_changed = added.contains(&format!("{}: policy {}", prefix, policy));
+        }
+        for rule in &mut chain.rules {
+            let line = format!("{}: {}", prefix, rule.rule);
+            if let Some(index) = added.iter().position(|added| *added == line) {
+                added.swap_remove(index);
+                rule.added = true;
+                report.added += 1;
+            }
+        }
+    }
+    report.baseline = Some(baseline.created.clone());
+}
+
+/// Alerte si le jeu de règles s'écarte de la référence.
+pub fn firewall_alerts(report: &FirewallReport) -> Vec<Alert> {
+    let policies = report
+        .chains
+        .iter()
+        .filter(|chain| chain.policy_changed)
+        .count();
+    if report.added == 0 && report.removed.is_empty() && policies == 0 {
+        return Vec::new();
+    }
+    vec![Alert::new(
+        &format!("firewall:{}", report.backend),
+        CheckState::Warning,
+        format!(
+            "ruleset changed since baseline of {}: {} rule(s) added, {} removed, {} policy change(s)",
+            report.baseline.as_deref().unwrap_or("?"),
+            report.added,
+            report.removed.len(),
+            policies
+        ),
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rulesets_and_detect_drift() {
+        let nft = r#"{"nftables": [
+            {"metainfo": {"version": "1.0.9", "json_schema_version": 1}},
+            {"table": {"family": "inet", "name": "filter", "handle": 1}},
+            {"set": {"family": "inet", "name": "blocked", "table": "filter", "type": "ipv4_addr", "handle": 2, "elem": ["192.0.2.1"]}},
+            {"chain": {"family": "inet", "table": "filter", "name": "input", "handle": 3, "type": "filter", "hook": "input", "prio": 0, "policy": "drop"}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 4, "expr": [
+                {"match": {"op": "in", "left": {"ct": {"key": "state"}}, "right": ["established", "related"]}},
+                {"accept": null}]}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 5, "expr": [
+                {"match": {"op": "==", "left": {"meta": {"key": "iifname"}}, "right": "lo"}},
+                {"accept": null}]}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 6, "expr": [
+                {"match": {"op": "==", "left": {"payload": {"protocol": "tcp", "field": "dport"}}, "right": 22}},
+                {"counter": {"packets": 10, "bytes": 600}},
+                {"accept": null}]}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 7, "comment": "web", "expr": [
+                {"match": {"op": "==", "left": {"payload": {"protocol": "ip", "field": "saddr"}}, "right": {"set": [{"prefix": {"addr": "10.0.0.0", "len": 8}}, {"range": ["192.168.1.10", "192.168.1.20"]}, "172.16.0.1"]}}},
+                {"match": {"op": "==", "left": {"payload": {"protocol": "tcp", "field": "dport"}}, "right": {"set": [80, 443, 8080]}}},
+                {"log": {"prefix": "web "}},
+                {"limit": {"rate": 10, "per": "second", "burst": 5}},
+                {"accept": null}]}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 8, "expr": [
+                {"match": {"op": "!=", "left": {"payload": {"protocol": "ip", "field": "saddr"}}, "right": "@blocked"}},
+                {"xt": {"type": "match", "name": "conntrack"}},
+                {"reject": {"type": "tcp reset"}}]}}
+        ]}"#;
+        let chains = parse_nft(nft).unwrap();
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].family, "inet");
+        assert_eq!(chains[0].policy.as_deref(), Some("drop"));
+        let rules: Vec<&str> = chains[0]
+            .rules
+            .iter()
+            .map(|rule| rule.rule.as_str())
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                "ct state established,related accept",
+                "iifname \"lo\" accept",
+                "tcp dport 22 accept",
+                "ip saddr { 10.0.0.0/8, 192.168.1.10-192.168.1.20, 172.16.0.1 } \
+                 tcp dport { 80, 443, 8080 } log prefix \"web \" limit rate 10/second \
+                 burst 5 packets accept comment \"web\"",
+                "ip saddr != @blocked {\"xt\":{\"name\":\"conntrack\",\"type\":\"match\"}} \
+                 reject with tcp reset",
+            ]
+        );
+        assert_eq!(chains[0].rules[2].packets, Some(10));
+        assert_eq!(chains[0].rules[0].packets, None);
+        assert!(parse_nft("not json").is_err());
+        assert_eq!(fingerprint(&ruleset_lines(&chains)).len(), 12);
+
+        let saved = "# Generated by iptables-save v1.8.9
+*filter
+:INPUT ACCEPT [1234:56789]
+:FORWARD DROP [0:0]
+:DOCKER - [0:0]
+[42:2520] -A INPUT -p tcp -m tcp --dport 22 -j ACCEPT
+[0:0] -A DOCKER -d 172.17.0.2/32 -p tcp -j ACCEPT
+COMMIT
+";
+        let iptables = parse_iptables_save("ip", saved);
+        assert_eq!(iptables.len(), 3);
+        assert_eq!(iptables[0].policy.as_deref(), Some("accept"));
+        assert_eq!(iptables[2].policy, None);
+        assert_eq!(
+            iptables[0].rules[0].rule,
+            "-p tcp -m tcp --dport 22 -j ACCEPT"
+        );
+        assert_eq!(iptables[0].rules[0].bytes, Some(2520));
+
+        let baseline = FirewallBaseline {
+            created: "2026-01-01 00:00:00".to_string(),
+            rules: vec![
+                "inet filter input: policy accept".to_string(),
+                "inet filter input: ct state established,related accept".to_string(),
+                "inet filter input: iifname \"lo\" accept".to_string(),
+                "inet filter input: tcp dport 22 accept".to_string(),
+                "inet filter input: tcp dport 443 accept".to_string(),
+            ],
+        };
+        let mut report = FirewallReport {
+            backend: "nftables".to_string(),
+            chains,
+            ..Default::default()
+        };
+        apply_baseline(&mut report, &baseline);
+        assert_eq!(report.added, 2);
+        assert!(!report.chains[0].rules[2].added);
+        assert!(report.chains[0].rules[3].added);
+        assert!(report.chains[0].policy_changed);
+        assert_eq!(
+            report.removed,
+            vec![
+                "inet filter input: policy accept",
+                "inet filter input: tcp dport 443 accept"
+            ]
+        );
+        assert_eq!(firewall_alerts(&report).len(), 1);
+    }
+}
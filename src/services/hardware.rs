@@ -1,6 +1,9 @@
-use crate::models::errors::SystemError;
+use crate::models::{
+    errors::SystemError,
+    templates::{MemoryBreakdown, VmStat},
+};
 use log::{error, warn};
-use std::{ffi::CString, fs};
+use std::{collections::HashMap, ffi::CString, fs};
 
 pub fn get_system_version() -> String {
     fs::read_to_string("/etc/os-release")
@@ -71,18 +74,159 @@ fn extract_memory_value(meminfo: &str, key: &str) -> Result<u64, SystemError> {
         })
 }
 
-pub fn get_disk_info(path: &str) -> Result<(u64, u64), SystemError> {
-    let c_path = CString::new(path).map_err(|_| {
-        error!("{}", SystemError::DiskInfoUnavailable.message());
-        SystemError::DiskInfoUnavailable
+/// Détail de `/proc/meminfo`, en octets.
+pub fn get_memory_breakdown() -> Result<MemoryBreakdown, SystemError> {
+    let meminfo = fs::read_to_string("/proc/meminfo").map_err(|_| {
+        error!("{}", SystemError::MemoryInfoUnavailable.message());
+        SystemError::MemoryInfoUnavailable
+    })?;
+    Ok(parse_meminfo(&meminfo))
+}
+
+fn parse_meminfo(meminfo: &str) -> MemoryBreakdown {
+    // `Clé:   valeur [kB]` ; les compteurs HugePages_* sont des nombres de pages.
+    let values: HashMap<&str, u64> = meminfo
+        .lines()
+        .filter_map(|line| {
+            let (key, rest) = line.split_once(':')?;
+            let mut fields = rest.split_whitespace();
+            let value = fields.next()?.parse::<u64>().ok()?;
+            Some((key, if fields.next() == Some("kB") { value * 1024 } else { value }))
+        })
+        .collect();
+    let value = |key: &str| values.get(key).copied().unwrap_or(0);
+
+    let total = value("MemTotal");
+    let free = value("MemFree");
+    let buffers = value("Buffers");
+    // Comme `free` : le cache inclut la partie récupérable du slab.
+    let cached = value("Cached") + value("SReclaimable");
+    let slab = value("Slab");
+    let swap_total = value("SwapTotal");
+    let swap_free = value("SwapFree");
+    let hugepage_size = value("Hugepagesize");
+
+    MemoryBreakdown {
+        total,
+        free,
+        available: value("MemAvailable"),
+        used: total.saturating_sub(free + buffers + cached),
+        buffers,
+        cached,
+        shared: value("Shmem"),
+        slab,
+        slab_unreclaimable: value("SUnreclaim"),
+        dirty: value("Dirty"),
+        writeback: value("Writeback"),
+        hugepages_total: value("HugePages_Total") * hugepage_size,
+        hugepages_free: value("HugePages_Free") * hugepage_size,
+        swap_total,
+        swap_free,
+        swap_used: swap_total.saturating_sub(swap_free),
+        swap_cached: value("SwapCached"),
+    }
+}
+
+/// Compteurs de `/proc/vmstat` utiles au diagnostic mémoire (cumulés depuis le démarrage).
+pub fn get_vmstat() -> Result<VmStat, SystemError> {
+    let vmstat = fs::read_to_string("/proc/vmstat").map_err(|_| {
+        error!("{}", SystemError::MemoryInfoUnavailable.message());
+        SystemError::MemoryInfoUnavailable
     })?;
-    let mut statvfs: libc::statvfs = unsafe { std::mem::zeroed() };
+    let values: HashMap<&str, u64> = vmstat
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key, value.trim().parse().ok()?))
+        })
+        .collect();
+    let value = |key: &str| values.get(key).copied().unwrap_or(0);
+
+    Ok(VmStat {
+        pgmajfault: value("pgmajfault"),
+        pswpin: value("pswpin"),
+        pswpout: value("pswpout"),
+        oom_kill: value("oom_kill"),
+    })
+}
+
+/// Une ligne de `/proc/self/mounts`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: String,
+    pub fstype: String,
+    pub options: Vec<String>,
+}
 
-    (unsafe { libc::statvfs(c_path.as_ptr(), &mut statvfs) } == 0)
-        .then(|| {
+impl MountEntry {
+    pub fn is_read_only(&self) -> bool {
+        self.options.iter().any(|option| option == "ro")
+    }
+}
+
+/// Systèmes de fichiers montés, dans l'ordre de `/proc/self/mounts`.
+pub fn read_mounts() -> Vec<MountEntry> {
+    parse_mounts(&fs::read_to_string("/proc/self/mounts").unwrap_or_default())
+}
+
+fn parse_mounts(content: &str) -> Vec<MountEntry> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [device, mount_point, fstype, options, ..] = fields[..] else {
+                return None;
+            };
+            Some(MountEntry {
+                device: unescape_mount_field(device),
+                mount_point: unescape_mount_field(mount_point),
+                fstype: fstype.to_string(),
+                options: options.split(',').map(str::to_string).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Le noyau encode espaces, tabulations, retours à la ligne et `\` en octal (`\040`).
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escape = bytes.get(index + 1..index + 4).filter(|digits| {
+            bytes[index] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit))
+        });
+        match escape {
+            Some(digits) => {
+                let value = digits
+                    .iter()
+                    .fold(0u32, |value, digit| value * 8 + u32::from(digit - b'0'));
+                decoded.push(value as u8);
+                index += 4;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// `statvfs(3)` sur un point de montage.
+pub fn filesystem_stats(path: &str) -> Option<libc::statvfs> {
+    let c_path = CString::new(path).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    (unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } == 0).then_some(stat)
+}
+
+pub fn get_disk_info(path: &str) -> Result<(u64, u64), SystemError> {
+    filesystem_stats(path)
+        .map(|statvfs| {
             (
-                statvfs.f_bavail as u64 * statvfs.f_frsize as u64,
-                statvfs.f_blocks as u64 * statvfs.f_frsize as u64,
+                statvfs.f_bavail * statvfs.f_frsize,
+                statvfs.f_blocks * statvfs.f_frsize,
             )
         })
         .ok_or_else(|| {
@@ -146,3 +290,56 @@ pub fn get_network_traffic() -> Result<(u64, u64), SystemError> {
             result.map(|(rx, tx)| (rx_total + rx, tx_total + tx))
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mounts() {
+        let mounts = parse_mounts(
+            "/dev/sda1 / ext4 rw,relatime 0 0
+/dev/sdb1 /mnt/my\\040disk vfat ro,noatime 0 0
+proc /proc proc rw 0 0
+",
+        );
+        assert_eq!(mounts.len(), 3);
+        assert_eq!(mounts[1].mount_point, "/mnt/my disk");
+        assert!(mounts[1].is_read_only());
+        assert!(!mounts[0].is_read_only());
+        assert_eq!(unescape_mount_field("a\\134b\\011c\\9"), "a\\b\tc\\9");
+    }
+
+    #[test]
+    fn test_parse_meminfo_breakdown() {
+        let meminfo = "MemTotal:        8000000 kB
+MemFree:         1000000 kB
+MemAvailable:    5000000 kB
+Buffers:          200000 kB
+Cached:          3000000 kB
+SwapCached:        10000 kB
+Shmem:            150000 kB
+Slab:             400000 kB
+SReclaimable:     300000 kB
+SUnreclaim:       100000 kB
+SwapTotal:       2000000 kB
+SwapFree:        1500000 kB
+Dirty:               120 kB
+Writeback:             0 kB
+HugePages_Total:       4
+HugePages_Free:        1
+Hugepagesize:       2048 kB
+";
+        let memory = parse_meminfo(meminfo);
+        assert_eq!(memory.total, 8_000_000 * 1024);
+        assert_eq!(memory.cached, 3_300_000 * 1024);
+        assert_eq!(memory.used, 3_500_000 * 1024);
+        assert_eq!(memory.swap_used, 500_000 * 1024);
+        assert_eq!(memory.hugepages_total, 4 * 2048 * 1024);
+        assert_eq!(memory.hugepages_free, 2048 * 1024);
+
+        let segments = memory.segments();
+        let percent: f64 = segments.iter().map(|segment| segment.percent).sum();
+        assert!((percent - 100.0).abs() < 0.01);
+    }
+}
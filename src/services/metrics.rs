@@ -0,0 +1,497 @@
+use crate::models::templates::{
+    CgroupUsage, CommandCheckStatus, ContainerStats, ContainerStatus, MemoryBreakdown,
+    ResourcePressure, VmStat,
+};
+use std::fmt::Write;
+
+/// Préfixe commun à toutes les métriques exportées.
+const PREFIX: &str = "monitor_server";
+
+/// Type d'une famille de métriques Prometheus.
+#[derive(Debug, Clone, Copy)]
+pub enum MetricKind {
+    Gauge,
+    Counter,
+}
+
+impl MetricKind {
+    fn label(self) -> &'static str {
+        match self {
+            MetricKind::Gauge => "gauge",
+            MetricKind::Counter => "counter",
+        }
+    }
+}
+
+/// Document au format d'exposition texte de Prometheus (`/metrics`).
+#[derive(Default)]
+pub struct Metrics {
+    output: String,
+}
+
+impl Metrics {
+    /// Ajoute une famille : lignes `HELP` et `TYPE`, puis un échantillon par jeu de labels.
+    /// Une famille sans échantillon n'est pas écrite.
+    pub fn family<'a>(
+        &mut self,
+        name: &str,
+        kind: MetricKind,
+        help: &str,
+        samples: impl IntoIterator<Item = (Vec<(&'a str, String)>, f64)>,
+    ) {
+        let mut samples = samples.into_iter().peekable();
+        if samples.peek().is_none() {
+            return;
+        }
+        let name = format!("{}_{}", PREFIX, name);
+        let _ = writeln!(self.output, "# HELP {} {}", name, help);
+        let _ = writeln!(self.output, "# TYPE {} {}", name, kind.label());
+        for (labels, value) in samples {
+            self.output.push_str(&name);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                    .collect();
+                let _ = write!(self.output, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(self.output, " {}", value);
+        }
+    }
+
+    /// Famille d'un seul échantillon, sans label.
+    pub fn single(&mut self, name: &str, kind: MetricKind, help: &str, value: f64) {
+        self.family(name, kind, help, [(Vec::new(), value)]);
+    }
+
+    pub fn render(self) -> String {
+        self.output
+    }
+}
+
+/// Échappe `\`, `"` et les retours à la ligne d'une valeur de label.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Répartition de la mémoire et du swap (`/proc/meminfo`) et compteurs de `/proc/vmstat`.
+pub fn memory_metrics(metrics: &mut Metrics, memory: &MemoryBreakdown, vmstat: Option<&VmStat>) {
+    let sample = |kind: &'static str, bytes: u64| (vec![("type", kind.to_string())], bytes as f64);
+    metrics.family(
+        "memory_bytes",
+        MetricKind::Gauge,
+        "Memory breakdown from /proc/meminfo.",
+        [
+            sample("total", memory.total),
+            sample("free", memory.free),
+            sample("available", memory.available),
+            sample("used", memory.used),
+            sample("buffers", memory.buffers),
+            sample("cached", memory.cached),
+            sample("shared", memory.shared),
+            sample("slab", memory.slab),
+            sample("slab_unreclaimable", memory.slab_unreclaimable),
+            sample("dirty", memory.dirty),
+            sample("writeback", memory.writeback),
+            sample("hugepages_total", memory.hugepages_total),
+            sample("hugepages_free", memory.hugepages_free),
+        ],
+    );
+    metrics.family(
+        "swap_bytes",
+        MetricKind::Gauge,
+        "Swap usage from /proc/meminfo.",
+        [
+            sample("total", memory.swap_total),
+            sample("free", memory.swap_free),
+            sample("used", memory.swap_used),
+            sample("cached", memory.swap_cached),
+        ],
+    );
+
+    if let Some(vmstat) = vmstat {
+        for (name, help, value) in [
+            (
+                "vmstat_pgmajfault_total",
+                "Major page faults since boot.",
+                vmstat.pgmajfault,
+            ),
+            (
+                "vmstat_pswpin_total",
+                "Pages swapped in since boot.",
+                vmstat.pswpin,
+            ),
+            (
+                "vmstat_pswpout_total",
+                "Pages swapped out since boot.",
+                vmstat.pswpout,
+            ),
+            (
+                "vmstat_oom_kill_total",
+                "Processes killed by the OOM killer since boot.",
+                vmstat.oom_kill,
+            ),
+        ] {
+            metrics.single(name, MetricKind::Counter, help, value as f64);
+        }
+    }
+}
+
+/// État et consommation de chaque conteneur (statistiques des conteneurs en cours
+/// d'exécution uniquement).
+pub fn container_metrics(metrics: &mut Metrics, containers: &[ContainerStatus]) {
+    let labels = |container: &ContainerStatus| {
+        vec![
+            ("endpoint", container.endpoint.clone()),
+            ("name", container.name.clone()),
+            ("id", container.id.clone()),
+        ]
+    };
+    metrics.family(
+        "container_running",
+        MetricKind::Gauge,
+        "1 if the container is running.",
+        containers.iter().map(|container| {
+            let mut labels = labels(container);
+            labels.push(("image", container.image.clone()));
+            (labels, f64::from(u8::from(container.state == "running")))
+        }),
+    );
+    metrics.family(
+        "container_restarts_total",
+        MetricKind::Counter,
+        "Restart count reported by the container runtime.",
+        containers
+            .iter()
+            .map(|container| (labels(container), container.restart_count as f64)),
+    );
+
+    let stats = |value: fn(&ContainerStats) -> f64| {
+        containers.iter().filter_map(move |container| {
+            Some((labels(container), value(container.stats.as_ref()?)))
+        })
+    };
+    metrics.family(
+        "container_cpu_percent",
+        MetricKind::Gauge,
+        "CPU usage relative to one CPU, as computed by docker stats.",
+        stats(|stats| stats.cpu_percent),
+    );
+    metrics.family(
+        "container_memory_usage_bytes",
+        MetricKind::Gauge,
+        "Memory usage excluding the inactive page cache.",
+        stats(|stats| stats.memory_usage as f64),
+    );
+    metrics.family(
+        "container_memory_limit_bytes",
+        MetricKind::Gauge,
+        "Memory limit of the container.",
+        stats(|stats| stats.memory_limit as f64),
+    );
+    metrics.family(
+        "container_network_receive_bytes_total",
+        MetricKind::Counter,
+        "Bytes received on all interfaces.",
+        stats(|stats| stats.network_rx as f64),
+    );
+    metrics.family(
+        "container_network_transmit_bytes_total",
+        MetricKind::Counter,
+        "Bytes sent on all interfaces.",
+        stats(|stats| stats.network_tx as f64),
+    );
+    metrics.family(
+        "container_block_read_bytes_total",
+        MetricKind::Counter,
+        "Bytes read from block devices.",
+        stats(|stats| stats.block_read as f64),
+    );
+    metrics.family(
+        "container_block_write_bytes_total",
+        MetricKind::Counter,
+        "Bytes written to block devices.",
+        stats(|stats| stats.block_write as f64),
+    );
+}
+
+/// Pressure Stall Information du système et de chaque cgroup suivi.
+pub fn pressure_metrics(
+    metrics: &mut Metrics,
+    system: Option<&ResourcePressure>,
+    cgroups: &[CgroupUsage],
+) {
+    let (averages, totals) = system
+        .map(|pressure| pressure_samples(Vec::new(), pressure))
+        .unwrap_or_default();
+    metrics.family(
+        "pressure_percent",
+        MetricKind::Gauge,
+        "Share of time some or all tasks stalled on a resource (/proc/pressure).",
+        averages,
+    );
+    metrics.family(
+        "pressure_stalled_seconds_total",
+        MetricKind::Counter,
+        "Total time some or all tasks stalled on a resource (/proc/pressure).",
+        totals,
+    );
+
+    let (averages, totals): (Vec<_>, Vec<_>) = cgroups
+        .iter()
+        .filter_map(|cgroup| {
+            let labels = vec![
+                ("cgroup", cgroup.name.clone()),
+                ("cgroup_type", cgroup.kind.clone()),
+            ];
+            Some(pressure_samples(labels, cgroup.pressure.as_ref()?))
+        })
+        .unzip();
+    metrics.family(
+        "cgroup_pressure_percent",
+        MetricKind::Gauge,
+        "Share of time some or all tasks of a cgroup stalled on a resource.",
+        averages.into_iter().flatten(),
+    );
+    metrics.family(
+        "cgroup_pressure_stalled_seconds_total",
+        MetricKind::Counter,
+        "Total time some or all tasks of a cgroup stalled on a resource.",
+        totals.into_iter().flatten(),
+    );
+}
+
+type Samples<'a> = Vec<(Vec<(&'a str, String)>, f64)>;
+
+/// Moyennes (`window` = avg10, avg60, avg300) et totaux, en secondes, de chaque ligne
+/// `some` / `full`.
+fn pressure_samples<'a>(
+    labels: Vec<(&'a str, String)>,
+    pressure: &ResourcePressure,
+) -> (Samples<'a>, Samples<'a>) {
+    let mut averages = Vec::new();
+    let mut totals = Vec::new();
+    for (resource, stats) in [
+        ("cpu", &pressure.cpu),
+        ("memory", &pressure.memory),
+        ("io", &pressure.io),
+    ] {
+        let Some(stats) = stats else {
+            continue;
+        };
+        for (kind, line) in [("some", &stats.some), ("full", &stats.full)] {
+            let Some(line) = line else {
+                continue;
+            };
+            let mut line_labels = labels.clone();
+            line_labels.push(("resource", resource.to_string()));
+            line_labels.push(("kind", kind.to_string()));
+            for (window, value) in [
+                ("avg10", line.avg10),
+                ("avg60", line.avg60),
+                ("avg300", line.avg300),
+            ] {
+                let mut labels = line_labels.clone();
+                labels.push(("window", window.to_string()));
+                averages.push((labels, value));
+            }
+            // `total` est en microsecondes
+            totals.push((line_labels, line.total as f64 / 1_000_000.0));
+        }
+    }
+    (averages, totals)
+}
+
+/// État, durée et perfdata des scripts de supervision.
+pub fn command_check_metrics(metrics: &mut Metrics, checks: &[CommandCheckStatus]) {
+    let labels = |check: &CommandCheckStatus| vec![("check", check.name.clone())];
+    metrics.family(
+        "command_check_state",
+        MetricKind::Gauge,
+        "Nagios state of a command check (0 OK, 1 WARNING, 2 CRITICAL, 3 UNKNOWN).",
+        checks
+            .iter()
+            .map(|check| (labels(check), f64::from(check.state.code()))),
+    );
+    metrics.family(
+        "command_check_duration_seconds",
+        MetricKind::Gauge,
+        "Run time of a command check.",
+        checks
+            .iter()
+            .map(|check| (labels(check), check.duration_ms as f64 / 1000.0)),
+    );
+    metrics.family(
+        "command_check_perfdata",
+        MetricKind::Gauge,
+        "Performance data reported by a command check, in its own unit.",
+        checks.iter().flat_map(|check| {
+            check.metrics.iter().map(|metric| {
+                let mut labels = labels(check);
+                labels.push(("label", metric.label.clone()));
+                labels.push(("unit", metric.unit.clone()));
+                (labels, metric.value)
+            })
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        checks::CheckState,
+        templates::{PressureLine, PressureStats},
+    };
+    use crate::services::command_checks::parse_perfdata;
+
+    #[test]
+    fn test_memory_metrics_exposition() {
+        let memory = MemoryBreakdown {
+            total: 8192,
+            swap_total: 1024,
+            ..Default::default()
+        };
+        let vmstat = VmStat {
+            oom_kill: 2,
+            ..Default::default()
+        };
+        let mut metrics = Metrics::default();
+        memory_metrics(&mut metrics, &memory, Some(&vmstat));
+        metrics.family(
+            "label_test",
+            MetricKind::Gauge,
+            "Escaping.",
+            [(vec![("name", "a\"b\\c".to_string())], 1.5)],
+        );
+        metrics.family("empty", MetricKind::Gauge, "Skipped.", []);
+        let output = metrics.render();
+
+        assert!(output.contains(
+            "# HELP monitor_server_memory_bytes Memory breakdown from /proc/meminfo.\n\
+             # TYPE monitor_server_memory_bytes gauge\n\
+             monitor_server_memory_bytes{type=\"total\"} 8192\n"
+        ));
+        assert!(output.contains("monitor_server_swap_bytes{type=\"total\"} 1024\n"));
+        assert!(output.contains(
+            "# TYPE monitor_server_vmstat_oom_kill_total counter\n\
+             monitor_server_vmstat_oom_kill_total 2\n"
+        ));
+        assert!(output.contains("monitor_server_label_test{name=\"a\\\"b\\\\c\"} 1.5\n"));
+        assert!(!output.contains("monitor_server_empty"));
+    }
+
+    #[test]
+    fn test_pressure_metrics() {
+        let pressure = ResourcePressure {
+            cpu: Some(PressureStats {
+                some: Some(PressureLine {
+                    avg10: 1.5,
+                    avg60: 0.75,
+                    avg300: 0.25,
+                    total: 2_500_000,
+                }),
+                full: None,
+            }),
+            memory: None,
+            io: None,
+        };
+        let cgroup = CgroupUsage {
+            name: "nginx".to_string(),
+            kind: "service".to_string(),
+            pressure: Some(pressure.clone()),
+            ..Default::default()
+        };
+        let mut metrics = Metrics::default();
+        pressure_metrics(&mut metrics, Some(&pressure), &[cgroup]);
+        let output = metrics.render();
+
+        assert!(output.contains(
+            "monitor_server_pressure_percent{resource=\"cpu\",kind=\"some\",window=\"avg60\"} 0.75\n"
+        ));
+        assert!(output.contains(
+            "monitor_server_pressure_stalled_seconds_total{resource=\"cpu\",kind=\"some\"} 2.5\n"
+        ));
+        assert!(output.contains(
+            "monitor_server_cgroup_pressure_percent{cgroup=\"nginx\",cgroup_type=\"service\",resource=\"cpu\",kind=\"some\",window=\"avg10\"} 1.5\n"
+        ));
+        assert!(!output.contains("kind=\"full\""));
+    }
+
+    #[test]
+    fn test_command_check_metrics() {
+        let check = CommandCheckStatus {
+            name: "disk".to_string(),
+            state: CheckState::Warning,
+            exit_code: Some(1),
+            output: "DISK WARNING".to_string(),
+            long_output: String::new(),
+            metrics: parse_perfdata("/=2643MB;5948;5958;0;5968 'inode ratio'=12%"),
+            duration_ms: 250,
+        };
+        let mut metrics = Metrics::default();
+        command_check_metrics(&mut metrics, &[check]);
+        let output = metrics.render();
+
+        assert!(output.contains("monitor_server_command_check_state{check=\"disk\"} 1\n"));
+        assert!(
+            output.contains("monitor_server_command_check_duration_seconds{check=\"disk\"} 0.25\n")
+        );
+        assert!(output.contains(
+            "monitor_server_command_check_perfdata{check=\"disk\",label=\"/\",unit=\"MB\"} 2643\n"
+        ));
+        assert!(output.contains(
+            "monitor_server_command_check_perfdata{check=\"disk\",label=\"inode ratio\",unit=\"%\"} 12\n"
+        ));
+    }
+
+    #[test]
+    fn test_container_metrics() {
+        let web = ContainerStatus {
+            endpoint: "local".to_string(),
+            id: "0123456789ab".to_string(),
+            name: "web".to_string(),
+            image: "nginx:1.27".to_string(),
+            state: "running".to_string(),
+            restart_count: 2,
+            stats: Some(ContainerStats {
+                cpu_percent: 12.5,
+                memory_usage: 1024,
+                memory_limit: 4096,
+                network_rx: 10,
+                network_tx: 20,
+                block_read: 30,
+                block_write: 40,
+            }),
+            ..Default::default()
+        };
+        let stopped = ContainerStatus {
+            name: "batch".to_string(),
+            state: "exited".to_string(),
+            ..Default::default()
+        };
+        let mut metrics = Metrics::default();
+        container_metrics(&mut metrics, &[web, stopped]);
+        let output = metrics.render();
+
+        assert!(output.contains(
+            "monitor_server_container_running{endpoint=\"local\",name=\"web\",id=\"0123456789ab\",image=\"nginx:1.27\"} 1\n"
+        ));
+        assert!(output.contains(
+            "monitor_server_container_running{endpoint=\"\",name=\"batch\",id=\"\",image=\"\"} 0\n"
+        ));
+        assert!(output.contains(
+            "monitor_server_container_cpu_percent{endpoint=\"local\",name=\"web\",id=\"0123456789ab\"} 12.5\n"
+        ));
+        // Pas de statistiques pour un conteneur arrêté
+        assert_eq!(
+            output
+                .matches("monitor_server_container_memory_usage_bytes{")
+                .count(),
+            1
+        );
+    }
+}
@@ -1,3 +1,29 @@
+pub mod cache;
+pub mod cgroups;
+pub mod command_checks;
+pub mod compose;
+pub mod disk_forecast;
+pub mod disk_io;
+pub mod dns;
 pub mod docker;
+pub mod docker_actions;
+pub mod docker_disk;
+pub mod docker_endpoints;
+pub mod docker_events;
+pub mod docker_logs;
+pub mod exec;
+pub mod firewall;
 pub mod hardware;
+pub mod metrics;
+pub mod network;
+pub mod pressure;
+pub mod processes;
+pub mod public_ip;
 pub mod service_checker;
+pub mod smart;
+pub mod sockets;
+pub mod state_file;
+pub mod storage;
+pub mod tls;
+pub mod users;
+pub mod wireguard;
@@ -0,0 +1,304 @@
+use crate::models::templates::{NetworkInterface, NetworkReport, ResolverConfig, Route};
+use get_if_addrs::{get_if_addrs, IfAddr};
+use log::warn;
+use std::{
+    fs,
+    net::{Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
+
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// Indicateurs de route (`include/uapi/linux/route.h`).
+const RTF_UP: u32 = 0x0001;
+const RTF_REJECT: u32 = 0x0200;
+const RTF_LOCAL: u32 = 0x8000_0000;
+
+/// Résolveur local de systemd-resolved et la liste de ses serveurs amont.
+const RESOLVED_STUB: &str = "127.0.0.53";
+const RESOLVED_UPSTREAM: &str = "/run/systemd/resolve/resolv.conf";
+
+/// Interfaces et leurs adresses, routes IPv4 / IPv6 et résolveurs DNS.
+///
+/// L'interface principale est `primary_interface` si elle existe, sinon celle de la
+/// route IPv4 par défaut de plus faible métrique.
+pub fn network_report(primary_interface: &str) -> NetworkReport {
+    let mut interfaces = read_interfaces();
+    let mut routes = parse_ipv4_routes(&fs::read_to_string("/proc/net/route").unwrap_or_default());
+    routes.extend(parse_ipv6_routes(
+        &fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default(),
+    ));
+    routes
+        .sort_by(|a, b| (!a.default, &a.family, a.metric).cmp(&(!b.default, &b.family, b.metric)));
+
+    let primary = select_primary(&interfaces, &routes, primary_interface);
+    for interface in &mut interfaces {
+        interface.primary = primary.as_deref() == Some(interface.name.as_str());
+    }
+
+    let mut resolvers =
+        parse_resolv_conf(&fs::read_to_string("/etc/resolv.conf").unwrap_or_default());
+    if resolvers
+        .nameservers
+        .iter()
+        .any(|server| server == RESOLVED_STUB)
+    {
+        resolvers.upstream =
+            parse_resolv_conf(&fs::read_to_string(RESOLVED_UPSTREAM).unwrap_or_default())
+                .nameservers;
+    }
+
+    NetworkReport {
+        interfaces,
+        routes,
+        resolvers,
+        primary,
+    }
+}
+
+/// Toutes les interfaces de `/sys/class/net`, avec leurs adresses (`getifaddrs`).
+fn read_interfaces() -> Vec<NetworkInterface> {
+    let addresses = get_if_addrs().unwrap_or_default();
+    let mut names: Vec<String> = fs::read_dir(SYS_CLASS_NET)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let dir = Path::new(SYS_CLASS_NET).join(&name);
+            let read = |file: &str| {
+                fs::read_to_string(dir.join(file))
+                    .map(|value| value.trim().to_string())
+                    .ok()
+            };
+            NetworkInterface {
+                addresses: addresses
+                    .iter()
+                    .filter(|address| address.name == name)
+                    .map(|address| match &address.addr {
+                        IfAddr::V4(v4) => {
+                            format!("{}/{}", v4.ip, prefix_length(&v4.netmask.octets()))
+                        }
+                        IfAddr::V6(v6) => {
+                            format!("{}/{}", v6.ip, prefix_length(&v6.netmask.octets()))
+                        }
+                    })
+                    .collect(),
+                state: read("operstate").unwrap_or_else(|| "unknown".to_string()),
+                mac: read("address").filter(|mac| mac != "00:00:00:00:00:00"),
+                mtu: read("mtu").and_then(|mtu| mtu.parse().ok()),
+                rx_bytes: read("statistics/rx_bytes")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                tx_bytes: read("statistics/tx_bytes")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                loopback: name == "lo",
+                primary: false,
+                name,
+            }
+        })
+        .collect()
+}
+
+fn prefix_length(netmask: &[u8]) -> u32 {
+    netmask.iter().map(|byte| byte.count_ones()).sum()
+}
+
+/// `Iface Destination Gateway Flags RefCnt Use Metric Mask ...`, adresses en
+/// hexadécimal dans l'ordre de la machine.
+fn parse_ipv4_routes(content: &str) -> Vec<Route> {
+    let address = |hex: &str| -> Option<Ipv4Addr> {
+        Some(Ipv4Addr::from(
+            u32::from_str_radix(hex, 16).ok()?.to_ne_bytes(),
+        ))
+    };
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+            if flags & RTF_UP == 0 || flags & RTF_REJECT != 0 {
+                return None;
+            }
+            let destination = address(fields.get(1)?)?;
+            let gateway = address(fields.get(2)?)?;
+            let prefix = prefix_length(&address(fields.get(7)?)?.octets());
+            Some(Route {
+                family: "ipv4".to_string(),
+                destination: format!("{}/{}", destination, prefix),
+                gateway: (!gateway.is_unspecified()).then(|| gateway.to_string()),
+                interface: fields[0].to_string(),
+                metric: fields.get(6)?.parse().ok()?,
+                default: prefix == 0,
+            })
+        })
+        .collect()
+}
+
+/// `destination préfixe source préfixe passerelle métrique refcnt use flags iface`,
+/// adresses sur 32 chiffres hexadécimaux dans l'ordre réseau. Les routes des
+/// adresses locales et du multicast sont ignorées.
+fn parse_ipv6_routes(content: &str) -> Vec<Route> {
+    let address = |hex: &str| -> Option<Ipv6Addr> {
+        Some(Ipv6Addr::from(u128::from_str_radix(hex, 16).ok()?))
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(8)?, 16).ok()?;
+            let destination = address(fields.first()?)?;
+            if flags & RTF_UP == 0
+                || flags & (RTF_REJECT | RTF_LOCAL) != 0
+                || destination.is_multicast()
+            {
+                return None;
+            }
+            let prefix = u8::from_str_radix(fields.get(1)?, 16).ok()?;
+            let gateway = address(fields.get(4)?)?;
+            Some(Route {
+                family: "ipv6".to_string(),
+                destination: format!("{}/{}", destination, prefix),
+                gateway: (!gateway.is_unspecified()).then(|| gateway.to_string()),
+                interface: fields.get(9)?.to_string(),
+                metric: u32::from_str_radix(fields.get(5)?, 16).ok()?,
+                default: prefix == 0,
+            })
+        })
+        .collect()
+}
+
+/// `nameserver`, `search` (ou l'ancien `domain`) et `options` de `/etc/resolv.conf`.
+fn parse_resolv_conf(content: &str) -> ResolverConfig {
+    let mut resolvers = ResolverConfig::default();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("nameserver") => resolvers
+                .nameservers
+                .extend(fields.next().map(str::to_string)),
+            // La dernière directive `search` / `domain` l'emporte.
+            Some("search") | Some("domain") => {
+                resolvers.search = fields.map(str::to_string).collect()
+            }
+            Some("options") => resolvers.options.extend(fields.map(str::to_string)),
+            _ => {}
+        }
+    }
+    resolvers
+}
+
+fn select_primary(
+    interfaces: &[NetworkInterface],
+    routes: &[Route],
+    configured: &str,
+) -> Option<String> {
+    if !configured.is_empty() {
+        if interfaces
+            .iter()
+            .any(|interface| interface.name == configured)
+        {
+            return Some(configured.to_string());
+        }
+        warn!(
+            "Primary interface '{}' not found, selecting it automatically",
+            configured
+        );
+    }
+    routes
+        .iter()
+        .filter(|route| route.default && route.family == "ipv4")
+        .min_by_key(|route| route.metric)
+        .map(|route| route.interface.clone())
+        .or_else(|| {
+            interfaces
+                .iter()
+                .find(|interface| {
+                    !interface.loopback && interface.addresses.iter().any(|a| a.contains('.'))
+                })
+                .map(|interface| interface.name.clone())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_routes_and_resolvers() {
+        let ipv4 =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0102A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0002A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+wg0\t0000080A\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0
+";
+        let routes = parse_ipv4_routes(ipv4);
+        assert_eq!(routes.len(), 3);
+        assert!(routes[0].default);
+        assert_eq!(routes[0].gateway.as_deref(), Some("192.168.2.1"));
+        assert_eq!(routes[1].destination, "192.168.2.0/24");
+        assert_eq!(routes[1].gateway, None);
+
+        let ipv6 = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+20010db8000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+20010db8000000000000000000000010 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001       lo
+";
+        let routes6 = parse_ipv6_routes(ipv6);
+        assert_eq!(routes6.len(), 2);
+        assert_eq!(routes6[0].destination, "::/0");
+        assert_eq!(routes6[0].gateway.as_deref(), Some("fe80::1"));
+        assert_eq!(routes6[0].metric, 1024);
+        assert_eq!(routes6[1].destination, "2001:db8::/64");
+
+        let interfaces = vec![
+            NetworkInterface {
+                name: "eth0".to_string(),
+                addresses: vec!["fe80::2/64".to_string(), "192.168.2.15/24".to_string()],
+                ..Default::default()
+            },
+            NetworkInterface {
+                name: "wg0".to_string(),
+                addresses: vec!["10.8.0.1/24".to_string()],
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            select_primary(&interfaces, &routes, "").as_deref(),
+            Some("eth0")
+        );
+        assert_eq!(
+            select_primary(&interfaces, &routes, "wg0").as_deref(),
+            Some("wg0")
+        );
+        assert_eq!(
+            select_primary(&interfaces, &routes, "eth9").as_deref(),
+            Some("eth0")
+        );
+
+        let mut report = NetworkReport {
+            interfaces,
+            routes,
+            resolvers: ResolverConfig::default(),
+            primary: Some("eth0".to_string()),
+        };
+        report.interfaces[0].primary = true;
+        assert_eq!(report.primary_address().as_deref(), Some("192.168.2.15"));
+        assert_eq!(report.gateways(), vec!["192.168.2.1 (eth0)"]);
+
+        let resolvers = parse_resolv_conf(
+            "# Generated by NetworkManager\nsearch example.com lan\nnameserver 127.0.0.53\nnameserver ::1\noptions edns0 trust-ad\n",
+        );
+        assert_eq!(resolvers.nameservers, vec!["127.0.0.53", "::1"]);
+        assert_eq!(resolvers.search, vec!["example.com", "lan"]);
+        assert_eq!(resolvers.options, vec!["edns0", "trust-ad"]);
+    }
+}
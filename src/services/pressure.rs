@@ -0,0 +1,163 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{PressureLine, PressureStats, ResourcePressure},
+};
+use log::debug;
+use std::{fs, path::Path};
+
+/// Fichiers PSI du système (noyau 4.20+ avec `CONFIG_PSI`).
+pub const PROC_PRESSURE: &str = "/proc/pressure";
+
+/// Seuils d'alerte sur la moyenne 60 s, en % du temps passé en attente.
+#[derive(Debug, Clone, Copy)]
+pub struct PressureThresholds {
+    /// Au moins une tâche bloquée (`some`) : Warning.
+    pub some_warning: f64,
+    /// Toutes les tâches bloquées (`full`) : Critical.
+    pub full_critical: f64,
+}
+
+impl PressureThresholds {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            some_warning: config.psi_some_warning,
+            full_critical: config.psi_full_critical,
+        }
+    }
+}
+
+/// PSI du système ; `PressureUnavailable` si le noyau ne l'expose pas.
+pub fn system_pressure() -> Result<ResourcePressure, SystemError> {
+    let pressure = read_pressure(Path::new(PROC_PRESSURE), "");
+    if pressure.cpu.is_none() && pressure.memory.is_none() && pressure.io.is_none() {
+        debug!("{}", SystemError::PressureUnavailable.message());
+        return Err(SystemError::PressureUnavailable);
+    }
+    Ok(pressure)
+}
+
+/// Lit `<dir>/cpu<suffix>`, `memory<suffix>` et `io<suffix>` : suffixe vide pour
+/// `/proc/pressure`, `.pressure` pour un cgroup.
+fn read_pressure(dir: &Path, suffix: &str) -> ResourcePressure {
+    let read = |resource: &str| {
+        fs::read_to_string(dir.join(format!("{}{}", resource, suffix)))
+            .ok()
+            .and_then(|content| parse_pressure(&content))
+    };
+    ResourcePressure {
+        cpu: read("cpu"),
+        memory: read("memory"),
+        io: read("io"),
+    }
+}
+
+/// PSI d'un cgroup v2 (`cpu.pressure`, `memory.pressure`, `io.pressure`).
+pub fn cgroup_pressure(path: &Path) -> Option<ResourcePressure> {
+    let pressure = read_pressure(path, ".pressure");
+    (pressure.cpu.is_some() || pressure.memory.is_some() || pressure.io.is_some())
+        .then_some(pressure)
+}
+
+/// Décode un fichier PSI :
+/// `some avg10=0.61 avg60=0.42 avg300=0.25 total=9163452` puis la ligne `full`.
+fn parse_pressure(content: &str) -> Option<PressureStats> {
+    let mut stats = PressureStats {
+        some: None,
+        full: None,
+    };
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let mut pressure = PressureLine::default();
+        for (key, value) in fields.filter_map(|field| field.split_once('=')) {
+            match key {
+                "avg10" => pressure.avg10 = value.parse().ok()?,
+                "avg60" => pressure.avg60 = value.parse().ok()?,
+                "avg300" => pressure.avg300 = value.parse().ok()?,
+                "total" => pressure.total = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        match kind {
+            "some" => stats.some = Some(pressure),
+            "full" => stats.full = Some(pressure),
+            _ => {}
+        }
+    }
+    stats.some.is_some().then_some(stats)
+}
+
+/// Alertes sur la pression d'un ensemble de ressources (`source` : `system`, nom du cgroup...).
+pub fn pressure_alerts(
+    source: &str,
+    pressure: &ResourcePressure,
+    thresholds: PressureThresholds,
+) -> Vec<Alert> {
+    [
+        ("cpu", &pressure.cpu),
+        ("memory", &pressure.memory),
+        ("io", &pressure.io),
+    ]
+    .into_iter()
+    .filter_map(|(resource, stats)| {
+        let stats = stats.as_ref()?;
+        // Au niveau système, `cpu full` n'a pas de sens et reste à zéro.
+        let full = stats.full.as_ref().map_or(0.0, |full| full.avg60);
+        let some = stats.some.as_ref().map_or(0.0, |some| some.avg60);
+        let state = if full >= thresholds.full_critical {
+            CheckState::Critical
+        } else if some >= thresholds.some_warning {
+            CheckState::Warning
+        } else {
+            return None;
+        };
+        Some(Alert::new(
+            &format!("psi:{}", source),
+            state,
+            format!(
+                "{} pressure over 60 s: some {:.2}%, full {:.2}%",
+                resource, some, full
+            ),
+        ))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pressure_and_alerts() {
+        let memory = parse_pressure(
+            "some avg10=31.50 avg60=25.00 avg300=3.73 total=101533951\n\
+             full avg10=12.00 avg60=11.20 avg300=0.00 total=6788412\n",
+        )
+        .unwrap();
+        assert_eq!(memory.some.as_ref().unwrap().avg10, 31.5);
+        assert_eq!(memory.full.as_ref().unwrap().total, 6788412);
+
+        // Noyaux < 5.13 : pas de ligne `full` pour le CPU
+        let cpu = parse_pressure("some avg10=0.00 avg60=21.00 avg300=0.00 total=305421\n").unwrap();
+        assert!(cpu.full.is_none());
+        assert!(parse_pressure("").is_none());
+
+        let pressure = ResourcePressure {
+            cpu: Some(cpu),
+            memory: Some(memory),
+            io: None,
+        };
+        let alerts = pressure_alerts(
+            "system",
+            &pressure,
+            PressureThresholds {
+                some_warning: 20.0,
+                full_critical: 10.0,
+            },
+        );
+        let states: Vec<CheckState> = alerts.iter().map(|alert| alert.state).collect();
+        assert_eq!(states, vec![CheckState::Warning, CheckState::Critical]);
+    }
+}
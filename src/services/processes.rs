@@ -0,0 +1,449 @@
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{ProcessCheckStatus, ProcessInfo},
+};
+use crate::services::{service_checker::load_checks_file, users::user_name};
+use log::{error, warn};
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, os::unix::fs::MetadataExt, time::Duration};
+
+/// Intervalle entre les deux relevés de `/proc` servant au calcul du %CPU.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Contrôle de processus déclaré dans `services.toml` (`[[processes]]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessCheck {
+    pub name: String,
+    /// Expression régulière appliquée au nom du processus (ou à la ligne de commande).
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub match_cmdline: bool,
+    /// Fichier contenant le PID à surveiller ; prioritaire sur `pattern`.
+    pub pidfile: Option<String>,
+    #[serde(default = "default_min")]
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ProcessChecksFile {
+    #[serde(default)]
+    processes: Vec<ProcessCheck>,
+}
+
+fn default_min() -> usize {
+    1
+}
+
+/// Critère de tri de la table des processus.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Rss,
+    Threads,
+    Pid,
+    Name,
+}
+
+/// Paramètres de `/api/v1/processes` : tri, nombre de lignes et filtres.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProcessQuery {
+    #[serde(default)]
+    pub sort: ProcessSort,
+    pub limit: Option<usize>,
+    /// Nom d'utilisateur exact.
+    pub user: Option<String>,
+    /// Sous-chaîne recherchée (sans casse) dans le nom ou la ligne de commande.
+    pub filter: Option<String>,
+    /// Lettre d'état (`R`, `S`, `D`, `Z`...).
+    pub state: Option<String>,
+}
+
+/// Relevé brut d'un processus lu dans `/proc/<pid>`.
+#[derive(Debug, Clone)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    pub cmdline: String,
+    pub uid: u32,
+    pub state: char,
+    /// Temps CPU cumulé (utime + stime) en ticks.
+    pub cpu_ticks: u64,
+    pub rss_bytes: u64,
+    pub threads: u64,
+    /// Date de démarrage en ticks depuis le boot.
+    pub start_ticks: u64,
+}
+
+pub fn load_process_checks(path: &str) -> Vec<ProcessCheck> {
+    load_checks_file::<ProcessChecksFile>(path)
+        .map(|file| file.processes)
+        .unwrap_or_default()
+}
+
+/// Évalue les contrôles de processus déclarés dans `services.toml` sur un relevé de `measure_processes`.
+pub fn check_processes(
+    config_path: &str,
+    measured: &[(ProcessSample, f64)],
+) -> Vec<ProcessCheckStatus> {
+    load_process_checks(config_path)
+        .iter()
+        .map(|check| evaluate_check(check, measured))
+        .collect()
+}
+
+/// Processus triés et filtrés selon `query`, limités à `query.limit` (ou `default_limit`).
+pub fn top_processes(
+    measured: &[(ProcessSample, f64)],
+    query: &ProcessQuery,
+    default_limit: usize,
+) -> Vec<ProcessInfo> {
+    let mut users: HashMap<u32, String> = HashMap::new();
+    let filter = query.filter.as_deref().map(str::to_lowercase);
+
+    let mut selected: Vec<&(ProcessSample, f64)> = measured
+        .iter()
+        .filter(|(sample, _)| {
+            query
+                .state
+                .as_deref()
+                .is_none_or(|state| state.eq_ignore_ascii_case(&sample.state.to_string()))
+        })
+        .filter(|(sample, _)| {
+            filter.as_deref().is_none_or(|filter| {
+                sample.name.to_lowercase().contains(filter)
+                    || sample.cmdline.to_lowercase().contains(filter)
+            })
+        })
+        .filter(|(sample, _)| {
+            query.user.as_deref().is_none_or(|user| {
+                users
+                    .entry(sample.uid)
+                    .or_insert_with(|| user_name(sample.uid))
+                    == user
+            })
+        })
+        .collect();
+
+    match query.sort {
+        ProcessSort::Cpu => selected.sort_by(|a, b| b.1.total_cmp(&a.1)),
+        ProcessSort::Rss => selected.sort_by_key(|(sample, _)| std::cmp::Reverse(sample.rss_bytes)),
+        ProcessSort::Threads => {
+            selected.sort_by_key(|(sample, _)| std::cmp::Reverse(sample.threads))
+        }
+        ProcessSort::Pid => selected.sort_by_key(|(sample, _)| sample.pid),
+        ProcessSort::Name => selected.sort_by(|a, b| a.0.name.cmp(&b.0.name)),
+    }
+
+    selected
+        .into_iter()
+        .take(query.limit.unwrap_or(default_limit))
+        .map(|(sample, cpu_percent)| process_info(sample, *cpu_percent))
+        .collect()
+}
+
+/// Alertes pour les contrôles dont le nombre d'instances sort des bornes.
+pub fn process_check_alerts(checks: &[ProcessCheckStatus]) -> Vec<Alert> {
+    checks
+        .iter()
+        .filter(|check| check.state != CheckState::Ok)
+        .map(|check| {
+            let message = match &check.error {
+                Some(e) => e.clone(),
+                None => format!(
+                    "{} matching processes (expected {}..{})",
+                    check.count,
+                    check.min,
+                    check.max.map(|max| max.to_string()).unwrap_or_default()
+                ),
+            };
+            Alert::new(&format!("process:{}", check.name), check.state, message)
+        })
+        .collect()
+}
+
+fn evaluate_check(check: &ProcessCheck, measured: &[(ProcessSample, f64)]) -> ProcessCheckStatus {
+    let mut status = ProcessCheckStatus {
+        name: check.name.clone(),
+        count: 0,
+        min: check.min,
+        max: check.max,
+        state: CheckState::Unknown,
+        processes: vec![],
+        error: None,
+    };
+
+    let matcher = match build_matcher(check) {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            warn!("Invalid process check {}: {}", check.name, e);
+            status.error = Some(e);
+            return status;
+        }
+    };
+
+    status.processes = measured
+        .iter()
+        .filter(|(sample, _)| matcher.matches(sample))
+        .map(|(sample, cpu_percent)| process_info(sample, *cpu_percent))
+        .collect();
+    status.count = status.processes.len();
+
+    let too_many = check.max.is_some_and(|max| status.count > max);
+    status.state = if status.count < check.min || too_many {
+        CheckState::Critical
+    } else {
+        CheckState::Ok
+    };
+    status
+}
+
+enum Matcher {
+    Pid(u32),
+    Name(Regex),
+    Cmdline(Regex),
+}
+
+impl Matcher {
+    fn matches(&self, sample: &ProcessSample) -> bool {
+        match self {
+            Self::Pid(pid) => sample.pid == *pid,
+            Self::Name(regex) => regex.is_match(&sample.name),
+            Self::Cmdline(regex) => regex.is_match(&sample.cmdline),
+        }
+    }
+}
+
+fn build_matcher(check: &ProcessCheck) -> Result<Matcher, String> {
+    if let Some(pidfile) = &check.pidfile {
+        return fs::read_to_string(pidfile)
+            .map_err(|e| format!("failed to read {}: {}", pidfile, e))?
+            .trim()
+            .parse::<u32>()
+            .map(Matcher::Pid)
+            .map_err(|_| format!("invalid PID in {}", pidfile));
+    }
+
+    let pattern = check
+        .pattern
+        .as_deref()
+        .ok_or_else(|| "neither pattern nor pidfile is configured".to_string())?;
+    let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+    Ok(if check.match_cmdline {
+        Matcher::Cmdline(regex)
+    } else {
+        Matcher::Name(regex)
+    })
+}
+
+/// Deux relevés de `/proc` espacés de `SAMPLE_INTERVAL` ; renvoie chaque processus avec son %CPU.
+pub async fn measure_processes() -> Result<Vec<(ProcessSample, f64)>, SystemError> {
+    let before: HashMap<u32, u64> = read_process_samples()?
+        .into_iter()
+        .map(|sample| (sample.pid, sample.cpu_ticks))
+        .collect();
+    tokio::time::sleep(SAMPLE_INTERVAL).await;
+    let after = read_process_samples()?;
+
+    let elapsed_ticks = SAMPLE_INTERVAL.as_secs_f64() * clock_ticks_per_second() as f64;
+    Ok(after
+        .into_iter()
+        .map(|sample| {
+            let previous = before.get(&sample.pid).copied().unwrap_or(sample.cpu_ticks);
+            let cpu_percent =
+                sample.cpu_ticks.saturating_sub(previous) as f64 / elapsed_ticks * 100.0;
+            (sample, cpu_percent)
+        })
+        .collect())
+}
+
+pub fn read_process_samples() -> Result<Vec<ProcessSample>, SystemError> {
+    let entries = fs::read_dir("/proc").map_err(|_| {
+        error!("{}", SystemError::ProcessInfoUnavailable.message());
+        SystemError::ProcessInfoUnavailable
+    })?;
+
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(read_process_sample)
+        .collect())
+}
+
+/// Lit un processus ; `None` s'il a disparu entre-temps.
+fn read_process_sample(pid: u32) -> Option<ProcessSample> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|raw| {
+            String::from_utf8_lossy(&raw)
+                .split('\0')
+                .filter(|arg| !arg.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+    let uid = fs::metadata(format!("/proc/{}", pid)).ok()?.uid();
+    parse_stat(pid, uid, &stat, cmdline)
+}
+
+/// Décode `/proc/<pid>/stat` ; le nom peut contenir espaces et parenthèses.
+fn parse_stat(pid: u32, uid: u32, stat: &str, cmdline: String) -> Option<ProcessSample> {
+    let name = &stat[stat.find('(')? + 1..stat.rfind(')')?];
+    // Champs à partir du 3e (state), indexés à 0.
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let field = |index: usize| fields.get(index).and_then(|v| v.parse::<u64>().ok());
+
+    Some(ProcessSample {
+        pid,
+        name: name.to_string(),
+        cmdline,
+        uid,
+        state: fields.first()?.chars().next()?,
+        cpu_ticks: field(11)? + field(12)?,
+        threads: field(17)?,
+        start_ticks: field(19)?,
+        rss_bytes: field(21)? * page_size(),
+    })
+}
+
+/// Complète un relevé avec les informations coûteuses (descripteurs ouverts, durée de vie).
+pub fn process_info(sample: &ProcessSample, cpu_percent: f64) -> ProcessInfo {
+    let open_fds = fs::read_dir(format!("/proc/{}/fd", sample.pid))
+        .map(|entries| entries.count())
+        .ok();
+
+    let uptime_secs = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|content| content.split_whitespace().next()?.parse::<f64>().ok())
+        .map(|system_uptime| {
+            let started = sample.start_ticks as f64 / clock_ticks_per_second() as f64;
+            (system_uptime - started).max(0.0) as u64
+        })
+        .unwrap_or(0);
+
+    ProcessInfo {
+        pid: sample.pid,
+        name: sample.name.clone(),
+        cmdline: sample.cmdline.clone(),
+        user: user_name(sample.uid),
+        state: sample.state.to_string(),
+        cpu_percent,
+        rss: sample.rss_bytes,
+        threads: sample.threads,
+        open_fds,
+        uptime_secs,
+    }
+}
+
+fn clock_ticks_per_second() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+fn page_size() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat_with_parentheses_in_name() {
+        let stat = "4242 (my (odd) proc) S 1 4242 4242 0 -1 4194560 1200 0 3 0 \
+                    150 50 0 0 20 0 7 0 123456 104857600 2560 18446744073709551615";
+        let sample = parse_stat(4242, 0, stat, "/usr/bin/odd --flag".to_string()).unwrap();
+        assert_eq!(sample.name, "my (odd) proc");
+        assert_eq!(sample.state, 'S');
+        assert_eq!(sample.cpu_ticks, 200);
+        assert_eq!(sample.threads, 7);
+        assert_eq!(sample.start_ticks, 123456);
+        assert_eq!(sample.rss_bytes, 2560 * page_size());
+    }
+
+    #[test]
+    fn test_instance_count_bounds() {
+        let sample = |pid, name: &str| {
+            (
+                parse_stat(
+                    pid,
+                    0,
+                    &format!(
+                        "{} ({}) S 1 1 1 0 -1 0 0 0 0 0 1 1 0 0 20 0 1 0 10 0 1",
+                        pid, name
+                    ),
+                    String::new(),
+                )
+                .unwrap(),
+                0.0,
+            )
+        };
+        let measured = vec![
+            sample(10, "worker"),
+            sample(11, "worker"),
+            sample(12, "nginx"),
+        ];
+        let check = ProcessCheck {
+            name: "workers".to_string(),
+            pattern: Some("^worker$".to_string()),
+            match_cmdline: false,
+            pidfile: None,
+            min: 1,
+            max: Some(1),
+        };
+
+        let status = evaluate_check(&check, &measured);
+        assert_eq!(status.count, 2);
+        assert_eq!(status.state, CheckState::Critical);
+
+        let status = evaluate_check(
+            &ProcessCheck {
+                max: Some(2),
+                ..check
+            },
+            &measured,
+        );
+        assert_eq!(status.state, CheckState::Ok);
+    }
+
+    #[test]
+    fn test_top_processes_sort_and_filter() {
+        let sample = |pid, name: &str, rss| {
+            parse_stat(
+                pid,
+                0,
+                &format!(
+                    "{} ({}) R 1 1 1 0 -1 0 0 0 0 0 1 1 0 0 20 0 1 0 10 0 {}",
+                    pid, name, rss
+                ),
+                format!("/usr/bin/{}", name),
+            )
+            .unwrap()
+        };
+        let measured = vec![
+            (sample(1, "postgres", 100), 5.0),
+            (sample(2, "nginx", 300), 1.0),
+            (sample(3, "postgres", 200), 20.0),
+        ];
+
+        let by_cpu = top_processes(&measured, &ProcessQuery::default(), 2);
+        assert_eq!(by_cpu.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![3, 1]);
+
+        let query = ProcessQuery {
+            sort: ProcessSort::Rss,
+            filter: Some("POSTGRES".to_string()),
+            ..Default::default()
+        };
+        let by_rss = top_processes(&measured, &query, 10);
+        assert_eq!(by_rss.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![3, 1]);
+    }
+}
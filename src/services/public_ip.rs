@@ -0,0 +1,465 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::{PublicIpChange, PublicIpReport},
+};
+use crate::services::cache::ReportCache;
+use chrono::{Local, Utc};
+use log::{debug, info, warn};
+use reqwest::Client;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    process::Stdio,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    net::{lookup_host, UdpSocket},
+    process::Command,
+    time::timeout,
+};
+
+/// Changements d'adresse conservés.
+const MAX_CHANGES: usize = 10;
+
+/// En-tête STUN (RFC 5389) : requête / réponse Binding et « magic cookie ».
+const STUN_BINDING_REQUEST: u16 = 0x0001;
+const STUN_BINDING_RESPONSE: u16 = 0x0101;
+const STUN_MAGIC_COOKIE: u32 = 0x2112_A442;
+const STUN_MAPPED_ADDRESS: u16 = 0x0001;
+const STUN_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// Source de l'adresse publique.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublicIpMethod {
+    /// Services web renvoyant l'adresse en texte brut, essayés dans l'ordre.
+    Http,
+    /// Requête Binding auprès de serveurs STUN.
+    Stun,
+    /// Commande locale (`sh -c`) qui affiche l'adresse.
+    Command(String),
+}
+
+/// Réglages de la découverte de l'adresse publique, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct PublicIpSettings {
+    pub method: PublicIpMethod,
+    pub providers_v4: Vec<String>,
+    pub providers_v6: Vec<String>,
+    pub stun_servers: Vec<String>,
+    /// Durée de validité d'une adresse avant nouvelle recherche ; zéro désactive la recherche.
+    pub ttl: Duration,
+    /// Délai maximal de chaque fournisseur.
+    pub timeout: Duration,
+}
+
+impl PublicIpSettings {
+    pub fn from_config(config: &Config) -> Self {
+        let method = match config.public_ip_method.as_str() {
+            "stun" => PublicIpMethod::Stun,
+            "command" if !config.public_ip_command.is_empty() => {
+                PublicIpMethod::Command(config.public_ip_command.clone())
+            }
+            "http" => PublicIpMethod::Http,
+            other => {
+                warn!(
+                    "Invalid public_ip_method '{}' (or empty public_ip_command), using http",
+                    other
+                );
+                PublicIpMethod::Http
+            }
+        };
+        Self {
+            method,
+            providers_v4: config.public_ip_providers_v4.clone(),
+            providers_v6: config.public_ip_providers_v6.clone(),
+            stun_servers: config.public_ip_stun_servers.clone(),
+            ttl: Duration::from_secs(config.public_ip_ttl_secs),
+            timeout: Duration::from_secs(config.public_ip_timeout_secs),
+        }
+    }
+
+    fn method_label(&self) -> &'static str {
+        match self.method {
+            PublicIpMethod::Http => "http",
+            PublicIpMethod::Stun => "stun",
+            PublicIpMethod::Command(_) => "command",
+        }
+    }
+}
+
+/// Dernière adresse publique connue, rafraîchie en tâche de fond par [`watch_public_ip`].
+pub type PublicIpCache = ReportCache<PublicIpReport>;
+
+/// Enregistre un relevé ; une famille introuvable garde sa dernière adresse connue.
+fn record_lookup(
+    report: &mut PublicIpReport,
+    ipv4: Option<IpAddr>,
+    ipv6: Option<IpAddr>,
+    error: Option<String>,
+) {
+    let now = Local::now();
+    let PublicIpReport {
+        ipv4: current_v4,
+        ipv6: current_v6,
+        changes,
+        ..
+    } = report;
+    for (family, current, found) in [("ipv4", current_v4, ipv4), ("ipv6", current_v6, ipv6)] {
+        let Some(found) = found.map(|ip| ip.to_string()) else {
+            continue;
+        };
+        if let Some(previous) = current.as_ref().filter(|previous| **previous != found) {
+            warn!("Public {} changed from {} to {}", family, previous, found);
+            changes.insert(
+                0,
+                PublicIpChange {
+                    family: family.to_string(),
+                    previous: previous.clone(),
+                    current: found.clone(),
+                    timestamp: now.timestamp(),
+                    date: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+                },
+            );
+        }
+        *current = Some(found);
+    }
+    report.changes.truncate(MAX_CHANGES);
+    report.error = error;
+    report.updated = Some(now.format("%Y-%m-%d %H:%M:%S").to_string());
+}
+
+/// Recherche l'adresse publique toutes les `ttl` ; un TTL nul désactive la recherche.
+pub async fn watch_public_ip(cache: Arc<PublicIpCache>, settings: PublicIpSettings) {
+    cache.update(|report| {
+        report.enabled = !settings.ttl.is_zero();
+        report.method = settings.method_label().to_string();
+    });
+    if settings.ttl.is_zero() {
+        info!("Public IP lookup disabled");
+        return;
+    }
+    loop {
+        let (ipv4, ipv6, error) = lookup(&settings).await;
+        info!(
+            "Public IP refreshed: {}",
+            [ipv4, ipv6]
+                .iter()
+                .flatten()
+                .map(|ip| ip.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        cache.update(|report| record_lookup(report, ipv4, ipv6, error));
+        tokio::time::sleep(settings.ttl).await;
+    }
+}
+
+/// Adresses IPv4 et IPv6 ; l'erreur n'est remontée que si aucune n'a été trouvée,
+/// beaucoup d'hôtes n'ayant pas de connectivité IPv6.
+async fn lookup(settings: &PublicIpSettings) -> (Option<IpAddr>, Option<IpAddr>, Option<String>) {
+    let (ipv4, ipv6) = match &settings.method {
+        PublicIpMethod::Http => (
+            http_lookup(&settings.providers_v4, settings.timeout, false).await,
+            http_lookup(&settings.providers_v6, settings.timeout, true).await,
+        ),
+        PublicIpMethod::Stun => (
+            stun_lookup(&settings.stun_servers, settings.timeout, false).await,
+            stun_lookup(&settings.stun_servers, settings.timeout, true).await,
+        ),
+        PublicIpMethod::Command(command) => match command_lookup(command, settings.timeout).await {
+            Ok(output) => (
+                parse_ip(&output, false).ok_or_else(|| "no IPv4 address in output".to_string()),
+                parse_ip(&output, true).ok_or_else(|| "no IPv6 address in output".to_string()),
+            ),
+            Err(e) => (Err(e.clone()), Err(e)),
+        },
+    };
+    if let Err(e) = &ipv6 {
+        debug!("Public IPv6 lookup failed: {}", e);
+    }
+    match (ipv4, ipv6) {
+        (Err(e), Err(_)) => {
+            warn!("Public IP lookup failed: {}", e);
+            (None, None, Some(e))
+        }
+        (ipv4, ipv6) => (ipv4.ok(), ipv6.ok(), None),
+    }
+}
+
+/// Premier fournisseur qui répond une adresse de la famille demandée ; la socket
+/// locale est liée à cette famille pour ne pas dépendre de la résolution DNS.
+async fn http_lookup(providers: &[String], delay: Duration, ipv6: bool) -> Result<IpAddr, String> {
+    if providers.is_empty() {
+        return Err("no provider configured".to_string());
+    }
+    let local: IpAddr = if ipv6 {
+        Ipv6Addr::UNSPECIFIED.into()
+    } else {
+        Ipv4Addr::UNSPECIFIED.into()
+    };
+    let client = Client::builder()
+        .timeout(delay)
+        .local_address(local)
+        .build()
+        .map_err(|e| format!("failed to build HTTP client: {}", e))?;
+
+    let mut errors = Vec::new();
+    for url in providers {
+        let body = match client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(response) => response.text().await,
+            Err(e) => Err(e),
+        };
+        match body {
+            Ok(body) => match parse_ip(&body, ipv6) {
+                Some(ip) => return Ok(ip),
+                None => errors.push(format!("{}: unexpected response", url)),
+            },
+            Err(e) => errors.push(format!("{}: {}", url, e)),
+        }
+    }
+    Err(errors.join("; "))
+}
+
+/// Premier serveur STUN qui renvoie l'adresse vue de l'extérieur.
+async fn stun_lookup(servers: &[String], delay: Duration, ipv6: bool) -> Result<IpAddr, String> {
+    if servers.is_empty() {
+        return Err("no STUN server configured".to_string());
+    }
+    let mut errors = Vec::new();
+    for server in servers {
+        match timeout(delay, stun_binding(server, ipv6)).await {
+            Ok(Ok(ip)) => return Ok(ip),
+            Ok(Err(e)) => errors.push(format!("{}: {}", server, e)),
+            Err(_) => errors.push(format!("{}: timed out", server)),
+        }
+    }
+    Err(errors.join("; "))
+}
+
+async fn stun_binding(server: &str, ipv6: bool) -> Result<IpAddr, String> {
+    let address: SocketAddr = lookup_host(server)
+        .await
+        .map_err(|e| e.to_string())?
+        .find(|address| address.is_ipv6() == ipv6)
+        .ok_or_else(|| format!("no {} address", if ipv6 { "IPv6" } else { "IPv4" }))?;
+    let socket = UdpSocket::bind(if ipv6 { "[::]:0" } else { "0.0.0.0:0" })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let transaction = transaction_id();
+    let mut request = Vec::with_capacity(20);
+    request.extend(STUN_BINDING_REQUEST.to_be_bytes());
+    request.extend(0u16.to_be_bytes());
+    request.extend(STUN_MAGIC_COOKIE.to_be_bytes());
+    request.extend(transaction);
+    socket
+        .send_to(&request, address)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut buffer = [0u8; 512];
+    loop {
+        let (len, from) = socket
+            .recv_from(&mut buffer)
+            .await
+            .map_err(|e| e.to_string())?;
+        if from != address {
+            continue;
+        }
+        return parse_stun_response(&buffer[..len], &transaction)
+            .ok_or_else(|| "invalid STUN response".to_string());
+    }
+}
+
+/// Identifiant de transaction STUN ; il n'a pas besoin d'être imprévisible, seulement unique.
+fn transaction_id() -> [u8; 12] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut id = [0u8; 12];
+    id[..8].copy_from_slice(&nanos.to_be_bytes());
+    id[8..].copy_from_slice(&std::process::id().to_be_bytes());
+    id
+}
+
+/// Adresse de `XOR-MAPPED-ADDRESS`, à défaut de `MAPPED-ADDRESS` (anciens serveurs),
+/// d'une réponse Binding correspondant à `transaction`.
+fn parse_stun_response(response: &[u8], transaction: &[u8; 12]) -> Option<IpAddr> {
+    let u16_at = |offset: usize| -> Option<u16> {
+        Some(u16::from_be_bytes(
+            response.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    if u16_at(0)? != STUN_BINDING_RESPONSE
+        || response.get(4..8)? != STUN_MAGIC_COOKIE.to_be_bytes()
+        || response.get(8..20)? != transaction
+    {
+        return None;
+    }
+    let end = (20 + u16_at(2)? as usize).min(response.len());
+
+    let mut mapped = None;
+    let mut offset = 20;
+    while offset + 4 <= end {
+        let kind = u16_at(offset)?;
+        let len = u16_at(offset + 2)? as usize;
+        let value = response.get(offset + 4..offset + 4 + len)?;
+        match kind {
+            STUN_XOR_MAPPED_ADDRESS => {
+                let mut key = STUN_MAGIC_COOKIE.to_be_bytes().to_vec();
+                key.extend(transaction);
+                return decode_stun_address(value, Some(&key));
+            }
+            STUN_MAPPED_ADDRESS => mapped = decode_stun_address(value, None),
+            _ => {}
+        }
+        // Les attributs sont alignés sur 4 octets.
+        offset += 4 + len.div_ceil(4) * 4;
+    }
+    mapped
+}
+
+/// `réservé famille port adresse`, l'adresse étant masquée par `key` (XOR) si fourni.
+fn decode_stun_address(value: &[u8], key: Option<&[u8]>) -> Option<IpAddr> {
+    let len = match value.get(1)? {
+        0x01 => 4,
+        0x02 => 16,
+        _ => return None,
+    };
+    let mut bytes = value.get(4..4 + len)?.to_vec();
+    if let Some(key) = key {
+        bytes.iter_mut().zip(key).for_each(|(byte, k)| *byte ^= k);
+    }
+    Some(match len {
+        4 => IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?),
+        _ => IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?),
+    })
+}
+
+/// Sortie standard de `sh -c command`.
+async fn command_lookup(command: &str, delay: Duration) -> Result<String, String> {
+    let child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to execute {}: {}", command, e))?;
+    match timeout(delay, child.wait_with_output()).await {
+        Ok(Ok(output)) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+        Ok(Ok(output)) => Err(format!("{} exited with {}", command, output.status)),
+        Ok(Err(e)) => Err(format!("failed to wait for {}: {}", command, e)),
+        Err(_) => Err(format!(
+            "{} timed out after {} seconds",
+            command,
+            delay.as_secs()
+        )),
+    }
+}
+
+/// Première adresse publique de la famille demandée dans un texte (réponse HTTP,
+/// sortie de commande) ; les adresses privées ou locales sont ignorées.
+fn parse_ip(text: &str, ipv6: bool) -> Option<IpAddr> {
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == '"')
+        .filter_map(|token| token.parse::<IpAddr>().ok())
+        .find(|ip| match ip {
+            IpAddr::V4(v4) if !ipv6 => {
+                !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified())
+            }
+            IpAddr::V6(v6) if ipv6 => {
+                // Adresses globales : 2000::/3
+                v6.segments()[0] & 0xe000 == 0x2000
+            }
+            _ => false,
+        })
+}
+
+/// Alerte pour chaque changement d'adresse publique récent.
+pub fn public_ip_alerts(report: &PublicIpReport, change_alert_secs: i64) -> Vec<Alert> {
+    let now = Utc::now().timestamp();
+    report
+        .changes
+        .iter()
+        .filter(|change| now - change.timestamp < change_alert_secs)
+        .map(|change| {
+            Alert::new(
+                "public_ip",
+                CheckState::Warning,
+                format!(
+                    "public {} changed from {} to {} at {}",
+                    change.family, change.previous, change.current, change.date
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stun_response_and_change_detection() {
+        let transaction = [7u8; 12];
+        // Réponse Binding avec SOFTWARE (longueur non alignée) puis XOR-MAPPED-ADDRESS
+        // 203.0.113.5:54321.
+        let mut response = vec![0x01, 0x01, 0x00, 0x14];
+        response.extend(STUN_MAGIC_COOKIE.to_be_bytes());
+        response.extend(transaction);
+        response.extend([0x80, 0x22, 0x00, 0x03, b'a', b'b', b'c', 0x00]);
+        response.extend([0x00, 0x20, 0x00, 0x08, 0x00, 0x01]);
+        response.extend((54321u16 ^ 0x2112).to_be_bytes());
+        let cookie = STUN_MAGIC_COOKIE.to_be_bytes();
+        response.extend([203, 0, 113, 5].iter().zip(cookie).map(|(b, k)| b ^ k));
+        assert_eq!(
+            parse_stun_response(&response, &transaction),
+            Some("203.0.113.5".parse().unwrap())
+        );
+        assert_eq!(parse_stun_response(&response, &[0u8; 12]), None);
+
+        assert_eq!(
+            parse_ip("192.168.1.2 198.51.100.4\n2001:db8::1\n", false),
+            Some("198.51.100.4".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip("\"fe80::1\" 2001:db8::1", true),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(parse_ip("<html>error</html>", false), None);
+
+        let cache = PublicIpCache::default();
+        let lookup = |ipv4: Option<&str>, error: Option<&str>| {
+            cache.update(|report| {
+                record_lookup(
+                    report,
+                    ipv4.map(|ip| ip.parse().unwrap()),
+                    None,
+                    error.map(str::to_string),
+                )
+            })
+        };
+        lookup(Some("198.51.100.4"), None);
+        lookup(None, Some("timed out"));
+        let report = cache.report();
+        assert_eq!(report.ipv4.as_deref(), Some("198.51.100.4"));
+        assert!(report.changes.is_empty());
+
+        lookup(Some("198.51.100.9"), None);
+        let report = cache.report();
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.changes[0].previous, "198.51.100.4");
+        assert_eq!(report.error, None);
+        assert_eq!(public_ip_alerts(&report, 3600).len(), 1);
+        assert!(public_ip_alerts(&report, 0).is_empty());
+    }
+}
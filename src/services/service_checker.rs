@@ -1,10 +1,11 @@
 use log::warn;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use std::fs;
 use std::process::Command;
 
 #[derive(Deserialize)]
 struct Config {
+    #[serde(default)]
     services: Vec<String>,
 }
 
@@ -31,21 +32,26 @@ pub fn check_services(config_path: &str) -> Vec<String> {
 }
 
 pub fn load_services_from_config(path: &str) -> Vec<String> {
+    load_checks_file::<Config>(path)
+        .map(|config| config.services)
+        .unwrap_or_default()
+}
+
+/// Lit `services.toml` et désérialise les sections utiles au module appelant.
+pub fn load_checks_file<T: DeserializeOwned>(path: &str) -> Option<T> {
     let content = match fs::read_to_string(path) {
         Ok(content) => content,
         Err(_) => {
             warn!("Failed to read configuration file at {}", path);
-            return vec![];
+            return None;
         }
     };
 
-    let config: Config = match toml::from_str(&content) {
-        Ok(config) => config,
+    match toml::from_str(&content) {
+        Ok(config) => Some(config),
         Err(_) => {
             warn!("Failed to parse TOML configuration file.");
-            return vec![];
+            None
         }
-    };
-
-    config.services
+    }
 }
@@ -0,0 +1,411 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{DriveHealth, SmartReport},
+};
+use crate::services::cache::ReportCache;
+use chrono::Local;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::{process::Stdio, sync::Arc, time::Duration};
+use tokio::{process::Command, time::timeout};
+
+/// Délai maximal d'un appel à `smartctl` (un disque en veille peut mettre du temps à répondre).
+const SMARTCTL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Usure NVMe (`percentage_used`) à partir de laquelle le disque est signalé.
+const NVME_WEAR_WARNING: u64 = 90;
+
+/// Bits 0 et 1 du code de sortie : ligne de commande invalide ou disque inaccessible.
+const SMARTCTL_FATAL_BITS: i32 = 0b11;
+
+/// Réglages de la collecte SMART, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct SmartSettings {
+    pub smartctl_path: String,
+    /// Disques à interroger ; vide : `smartctl --scan`.
+    pub devices: Vec<String>,
+    pub interval: Duration,
+}
+
+impl SmartSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            smartctl_path: config.smartctl_path.clone(),
+            devices: config.smart_devices.clone(),
+            interval: Duration::from_secs(config.smart_interval_secs),
+        }
+    }
+}
+
+/// Dernier relevé SMART, rafraîchi en tâche de fond par [`watch_smart`].
+pub type SmartCache = ReportCache<SmartReport>;
+
+/// Interroge les disques toutes les `interval` ; un intervalle nul désactive la collecte.
+pub async fn watch_smart(cache: Arc<SmartCache>, settings: SmartSettings) {
+    if settings.interval.is_zero() {
+        info!("SMART collection disabled");
+        return;
+    }
+    loop {
+        let report = collect_smart(&settings).await;
+        info!("SMART data refreshed for {} drive(s)", report.drives.len());
+        cache.set(report);
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+async fn collect_smart(settings: &SmartSettings) -> SmartReport {
+    let mut report = SmartReport {
+        updated: Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+        ..Default::default()
+    };
+
+    let devices: Vec<(String, Option<String>)> = if settings.devices.is_empty() {
+        match run_smartctl(&settings.smartctl_path, &["--scan", "--json"]).await {
+            Ok(output) => match parse_scan(&output) {
+                Ok(devices) => devices,
+                Err(e) => {
+                    report.error = Some(e);
+                    return report;
+                }
+            },
+            Err(e) => {
+                report.error = Some(e);
+                return report;
+            }
+        }
+    } else {
+        settings
+            .devices
+            .iter()
+            .map(|device| (device.clone(), None))
+            .collect()
+    };
+
+    for (device, device_type) in devices {
+        let mut args = vec!["--json", "-a"];
+        if let Some(device_type) = &device_type {
+            args.extend(["-d", device_type.as_str()]);
+        }
+        args.push(&device);
+        let drive = match run_smartctl(&settings.smartctl_path, &args).await {
+            Ok(output) => parse_smartctl(&device, &output),
+            Err(e) => failed_drive(&device, e),
+        };
+        debug!("SMART {}: {}", device, drive.state.label());
+        report.drives.push(drive);
+    }
+    report
+}
+
+/// Sortie standard de `smartctl` ; son code de sortie est un masque de bits
+/// analysé avec le JSON, il n'est donc pas traité comme une erreur ici.
+async fn run_smartctl(smartctl: &str, args: &[&str]) -> Result<String, String> {
+    let child = Command::new(smartctl)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            warn!("{} {}", SystemError::SmartctlFailed.message(), e);
+            format!("failed to execute {}: {}", smartctl, e)
+        })?;
+
+    match timeout(SMARTCTL_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+        Ok(Err(e)) => Err(format!("failed to wait for {}: {}", smartctl, e)),
+        Err(_) => Err(format!(
+            "{} timed out after {} seconds",
+            smartctl,
+            SMARTCTL_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct ScanOutput {
+    #[serde(default)]
+    devices: Vec<ScannedDevice>,
+}
+
+#[derive(Deserialize)]
+struct ScannedDevice {
+    name: String,
+    #[serde(rename = "type")]
+    device_type: Option<String>,
+}
+
+/// Sous-ensemble de `smartctl --json -a` utilisé par le tableau de bord.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SmartctlOutput {
+    smartctl: SmartctlInfo,
+    device: Option<SmartctlDevice>,
+    model_name: Option<String>,
+    serial_number: Option<String>,
+    smart_status: Option<SmartStatus>,
+    temperature: Option<Temperature>,
+    power_on_time: Option<PowerOnTime>,
+    ata_smart_attributes: Option<AtaAttributes>,
+    nvme_smart_health_information_log: Option<NvmeHealth>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SmartctlInfo {
+    exit_status: i32,
+    messages: Vec<SmartctlMessage>,
+}
+
+#[derive(Deserialize)]
+struct SmartctlMessage {
+    string: String,
+}
+
+#[derive(Deserialize)]
+struct SmartctlDevice {
+    protocol: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SmartStatus {
+    passed: bool,
+}
+
+#[derive(Deserialize)]
+struct Temperature {
+    current: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct PowerOnTime {
+    hours: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct AtaAttributes {
+    #[serde(default)]
+    table: Vec<AtaAttribute>,
+}
+
+#[derive(Deserialize)]
+struct AtaAttribute {
+    id: u32,
+    raw: AtaRawValue,
+}
+
+#[derive(Deserialize)]
+struct AtaRawValue {
+    value: u64,
+}
+
+#[derive(Deserialize)]
+struct NvmeHealth {
+    critical_warning: Option<u64>,
+    available_spare: Option<u64>,
+    percentage_used: Option<u64>,
+    media_errors: Option<u64>,
+}
+
+/// Disques renvoyés par `smartctl --scan --json`, avec leur type (`sat`, `nvme`...).
+fn parse_scan(output: &str) -> Result<Vec<(String, Option<String>)>, String> {
+    let scan: ScanOutput = serde_json::from_str(output)
+        .map_err(|e| format!("invalid smartctl --scan output: {}", e))?;
+    Ok(scan
+        .devices
+        .into_iter()
+        .map(|device| (device.name, device.device_type))
+        .collect())
+}
+
+/// Décode la sortie JSON de `smartctl -a` et en déduit l'état du disque.
+fn parse_smartctl(device: &str, output: &str) -> DriveHealth {
+    let parsed: SmartctlOutput = match serde_json::from_str(output) {
+        Ok(parsed) => parsed,
+        Err(e) => return failed_drive(device, format!("invalid smartctl output: {}", e)),
+    };
+    if parsed.smartctl.exit_status & SMARTCTL_FATAL_BITS != 0 {
+        let message = parsed
+            .smartctl
+            .messages
+            .into_iter()
+            .map(|message| message.string)
+            .collect::<Vec<_>>()
+            .join("; ");
+        return failed_drive(
+            device,
+            format!(
+                "smartctl exited with status {}: {}",
+                parsed.smartctl.exit_status, message
+            ),
+        );
+    }
+
+    let attribute = |id: u32| {
+        parsed.ata_smart_attributes.as_ref().and_then(|attributes| {
+            attributes
+                .table
+                .iter()
+                .find(|attribute| attribute.id == id)
+                .map(|attribute| attribute.raw.value)
+        })
+    };
+    let nvme = parsed.nvme_smart_health_information_log.as_ref();
+
+    let mut drive = DriveHealth {
+        device: device.to_string(),
+        model: parsed.model_name.clone().unwrap_or_default(),
+        serial: parsed.serial_number.clone().unwrap_or_default(),
+        protocol: parsed
+            .device
+            .as_ref()
+            .and_then(|device| device.protocol.clone())
+            .unwrap_or_default(),
+        passed: parsed.smart_status.as_ref().map(|status| status.passed),
+        state: CheckState::Ok,
+        temperature: parsed
+            .temperature
+            .as_ref()
+            .and_then(|temperature| temperature.current),
+        power_on_hours: parsed.power_on_time.as_ref().and_then(|time| time.hours),
+        reallocated_sectors: attribute(5),
+        pending_sectors: attribute(197),
+        offline_uncorrectable: attribute(198),
+        percentage_used: nvme.and_then(|nvme| nvme.percentage_used),
+        available_spare: nvme.and_then(|nvme| nvme.available_spare),
+        media_errors: nvme.and_then(|nvme| nvme.media_errors),
+        critical_warning: nvme.and_then(|nvme| nvme.critical_warning),
+        problems: Vec::new(),
+        error: None,
+    };
+    evaluate(&mut drive);
+    drive
+}
+
+/// Critical si le test global échoue ou si le contrôleur NVMe lève un avertissement
+/// critique ; Warning pour les secteurs réalloués / en attente, erreurs média et usure.
+fn evaluate(drive: &mut DriveHealth) {
+    let mut critical = Vec::new();
+    let mut warning = Vec::new();
+
+    if drive.passed == Some(false) {
+        critical.push("SMART overall health test FAILED".to_string());
+    }
+    if let Some(flags) = drive.critical_warning.filter(|flags| *flags != 0) {
+        critical.push(format!("NVMe critical warning 0x{:02x}", flags));
+    }
+    for (count, label) in [
+        (drive.reallocated_sectors, "reallocated sectors"),
+        (drive.pending_sectors, "pending sectors"),
+        (drive.offline_uncorrectable, "offline uncorrectable sectors"),
+        (drive.media_errors, "media errors"),
+    ] {
+        if let Some(count) = count.filter(|count| *count > 0) {
+            warning.push(format!("{} {}", count, label));
+        }
+    }
+    if let Some(used) = drive
+        .percentage_used
+        .filter(|used| *used >= NVME_WEAR_WARNING)
+    {
+        warning.push(format!("{}% of rated endurance used", used));
+    }
+
+    drive.state = if !critical.is_empty() {
+        CheckState::Critical
+    } else if !warning.is_empty() {
+        CheckState::Warning
+    } else {
+        CheckState::Ok
+    };
+    drive.problems = critical.into_iter().chain(warning).collect();
+}
+
+fn failed_drive(device: &str, error: String) -> DriveHealth {
+    DriveHealth {
+        device: device.to_string(),
+        state: CheckState::Unknown,
+        error: Some(error),
+        ..Default::default()
+    }
+}
+
+/// Alertes pour les disques en erreur ou dont l'état n'a pas pu être lu.
+pub fn smart_alerts(report: &SmartReport) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = report
+        .drives
+        .iter()
+        .filter(|drive| drive.state != CheckState::Ok)
+        .map(|drive| {
+            let detail = match &drive.error {
+                Some(error) => error.clone(),
+                None => drive.problems.join(", "),
+            };
+            Alert::new(
+                &format!("smart:{}", drive.device),
+                drive.state,
+                format!("{} {}: {}", drive.model, drive.serial, detail)
+                    .trim()
+                    .to_string(),
+            )
+        })
+        .collect();
+    if let Some(error) = &report.error {
+        alerts.push(Alert::new("smart", CheckState::Unknown, error.clone()));
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smartctl_fixtures() {
+        let scan = parse_scan(include_str!("../../tests/fixtures/smart/scan.json")).unwrap();
+        assert_eq!(
+            scan[1],
+            ("/dev/nvme0".to_string(), Some("nvme".to_string()))
+        );
+
+        let sda = parse_smartctl(
+            "/dev/sda",
+            include_str!("../../tests/fixtures/smart/ata_failing.json"),
+        );
+        assert_eq!(sda.state, CheckState::Critical);
+        assert_eq!(sda.passed, Some(false));
+        assert_eq!(sda.temperature, Some(38));
+        assert_eq!(sda.power_on_hours, Some(48213));
+        assert_eq!(sda.reallocated_sectors, Some(344));
+        assert_eq!(sda.pending_sectors, Some(8));
+        assert_eq!(sda.offline_uncorrectable, Some(2));
+        assert_eq!(sda.problems.len(), 4);
+
+        let nvme = parse_smartctl(
+            "/dev/nvme0",
+            include_str!("../../tests/fixtures/smart/nvme_healthy.json"),
+        );
+        assert_eq!(nvme.state, CheckState::Ok);
+        assert_eq!(nvme.protocol, "NVMe");
+        assert_eq!(nvme.percentage_used, Some(3));
+        assert_eq!(nvme.media_errors, Some(0));
+        assert_eq!(nvme.reallocated_sectors, None);
+
+        let missing = parse_smartctl(
+            "/dev/sdz",
+            include_str!("../../tests/fixtures/smart/open_failed.json"),
+        );
+        assert_eq!(missing.state, CheckState::Unknown);
+        assert!(missing.error.unwrap().contains("No such device"));
+
+        let report = SmartReport {
+            drives: vec![sda, nvme],
+            ..Default::default()
+        };
+        assert_eq!(smart_alerts(&report).len(), 1);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::{ListeningSocket, SocketInventory, UnixSocket},
+};
+use crate::services::{cache::ReportCache, users::user_name};
+use log::{debug, error, info, warn};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::unix::fs::MetadataExt,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Dernier inventaire des sockets, rafraîchi en tâche de fond par [`watch_sockets`] ;
+/// `None` tant qu'aucun relevé n'a abouti.
+pub type SocketCache = ReportCache<Option<SocketInventory>>;
+
+/// `st` de `/proc/net/tcp` (`include/net/tcp_states.h`).
+const TCP_STATES: [&str; 12] = [
+    "UNKNOWN",
+    "ESTABLISHED",
+    "SYN_SENT",
+    "SYN_RECV",
+    "FIN_WAIT1",
+    "FIN_WAIT2",
+    "TIME_WAIT",
+    "CLOSE",
+    "CLOSE_WAIT",
+    "LAST_ACK",
+    "LISTEN",
+    "CLOSING",
+];
+const TCP_LISTEN: u8 = 0x0A;
+/// Une socket UDP liée mais non connectée est dans l'état `CLOSE`.
+const UDP_UNCONNECTED: u8 = 0x07;
+
+/// `__SO_ACCEPTCON` dans la colonne `Flags` de `/proc/net/unix`.
+const UNIX_ACCEPTING: u32 = 0x10000;
+
+/// Ligne de `/proc/net/{tcp,tcp6,udp,udp6}`.
+#[derive(Debug, Clone, PartialEq)]
+struct InetSocket {
+    local: (IpAddr, u16),
+    remote: (IpAddr, u16),
+    state: u8,
+    uid: u32,
+    inode: u64,
+}
+
+/// Processus propriétaire d'une socket, d'après `/proc/<pid>/fd`.
+#[derive(Debug, Clone)]
+struct SocketOwner {
+    pid: u32,
+    process: String,
+    uid: u32,
+}
+
+/// Relève les sockets toutes les `interval` ; un intervalle nul désactive l'inventaire.
+///
+/// Le parcours de `/proc/<pid>/fd` coûte un appel système par descripteur ouvert :
+/// il tourne hors de l'exécuteur, jamais pendant une requête.
+pub async fn watch_sockets(cache: Arc<SocketCache>, interval: Duration) {
+    if interval.is_zero() {
+        info!("Socket inventory disabled");
+        return;
+    }
+    loop {
+        match tokio::task::spawn_blocking(socket_inventory).await {
+            Ok(Ok(inventory)) => {
+                debug!("Sockets listed: {} listener(s)", inventory.listeners.len());
+                cache.set(Some(inventory));
+            }
+            Ok(Err(_)) => cache.set(None),
+            Err(e) => warn!("Socket inventory aborted: {}", e),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Sockets en écoute, avec leur processus, et connexions TCP par état.
+///
+/// Sans les droits root, le processus des sockets appartenant à d'autres
+/// utilisateurs reste inconnu.
+pub fn socket_inventory() -> Result<SocketInventory, SystemError> {
+    let read = |file: &str| fs::read_to_string(format!("/proc/net/{}", file)).unwrap_or_default();
+    let tcp = fs::read_to_string("/proc/net/tcp").map_err(|_| {
+        error!("{}", SystemError::SocketsUnavailable.message());
+        SystemError::SocketsUnavailable
+    })?;
+    let owners = socket_owners();
+
+    let mut inventory = SocketInventory::default();
+    let mut tcp_states: BTreeMap<&str, usize> = BTreeMap::new();
+    for (protocol, content) in [
+        ("tcp", tcp),
+        ("tcp6", read("tcp6")),
+        ("udp", read("udp")),
+        ("udp6", read("udp6")),
+    ] {
+        for socket in parse_inet(&content) {
+            let listening = if protocol.starts_with("tcp") {
+                *tcp_states
+                    .entry(TCP_STATES.get(socket.state as usize).unwrap_or(&"UNKNOWN"))
+                    .or_default() += 1;
+                socket.state == TCP_LISTEN
+            } else {
+                socket.state == UDP_UNCONNECTED && socket.remote.1 == 0
+            };
+            if listening {
+                inventory.listeners.push(listening_socket(
+                    protocol,
+                    &socket,
+                    owners.get(&socket.inode),
+                ));
+            }
+        }
+    }
+    inventory.tcp_states = tcp_states
+        .into_iter()
+        .map(|(state, count)| (state.to_string(), count))
+        .collect();
+    inventory
+        .listeners
+        .sort_by(|a, b| (a.port, &a.protocol, &a.address).cmp(&(b.port, &b.protocol, &b.address)));
+
+    inventory.unix_listeners = parse_unix(&read("unix"))
+        .into_iter()
+        .map(|(path, kind, inode)| {
+            let owner = owners.get(&inode);
+            UnixSocket {
+                path,
+                kind: kind.to_string(),
+                inode,
+                pid: owner.map(|owner| owner.pid),
+                process: owner.map(|owner| owner.process.clone()),
+                user: owner.map(|owner| user_name(owner.uid)),
+            }
+        })
+        .collect();
+    inventory.unix_listeners.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(inventory)
+}
+
+fn listening_socket(
+    protocol: &str,
+    socket: &InetSocket,
+    owner: Option<&SocketOwner>,
+) -> ListeningSocket {
+    ListeningSocket {
+        protocol: protocol.to_string(),
+        address: socket.local.0.to_string(),
+        port: socket.local.1,
+        loopback: socket.local.0.is_loopback(),
+        inode: socket.inode,
+        user: user_name(socket.uid),
+        pid: owner.map(|owner| owner.pid),
+        process: owner.map(|owner| owner.process.clone()),
+        unexpected: false,
+    }
+}
+
+/// `sl local_address rem_address st tx:rx tr:when retrnsmt uid timeout inode ...`
+fn parse_inet(content: &str) -> Vec<InetSocket> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(InetSocket {
+                local: parse_address(fields.get(1)?)?,
+                remote: parse_address(fields.get(2)?)?,
+                state: u8::from_str_radix(fields.get(3)?, 16).ok()?,
+                uid: fields.get(7)?.parse().ok()?,
+                inode: fields.get(9)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// `0100007F:0035` ou `00000000000000000000000001000000:0277` : adresse écrite
+/// mot de 32 bits par mot de 32 bits dans l'ordre de la machine, port en hexadécimal.
+fn parse_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let words: Vec<[u8; 4]> = (0..address.len() / 8)
+        .map(|i| u32::from_str_radix(&address[i * 8..i * 8 + 8], 16).map(u32::to_ne_bytes))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let ip = match words[..] {
+        [word] => IpAddr::V4(Ipv4Addr::from(word)),
+        [_, _, _, _] => {
+            let bytes: [u8; 16] = words.concat().try_into().ok()?;
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        }
+        _ => return None,
+    };
+    Some((ip, u16::from_str_radix(port, 16).ok()?))
+}
+
+/// Sockets Unix en écoute : `Num RefCount Protocol Flags Type St Inode Path`.
+fn parse_unix(content: &str) -> Vec<(String, &'static str, u64)> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+            let kind = match *fields.get(4)? {
+                "0001" => "stream",
+                "0005" => "seqpacket",
+                _ => return None,
+            };
+            if flags & UNIX_ACCEPTING == 0 {
+                return None;
+            }
+            Some((
+                fields.get(7).unwrap_or(&"(unnamed)").to_string(),
+                kind,
+                fields.get(6)?.parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Inode de socket → processus, en parcourant `/proc/<pid>/fd` (le plus petit PID l'emporte).
+fn socket_owners() -> HashMap<u64, SocketOwner> {
+    let mut pids: Vec<u32> = fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    pids.sort_unstable();
+
+    let mut owners = HashMap::new();
+    for pid in pids {
+        let Ok(fds) = fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue;
+        };
+        let Ok(metadata) = fs::metadata(format!("/proc/{}", pid)) else {
+            continue;
+        };
+        let process = fs::read_to_string(format!("/proc/{}/comm", pid))
+            .map(|comm| comm.trim_end().to_string())
+            .unwrap_or_default();
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|target| target.strip_suffix(']'))
+                .and_then(|inode| inode.parse().ok());
+            if let Some(inode) = inode {
+                owners.entry(inode).or_insert_with(|| SocketOwner {
+                    pid,
+                    process: process.clone(),
+                    uid: metadata.uid(),
+                });
+            }
+        }
+    }
+    owners
+}
+
+/// Compare les sockets en écoute à la liste attendue (`tcp/22`, `udp/53`,
+/// `tcp/127.0.0.1:5432`, `tcp/[::1]:5432`) : un protocole couvre IPv4 et IPv6,
+/// une entrée sans adresse couvre toutes les adresses. Sans liste, rien n'est signalé ;
+/// une entrée illisible est ignorée avec un avertissement.
+pub fn check_expected_ports(
+    inventory: &mut SocketInventory,
+    expected_ports: &[String],
+    ignore_loopback: bool,
+) {
+    if expected_ports.is_empty() {
+        return;
+    }
+    let expected: Vec<(&String, ExpectedPort)> = expected_ports
+        .iter()
+        .filter_map(|entry| match parse_expected_port(entry) {
+            Some(parsed) => Some((entry, parsed)),
+            None => {
+                warn!("Ignoring invalid expected_ports entry '{}'", entry);
+                None
+            }
+        })
+        .collect();
+    let matches = |socket: &ListeningSocket, (protocol, address, port): &ExpectedPort| {
+        socket.protocol.trim_end_matches('6') == *protocol
+            && socket.port == *port
+            && address.is_none_or(|address| address == socket.address)
+    };
+
+    for socket in &mut inventory.listeners {
+        let exempt = ignore_loopback && socket.loopback;
+        socket.unexpected = !exempt && !expected.iter().any(|(_, entry)| matches(socket, entry));
+    }
+    inventory.missing = expected
+        .iter()
+        .filter(|(_, entry)| {
+            !inventory
+                .listeners
+                .iter()
+                .any(|socket| matches(socket, entry))
+        })
+        .map(|(name, _)| name.to_string())
+        .collect();
+}
+
+/// Protocole, adresse éventuelle et port d'une entrée de `expected_ports`.
+type ExpectedPort<'a> = (&'a str, Option<&'a str>, u16);
+
+fn parse_expected_port(entry: &str) -> Option<ExpectedPort<'_>> {
+    let (protocol, rest) = entry.split_once('/')?;
+    match rest.rsplit_once(':') {
+        Some((address, port)) => Some((
+            protocol,
+            Some(address.trim_start_matches('[').trim_end_matches(']')),
+            port.parse().ok()?,
+        )),
+        None => Some((protocol, None, rest.parse().ok()?)),
+    }
+}
+
+/// Alerte pour chaque socket en écoute hors de la liste attendue et chaque port attendu absent.
+pub fn socket_alerts(inventory: &SocketInventory) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = inventory
+        .listeners
+        .iter()
+        .filter(|socket| socket.unexpected)
+        .map(|socket| {
+            Alert::new(
+                &format!("socket:{}/{}", socket.protocol, socket.port),
+                CheckState::Warning,
+                format!(
+                    "unexpected listener on {} ({})",
+                    socket.address,
+                    socket.process.as_deref().unwrap_or("unknown process")
+                ),
+            )
+        })
+        .collect();
+    alerts.extend(inventory.missing.iter().map(|port| {
+        Alert::new(
+            &format!("socket:{}", port),
+            CheckState::Warning,
+            "expected port is not listening".to_string(),
+        )
+    }));
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sockets_and_expected_ports() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 21334 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1538 00000000:0000 0A 00000000:00000000 00:00000000 00000000   114        0 24581 1 0000000000000000 100 0 0 10 0
+   2: 0F02000A:0016 0202000A:D4C2 01 00000000:00000000 02:000A7D8E 00000000     0        0 91210 4 0000000000000000 20 4 29 10 -1
+";
+        let sockets = parse_inet(tcp);
+        assert_eq!(sockets.len(), 3);
+        assert_eq!(sockets[1].local, ("127.0.0.1".parse().unwrap(), 5432));
+        assert_eq!(sockets[1].uid, 114);
+        assert_eq!(sockets[2].remote, ("10.0.2.2".parse().unwrap(), 54466));
+        assert_eq!(sockets[2].state, 1);
+        assert_eq!(
+            parse_address("00000000000000000000000001000000:0277"),
+            Some(("::1".parse().unwrap(), 631))
+        );
+
+        let unix = "Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 18230 /run/systemd/private
+0000000000000000: 00000003 00000000 00000000 0001 03 28744 /run/systemd/journal/stdout
+0000000000000000: 00000002 00000000 00010000 0005 01 16442 /run/udev/control
+";
+        let listening: Vec<&str> = parse_unix(unix).iter().map(|(_, kind, _)| *kind).collect();
+        assert_eq!(listening, vec!["stream", "seqpacket"]);
+
+        let mut inventory = SocketInventory {
+            listeners: sockets
+                .iter()
+                .filter(|socket| socket.state == TCP_LISTEN)
+                .map(|socket| listening_socket("tcp", socket, None))
+                .collect(),
+            ..Default::default()
+        };
+        inventory.listeners.push(ListeningSocket {
+            protocol: "udp6".to_string(),
+            address: "::".to_string(),
+            port: 5353,
+            ..inventory.listeners[0].clone()
+        });
+        check_expected_ports(
+            &mut inventory,
+            &[
+                "tcp/ssh".to_string(),
+                "tcp/22".to_string(),
+                "tcp/0.0.0.0:443".to_string(),
+            ],
+            true,
+        );
+        let unexpected: Vec<u16> = inventory
+            .listeners
+            .iter()
+            .filter(|socket| socket.unexpected)
+            .map(|socket| socket.port)
+            .collect();
+        assert_eq!(unexpected, vec![5353]);
+        assert_eq!(inventory.missing, vec!["tcp/0.0.0.0:443"]);
+        assert_eq!(socket_alerts(&inventory).len(), 2);
+    }
+}
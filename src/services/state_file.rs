@@ -0,0 +1,36 @@
+use std::{fs, io, path::Path};
+
+/// Enregistre un fichier d'état (historique, référence...) sous `path`.
+///
+/// Le répertoire parent est créé au besoin (`/var/lib/monitor_server` n'existe pas sur
+/// une installation neuve) et l'écriture passe par un fichier temporaire renommé :
+/// un arrêt brutal ne laisse jamais de fichier tronqué.
+pub fn write_state_file(path: &str, contents: &str) -> io::Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    let temporary = format!("{}.tmp", path);
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_state_file_creates_parent() {
+        let dir = std::env::temp_dir().join(format!("monitor_server_state_{}", std::process::id()));
+        let path = dir.join("nested/history.json");
+        let path = path.to_str().unwrap();
+
+        write_state_file(path, "[1]").unwrap();
+        write_state_file(path, "[2]").unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "[2]");
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
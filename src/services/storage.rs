@@ -0,0 +1,462 @@
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::{BtrfsFilesystem, MdArray, StorageReport, ThinPool, ZfsPool},
+};
+use crate::services::{cache::ReportCache, exec::run_tool, hardware::read_mounts};
+use log::{debug, info};
+use regex::Regex;
+use std::{
+    collections::HashSet,
+    fs,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+/// Délai maximal de chaque outil (`zpool`, `lvs`, `btrfs`).
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Remplissage d'un thin pool LVM (données ou métadonnées) déclenchant une alerte, en %.
+const THIN_POOL_WARNING: f64 = 80.0;
+const THIN_POOL_CRITICAL: f64 = 90.0;
+
+/// `[2/1] [U_]` : membres attendus, actifs et leur état.
+static MDSTAT_COUNTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[(\d+)/(\d+)\]\s+\[([U_]+)\]").expect("valid regex"));
+/// `recovery = 12.6%` ou `resync=DELAYED`.
+static MDSTAT_PROGRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(resync|recovery|reshape|check|repair)\s*=\s*(?:([\d.]+)%|(\w+))")
+        .expect("valid regex")
+});
+static MDSTAT_FINISH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"finish=(\S+)").expect("valid regex"));
+
+/// Dernier relevé du stockage, rafraîchi en tâche de fond par [`watch_storage`].
+pub type StorageCache = ReportCache<StorageReport>;
+
+/// Relève le stockage toutes les `interval` ; un intervalle nul désactive la collecte.
+///
+/// `zpool`, `zfs`, `lvs` et `btrfs` sont lents (jusqu'à [`COMMAND_TIMEOUT`] chacun) :
+/// ils ne sont jamais lancés pendant une requête.
+pub async fn watch_storage(cache: Arc<StorageCache>, interval: Duration) {
+    if interval.is_zero() {
+        info!("Storage collection disabled");
+        return;
+    }
+    loop {
+        let report = check_storage().await;
+        debug!(
+            "Storage: {} md arrays, {} ZFS pools, {} thin pools, {} btrfs filesystems",
+            report.md_arrays.len(),
+            report.zfs_pools.len(),
+            report.thin_pools.len(),
+            report.btrfs.len()
+        );
+        cache.set(report);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// État des grappes mdraid, pools ZFS, thin pools LVM et systèmes de fichiers Btrfs.
+///
+/// Les outils absents sont ignorés : seules les technologies présentes sur l'hôte
+/// apparaissent dans le rapport.
+async fn check_storage() -> StorageReport {
+    let mut report = StorageReport {
+        md_arrays: fs::read_to_string("/proc/mdstat")
+            .map(|mdstat| parse_mdstat(&mdstat))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let (zpool, zfs, lvs) = tokio::join!(
+        run_tool("zpool", &["status"], COMMAND_TIMEOUT),
+        run_tool(
+            "zfs",
+            &["list", "-Hp", "-d", "0", "-o", "name,used,avail"],
+            COMMAND_TIMEOUT,
+        ),
+        run_tool(
+            "lvs",
+            &[
+                "--noheadings",
+                "--nosuffix",
+                "--units",
+                "b",
+                "--separator",
+                "|",
+                "-o",
+                "vg_name,lv_name,lv_attr,lv_size,data_percent,metadata_percent",
+            ],
+            COMMAND_TIMEOUT,
+        ),
+    );
+
+    match zpool {
+        Some(Ok(status)) => {
+            report.zfs_pools = parse_zpool_status(&status);
+            if let Some(Ok(list)) = &zfs {
+                apply_zfs_usage(&mut report.zfs_pools, list);
+            }
+        }
+        Some(Err(e)) => report.errors.push(e),
+        None => {}
+    }
+    match lvs {
+        Some(Ok(lvs)) => report.thin_pools = parse_lvs(&lvs),
+        Some(Err(e)) => report.errors.push(e),
+        None => {}
+    }
+
+    for (device, mount_point) in btrfs_mounts() {
+        match run_tool("btrfs", &["device", "stats", &mount_point], COMMAND_TIMEOUT).await {
+            Some(Ok(stats)) => report
+                .btrfs
+                .push(parse_btrfs_stats(&device, &mount_point, &stats)),
+            Some(Err(e)) => report.errors.push(e),
+            None => break,
+        }
+    }
+    report
+}
+
+/// Décode `/proc/mdstat` : une grappe par bloc `mdX : état niveau membres...`.
+fn parse_mdstat(mdstat: &str) -> Vec<MdArray> {
+    let mut arrays: Vec<MdArray> = Vec::new();
+    for line in mdstat.lines() {
+        if let Some((name, rest)) = line
+            .split_once(" : ")
+            .filter(|(name, _)| name.starts_with("md"))
+        {
+            let mut array = MdArray {
+                name: name.trim().to_string(),
+                ..Default::default()
+            };
+            for token in rest.split_whitespace() {
+                if let Some((device, flags)) = token.split_once('[') {
+                    array.devices.push(device.to_string());
+                    if flags.contains("(F)") {
+                        array.failed.push(device.to_string());
+                    } else if flags.contains("(S)") {
+                        array.spares.push(device.to_string());
+                    }
+                } else if array.state.is_empty() {
+                    array.state = token.to_string();
+                } else if !token.starts_with('(') && array.level.is_empty() {
+                    array.level = token.to_string();
+                }
+            }
+            arrays.push(array);
+            continue;
+        }
+
+        let Some(array) = arrays.last_mut() else {
+            continue;
+        };
+        if let Some(captures) = MDSTAT_COUNTS.captures(line) {
+            array.expected = captures[1].parse().unwrap_or(0);
+            array.active = captures[2].parse().unwrap_or(0);
+            array.members = captures[3].to_string();
+        }
+        if let Some(captures) = MDSTAT_PROGRESS.captures(line) {
+            array.sync_action = Some(captures[1].to_string());
+            array.sync_progress = captures
+                .get(2)
+                .and_then(|value| value.as_str().parse().ok());
+            array.finish = MDSTAT_FINISH
+                .captures(line)
+                .map(|captures| captures[1].to_string());
+        }
+    }
+
+    for array in &mut arrays {
+        array.health =
+            if array.state != "active" || array.active < array.expected || !array.failed.is_empty()
+            {
+                CheckState::Critical
+            } else if array
+                .sync_action
+                .as_deref()
+                .is_some_and(|action| action != "check")
+            {
+                CheckState::Warning
+            } else {
+                CheckState::Ok
+            };
+    }
+    arrays
+}
+
+/// Décode `zpool status` : état, dernière vérification, erreurs et vdevs hors ligne.
+fn parse_zpool_status(status: &str) -> Vec<ZfsPool> {
+    let mut pools: Vec<ZfsPool> = Vec::new();
+    let mut in_config = false;
+    for line in status.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix("pool:") {
+            pools.push(ZfsPool {
+                name: name.trim().to_string(),
+                ..Default::default()
+            });
+            in_config = false;
+            continue;
+        }
+        let Some(pool) = pools.last_mut() else {
+            continue;
+        };
+        if let Some(state) = trimmed.strip_prefix("state:") {
+            pool.state = state.trim().to_string();
+        } else if let Some(scan) = trimmed.strip_prefix("scan:") {
+            pool.scan = Some(scan.trim().to_string());
+        } else if let Some(errors) = trimmed.strip_prefix("errors:") {
+            pool.errors = errors.trim().to_string();
+            in_config = false;
+        } else if trimmed.starts_with("config:") {
+            in_config = true;
+        } else if in_config {
+            let fields: Vec<&str> = trimmed.split_whitespace().collect();
+            if let [vdev, state, ..] = fields[..] {
+                if vdev != "NAME"
+                    && vdev != pool.name
+                    && matches!(
+                        state,
+                        "DEGRADED" | "FAULTED" | "OFFLINE" | "UNAVAIL" | "REMOVED"
+                    )
+                {
+                    pool.problems.push(format!("{} {}", vdev, state));
+                }
+            }
+        }
+    }
+
+    for pool in &mut pools {
+        pool.health = match pool.state.as_str() {
+            "ONLINE" if pool.errors.is_empty() || pool.errors == "No known data errors" => {
+                CheckState::Ok
+            }
+            "ONLINE" | "DEGRADED" => CheckState::Warning,
+            _ => CheckState::Critical,
+        };
+    }
+    pools
+}
+
+/// Complète les pools avec `zfs list -Hp -d 0 -o name,used,avail` (octets, séparés par des tabulations).
+fn apply_zfs_usage(pools: &mut [ZfsPool], list: &str) {
+    for line in list.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if let [name, used, available] = fields[..] {
+            if let Some(pool) = pools.iter_mut().find(|pool| pool.name == name) {
+                pool.used = used.parse().ok();
+                pool.available = available.parse().ok();
+            }
+        }
+    }
+}
+
+/// Thin pools parmi les volumes listés par `lvs` (attribut commençant par `t`).
+fn parse_lvs(lvs: &str) -> Vec<ThinPool> {
+    lvs.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim().split('|').collect();
+            let [vg, lv, attr, size, data, metadata] = fields[..] else {
+                return None;
+            };
+            if !attr.starts_with('t') {
+                return None;
+            }
+            // Certaines locales écrivent les pourcentages avec une virgule
+            let percent = |value: &str| value.trim().replace(',', ".").parse::<f64>().ok();
+            let data_percent = percent(data);
+            let metadata_percent = percent(metadata);
+            let fullest = data_percent
+                .unwrap_or(0.0)
+                .max(metadata_percent.unwrap_or(0.0));
+            Some(ThinPool {
+                vg: vg.to_string(),
+                lv: lv.to_string(),
+                size: size.trim().parse().unwrap_or(0),
+                data_percent,
+                metadata_percent,
+                health: if fullest >= THIN_POOL_CRITICAL {
+                    CheckState::Critical
+                } else if fullest >= THIN_POOL_WARNING {
+                    CheckState::Warning
+                } else {
+                    CheckState::Ok
+                },
+            })
+        })
+        .collect()
+}
+
+/// Systèmes de fichiers Btrfs montés, un point de montage par périphérique
+/// (les sous-volumes d'un même système de fichiers partagent leurs compteurs).
+fn btrfs_mounts() -> Vec<(String, String)> {
+    let mut seen = HashSet::new();
+    read_mounts()
+        .into_iter()
+        .filter(|mount| mount.fstype == "btrfs" && seen.insert(mount.device.clone()))
+        .map(|mount| (mount.device, mount.mount_point))
+        .collect()
+}
+
+/// Décode `btrfs device stats` : `[/dev/sda1].write_io_errs    0`, une ligne par compteur.
+fn parse_btrfs_stats(device: &str, mount_point: &str, stats: &str) -> BtrfsFilesystem {
+    let mut filesystem = BtrfsFilesystem {
+        device: device.to_string(),
+        mount_point: mount_point.to_string(),
+        ..Default::default()
+    };
+    let mut corrupted = false;
+    for line in stats.lines() {
+        let Some((counter, value)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let value: u64 = value.trim().parse().unwrap_or(0);
+        if value > 0 {
+            corrupted |= counter.ends_with("corruption_errs");
+            filesystem.problems.push(format!("{} = {}", counter, value));
+        }
+    }
+    filesystem.health = if corrupted {
+        CheckState::Critical
+    } else if !filesystem.problems.is_empty() {
+        CheckState::Warning
+    } else {
+        CheckState::Ok
+    };
+    filesystem
+}
+
+/// Alertes pour chaque grappe, pool ou système de fichiers qui n'est pas en bon état.
+pub fn storage_alerts(report: &StorageReport) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for array in report
+        .md_arrays
+        .iter()
+        .filter(|array| array.health != CheckState::Ok)
+    {
+        let mut message = format!(
+            "{} {} [{}/{}] [{}]",
+            array.state, array.level, array.expected, array.active, array.members
+        );
+        if !array.failed.is_empty() {
+            message.push_str(&format!(", failed: {}", array.failed.join(", ")));
+        }
+        if let Some(action) = &array.sync_action {
+            message.push_str(&format!(", {} in progress", action));
+        }
+        alerts.push(Alert::new(
+            &format!("mdraid:{}", array.name),
+            array.health,
+            message,
+        ));
+    }
+    for pool in report
+        .zfs_pools
+        .iter()
+        .filter(|pool| pool.health != CheckState::Ok)
+    {
+        let mut message = format!("pool {}", pool.state);
+        if !pool.problems.is_empty() {
+            message.push_str(&format!(" ({})", pool.problems.join(", ")));
+        }
+        message.push_str(&format!(", errors: {}", pool.errors));
+        alerts.push(Alert::new(
+            &format!("zfs:{}", pool.name),
+            pool.health,
+            message,
+        ));
+    }
+    for pool in report
+        .thin_pools
+        .iter()
+        .filter(|pool| pool.health != CheckState::Ok)
+    {
+        alerts.push(Alert::new(
+            &format!("lvm:{}/{}", pool.vg, pool.lv),
+            pool.health,
+            format!(
+                "thin pool data {:.1}%, metadata {:.1}%",
+                pool.data_percent.unwrap_or(0.0),
+                pool.metadata_percent.unwrap_or(0.0)
+            ),
+        ));
+    }
+    for filesystem in report.btrfs.iter().filter(|fs| fs.health != CheckState::Ok) {
+        alerts.push(Alert::new(
+            &format!("btrfs:{}", filesystem.mount_point),
+            filesystem.health,
+            filesystem.problems.join(", "),
+        ));
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_storage_tool_outputs() {
+        let mdstat = "Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid5 sdd1[3] sdc1[1] sdb1[0](F)
+      1953258496 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [_UU]
+      [=>...................]  recovery =  8.5% (83064448/976629248) finish=76.0min speed=195840K/sec
+      bitmap: 0/8 pages [0KB], 65536KB chunk
+
+md0 : active raid1 sdb2[1] sda2[0]
+      976630464 blocks super 1.2 [2/2] [UU]
+
+unused devices: <none>
+";
+        let arrays = parse_mdstat(mdstat);
+        assert_eq!(arrays.len(), 2);
+        assert_eq!(arrays[0].level, "raid5");
+        assert_eq!(arrays[0].failed, vec!["sdb1"]);
+        assert_eq!((arrays[0].expected, arrays[0].active), (3, 2));
+        assert_eq!(arrays[0].sync_action.as_deref(), Some("recovery"));
+        assert_eq!(arrays[0].sync_progress, Some(8.5));
+        assert_eq!(arrays[0].finish.as_deref(), Some("76.0min"));
+        assert_eq!(arrays[0].health, CheckState::Critical);
+        assert_eq!(arrays[1].members, "UU");
+        assert_eq!(arrays[1].health, CheckState::Ok);
+
+        let zpool = "  pool: tank
+ state: DEGRADED
+status: One or more devices could not be used because the label is missing or
+\tinvalid.
+  scan: resilvered 1.20G in 00:01:02 with 0 errors on Sun Oct 12 10:00:00 2025
+config:
+
+\tNAME        STATE     READ WRITE CKSUM
+\ttank        DEGRADED     0     0     0
+\t  mirror-0  DEGRADED     0     0     0
+\t    sda     ONLINE       0     0     0
+\t    sdb     UNAVAIL      0     0     0  cannot open
+
+errors: No known data errors
+";
+        let mut pools = parse_zpool_status(zpool);
+        apply_zfs_usage(&mut pools, "tank\t1288490188\t2147483648\n");
+        assert_eq!(pools[0].state, "DEGRADED");
+        assert_eq!(pools[0].problems, vec!["mirror-0 DEGRADED", "sdb UNAVAIL"]);
+        assert_eq!(pools[0].health, CheckState::Warning);
+        assert_eq!(pools[0].available, Some(2147483648));
+
+        let thin = parse_lvs(
+            "  vg0|root|-wi-ao----|21474836480||\n  vg0|thin|twi-aotz--|107374182400|91,50|10.03\n",
+        );
+        assert_eq!(thin.len(), 1);
+        assert_eq!(thin[0].data_percent, Some(91.5));
+        assert_eq!(thin[0].health, CheckState::Critical);
+
+        let btrfs = parse_btrfs_stats(
+            "/dev/sda1",
+            "/srv",
+            "[/dev/sda1].write_io_errs    0\n[/dev/sda1].read_io_errs     3\n[/dev/sda1].corruption_errs  0\n",
+        );
+        assert_eq!(btrfs.problems, vec!["[/dev/sda1].read_io_errs = 3"]);
+        assert_eq!(btrfs.health, CheckState::Warning);
+    }
+}
@@ -0,0 +1,499 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    errors::SystemError,
+    templates::CertificateStatus,
+};
+use crate::services::{cache::ReportCache, service_checker::load_checks_file};
+use log::{debug, error, info, warn};
+use openssl::{
+    asn1::{Asn1Time, Asn1TimeRef},
+    ssl::{SslConnector, SslMethod, SslVerifyMode},
+    stack::Stack,
+    x509::{
+        store::X509StoreBuilder, X509NameRef, X509Ref, X509StoreContext, X509VerifyResult, X509,
+    },
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Dernier relevé des sondes TLS, rafraîchi en tâche de fond par [`watch_certificates`].
+pub type CertificateCache = ReportCache<Vec<CertificateStatus>>;
+
+/// Réglages des sondes TLS, lus au démarrage.
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    pub services_path: String,
+    pub interval: Duration,
+}
+
+impl TlsSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            services_path: config.services_path.clone(),
+            interval: Duration::from_secs(config.tls_interval_secs),
+        }
+    }
+}
+
+/// Sonde TLS déclarée dans `services.toml` (`[[tls]]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsCheck {
+    pub name: String,
+    /// Hôte distant à contacter ; ignoré si `pem_path` est renseigné.
+    pub host: Option<String>,
+    #[serde(default = "default_tls_port")]
+    pub port: u16,
+    /// Nom envoyé en SNI, par défaut `host`.
+    pub sni: Option<String>,
+    /// `smtp` ou `imap` pour négocier STARTTLS avant la poignée de main.
+    pub starttls: Option<String>,
+    /// Fichier PEM local (certificat suivi éventuellement de sa chaîne).
+    pub pem_path: Option<String>,
+    #[serde(default = "default_warning_days")]
+    pub warning_days: i64,
+    #[serde(default = "default_critical_days")]
+    pub critical_days: i64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Deserialize)]
+struct TlsChecksFile {
+    #[serde(default)]
+    tls: Vec<TlsCheck>,
+}
+
+fn default_tls_port() -> u16 {
+    443
+}
+
+fn default_warning_days() -> i64 {
+    30
+}
+
+fn default_critical_days() -> i64 {
+    7
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+pub fn load_tls_checks(path: &str) -> Vec<TlsCheck> {
+    load_checks_file::<TlsChecksFile>(path)
+        .map(|file| file.tls)
+        .unwrap_or_default()
+}
+
+/// Exécute les sondes toutes les `interval` ; un intervalle nul désactive les sondes.
+///
+/// Connexions et poignées de main sont bloquantes : elles tournent hors de l'exécuteur,
+/// et `services.toml` est relu à chaque passe.
+pub async fn watch_certificates(cache: Arc<CertificateCache>, settings: TlsSettings) {
+    if settings.interval.is_zero() {
+        info!("TLS probes disabled");
+        return;
+    }
+    loop {
+        let services_path = settings.services_path.clone();
+        match tokio::task::spawn_blocking(move || check_certificates(&services_path)).await {
+            Ok(certificates) => {
+                debug!("TLS certificates checked: {}", certificates.len());
+                cache.set(certificates);
+            }
+            Err(e) => warn!("TLS probes aborted: {}", e),
+        }
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Exécute toutes les sondes TLS déclarées dans `services.toml`.
+pub fn check_certificates(config_path: &str) -> Vec<CertificateStatus> {
+    load_tls_checks(config_path)
+        .iter()
+        .map(check_certificate)
+        .collect()
+}
+
+pub fn check_certificate(check: &TlsCheck) -> CertificateStatus {
+    let result = match (&check.pem_path, &check.host) {
+        (Some(path), _) => inspect_pem_file(path),
+        (None, Some(host)) => inspect_remote(check, host),
+        (None, None) => Err("neither host nor pem_path is configured".to_string()),
+    };
+
+    match result {
+        Ok(inspection) => {
+            let state = expiry_state(inspection.days_left, check);
+            let state = if !inspection.chain_valid && state == CheckState::Ok {
+                CheckState::Warning
+            } else {
+                state
+            };
+            CertificateStatus {
+                name: check.name.clone(),
+                target: target_label(check),
+                subject: inspection.subject,
+                sans: inspection.sans,
+                issuer: inspection.issuer,
+                chain_valid: inspection.chain_valid,
+                chain_error: inspection.chain_error,
+                not_after: inspection.not_after,
+                days_left: Some(inspection.days_left),
+                state,
+                error: None,
+            }
+        }
+        Err(e) => {
+            error!(
+                "{} ({}): {}",
+                SystemError::TlsProbeFailed.message(),
+                check.name,
+                e
+            );
+            CertificateStatus {
+                name: check.name.clone(),
+                target: target_label(check),
+                subject: String::new(),
+                sans: vec![],
+                issuer: String::new(),
+                chain_valid: false,
+                chain_error: None,
+                not_after: String::new(),
+                days_left: None,
+                state: CheckState::Unknown,
+                error: Some(e),
+            }
+        }
+    }
+}
+
+/// Alertes pour les certificats en erreur ou proches de l'expiration.
+pub fn certificate_alerts(certificates: &[CertificateStatus]) -> Vec<Alert> {
+    certificates
+        .iter()
+        .filter(|cert| cert.state != CheckState::Ok)
+        .map(|cert| {
+            let message = match (&cert.error, cert.days_left) {
+                (Some(e), _) => format!("{}: {}", cert.target, e),
+                (None, Some(days)) if days < 0 => {
+                    format!("{}: certificate expired {} days ago", cert.target, -days)
+                }
+                (None, Some(days)) if !cert.chain_valid => format!(
+                    "{}: invalid chain ({}), expires in {} days",
+                    cert.target,
+                    cert.chain_error.as_deref().unwrap_or("unknown error"),
+                    days
+                ),
+                (None, Some(days)) => {
+                    format!("{}: certificate expires in {} days", cert.target, days)
+                }
+                (None, None) => cert.target.clone(),
+            };
+            Alert::new(&format!("tls:{}", cert.name), cert.state, message)
+        })
+        .collect()
+}
+
+fn expiry_state(days_left: i64, check: &TlsCheck) -> CheckState {
+    if days_left <= check.critical_days {
+        CheckState::Critical
+    } else if days_left <= check.warning_days {
+        CheckState::Warning
+    } else {
+        CheckState::Ok
+    }
+}
+
+fn target_label(check: &TlsCheck) -> String {
+    match (&check.pem_path, &check.host) {
+        (Some(path), _) => path.clone(),
+        (None, Some(host)) => format!("{}:{}", host, check.port),
+        (None, None) => check.name.clone(),
+    }
+}
+
+struct CertificateInspection {
+    subject: String,
+    sans: Vec<String>,
+    issuer: String,
+    chain_valid: bool,
+    chain_error: Option<String>,
+    not_after: String,
+    days_left: i64,
+}
+
+fn inspect_remote(check: &TlsCheck, host: &str) -> Result<CertificateInspection, String> {
+    let timeout = Duration::from_secs(check.timeout_secs);
+    let address = (host, check.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("failed to resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("no address found for {}", host))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, timeout)
+        .map_err(|e| format!("failed to connect to {}: {}", address, e))?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| e.to_string())?;
+
+    if let Some(protocol) = &check.starttls {
+        starttls(&mut stream, protocol)?;
+    }
+
+    // La vérification est désactivée pour récupérer le certificat même invalide ;
+    // le résultat de la validation est lu ensuite via `verify_result`.
+    let mut builder = SslConnector::builder(SslMethod::tls_client()).map_err(|e| e.to_string())?;
+    builder.set_verify(SslVerifyMode::NONE);
+    let connector = builder.build();
+
+    let server_name = check.sni.as_deref().unwrap_or(host);
+    let tls_stream = connector
+        .configure()
+        .map_err(|e| e.to_string())?
+        .connect(server_name, stream)
+        .map_err(|e| format!("TLS handshake failed: {}", e))?;
+
+    let ssl = tls_stream.ssl();
+    let certificate = ssl
+        .peer_certificate()
+        .ok_or_else(|| "no certificate presented by peer".to_string())?;
+    let verify_result = ssl.verify_result();
+    let chain_error =
+        (verify_result != X509VerifyResult::OK).then(|| verify_result.error_string().to_string());
+
+    inspect_certificate(&certificate, chain_error)
+}
+
+fn inspect_pem_file(path: &str) -> Result<CertificateInspection, String> {
+    let pem = fs::read(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    let mut certificates = X509::stack_from_pem(&pem)
+        .map_err(|e| format!("failed to parse {}: {}", path, e))?
+        .into_iter();
+    let leaf = certificates
+        .next()
+        .ok_or_else(|| format!("no certificate found in {}", path))?;
+
+    let mut chain = Stack::new().map_err(|e| e.to_string())?;
+    for intermediate in certificates {
+        chain.push(intermediate).map_err(|e| e.to_string())?;
+    }
+
+    let mut store = X509StoreBuilder::new().map_err(|e| e.to_string())?;
+    store.set_default_paths().map_err(|e| e.to_string())?;
+    let store = store.build();
+
+    let mut context = X509StoreContext::new().map_err(|e| e.to_string())?;
+    let chain_error = context
+        .init(&store, &leaf, &chain, |ctx| {
+            if ctx.verify_cert()? {
+                Ok(None)
+            } else {
+                Ok(Some(ctx.error().error_string().to_string()))
+            }
+        })
+        .map_err(|e| e.to_string())?;
+
+    inspect_certificate(&leaf, chain_error)
+}
+
+fn inspect_certificate(
+    certificate: &X509Ref,
+    chain_error: Option<String>,
+) -> Result<CertificateInspection, String> {
+    let sans = certificate
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| {
+                    name.dnsname().map(str::to_string).or_else(|| {
+                        name.ipaddress().and_then(|ip| match ip.len() {
+                            4 => Some(
+                                std::net::Ipv4Addr::from(<[u8; 4]>::try_from(ip).ok()?).to_string(),
+                            ),
+                            16 => Some(
+                                std::net::Ipv6Addr::from(<[u8; 16]>::try_from(ip).ok()?)
+                                    .to_string(),
+                            ),
+                            _ => None,
+                        })
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(CertificateInspection {
+        subject: format_name(certificate.subject_name()),
+        sans,
+        issuer: format_name(certificate.issuer_name()),
+        chain_valid: chain_error.is_none(),
+        chain_error,
+        not_after: certificate.not_after().to_string(),
+        days_left: days_until(certificate.not_after())?,
+    })
+}
+
+fn days_until(time: &Asn1TimeRef) -> Result<i64, String> {
+    let now = Asn1Time::days_from_now(0).map_err(|e| e.to_string())?;
+    now.diff(time)
+        .map(|diff| diff.days as i64)
+        .map_err(|e| e.to_string())
+}
+
+fn format_name(name: &X509NameRef) -> String {
+    name.entries()
+        .filter_map(|entry| {
+            let key = entry.object().nid().short_name().ok()?;
+            let value = entry.data().as_utf8().ok()?;
+            Some(format!("{}={}", key, value))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Négocie STARTTLS en clair avant la poignée de main TLS.
+fn starttls(stream: &mut TcpStream, protocol: &str) -> Result<(), String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    match protocol.to_lowercase().as_str() {
+        "smtp" => {
+            expect_smtp_reply(&mut reader, "220")?;
+            send_line(stream, "EHLO monitor_server")?;
+            expect_smtp_reply(&mut reader, "250")?;
+            send_line(stream, "STARTTLS")?;
+            expect_smtp_reply(&mut reader, "220")
+        }
+        "imap" => {
+            let greeting = read_line(&mut reader)?;
+            if !greeting.starts_with("* OK") {
+                return Err(format!("unexpected IMAP greeting: {}", greeting.trim()));
+            }
+            send_line(stream, "a001 STARTTLS")?;
+            loop {
+                let line = read_line(&mut reader)?;
+                if line.starts_with("a001 ") {
+                    return if line.starts_with("a001 OK") {
+                        Ok(())
+                    } else {
+                        Err(format!("IMAP STARTTLS refused: {}", line.trim()))
+                    };
+                }
+            }
+        }
+        other => {
+            warn!("Unsupported STARTTLS protocol: {}", other);
+            Err(format!("unsupported STARTTLS protocol '{}'", other))
+        }
+    }
+}
+
+/// Lit une réponse SMTP (éventuellement multiligne) et vérifie son code.
+fn expect_smtp_reply(reader: &mut BufReader<TcpStream>, code: &str) -> Result<(), String> {
+    loop {
+        let line = read_line(reader)?;
+        if !line.starts_with(code) {
+            return Err(format!("unexpected SMTP reply: {}", line.trim()));
+        }
+        // "250-..." annonce une suite, "250 ..." termine la réponse.
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
+
+fn read_line(reader: &mut BufReader<TcpStream>) -> Result<String, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Err("connection closed during STARTTLS".to_string()),
+        Ok(_) => Ok(line),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn send_line(stream: &mut TcpStream, line: &str) -> Result<(), String> {
+    stream
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        bn::BigNum,
+        hash::MessageDigest,
+        pkey::PKey,
+        rsa::Rsa,
+        x509::{extension::SubjectAlternativeName, X509Builder, X509NameBuilder},
+    };
+
+    fn self_signed_pem(days: u32) -> Vec<u8> {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "monitor.test").unwrap();
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(days).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("monitor.test")
+            .ip("127.0.0.1")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build().to_pem().unwrap()
+    }
+
+    fn pem_check(path: &str) -> TlsCheck {
+        toml::from_str(&format!("name = \"test\"\npem_path = \"{}\"", path)).unwrap()
+    }
+
+    #[test]
+    fn test_pem_file_expiring_soon() {
+        let path = std::env::temp_dir().join(format!(
+            "monitor_server_tls_test_{}.pem",
+            std::process::id()
+        ));
+        fs::write(&path, self_signed_pem(5)).unwrap();
+
+        let status = check_certificate(&pem_check(path.to_str().unwrap()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(status.subject, "CN=monitor.test");
+        assert_eq!(status.sans, vec!["monitor.test", "127.0.0.1"]);
+        // 5 jours moins la durée du test : 4 si la seconde a changé entre-temps.
+        assert!(matches!(status.days_left, Some(4..=5)));
+        assert!(!status.chain_valid);
+        assert_eq!(status.state, CheckState::Critical);
+        assert_eq!(certificate_alerts(&[status]).len(), 1);
+    }
+
+    #[test]
+    fn test_missing_pem_file_is_unknown() {
+        let status = check_certificate(&pem_check("/nonexistent/cert.pem"));
+        assert_eq!(status.state, CheckState::Unknown);
+        assert!(status.error.is_some());
+    }
+}
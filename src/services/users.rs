@@ -0,0 +1,46 @@
+use std::ffi::{CStr, CString};
+
+const PASSWD_BUFFER_SIZE: usize = 16384;
+
+/// Résout l'uid/gid d'un utilisateur via NSS.
+pub fn lookup_user(name: &str) -> Option<(u32, u32)> {
+    let c_name = CString::new(name).ok()?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; PASSWD_BUFFER_SIZE];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+    let rc = unsafe {
+        libc::getpwnam_r(
+            c_name.as_ptr(),
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    (rc == 0 && !result.is_null()).then_some((passwd.pw_uid, passwd.pw_gid))
+}
+
+/// Nom de l'utilisateur correspondant à un uid, ou l'uid lui-même s'il est inconnu.
+pub fn user_name(uid: u32) -> String {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; PASSWD_BUFFER_SIZE];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+    let rc = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if rc == 0 && !result.is_null() {
+        unsafe { CStr::from_ptr(passwd.pw_name) }
+            .to_string_lossy()
+            .into_owned()
+    } else {
+        uid.to_string()
+    }
+}
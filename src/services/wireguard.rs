@@ -0,0 +1,214 @@
+use crate::config::Config;
+use crate::models::{
+    checks::{Alert, CheckState},
+    templates::{WireGuardInterface, WireGuardPeer, WireGuardReport},
+};
+use crate::services::{cache::ReportCache, docker::human_duration, exec::run_tool};
+use chrono::Utc;
+use log::{debug, info};
+use std::{sync::Arc, time::Duration};
+
+/// Délai maximal de `wg show`.
+const WG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Dernier relevé de `wg show` ; `None` si `wg` n'est pas installé.
+pub type WireGuardCache = ReportCache<Option<WireGuardReport>>;
+
+/// Âge du dernier handshake au-delà duquel un pair est signalé, en secondes.
+#[derive(Debug, Clone, Copy)]
+pub struct HandshakeThresholds {
+    pub warning: u64,
+    pub critical: u64,
+}
+
+impl HandshakeThresholds {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            warning: config.wireguard_handshake_warning_secs,
+            critical: config.wireguard_handshake_critical_secs,
+        }
+    }
+}
+
+/// Réglages du relevé WireGuard, lus au démarrage.
+#[derive(Debug, Clone, Copy)]
+pub struct WireGuardSettings {
+    pub thresholds: HandshakeThresholds,
+    pub interval: Duration,
+}
+
+impl WireGuardSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            thresholds: HandshakeThresholds::from_config(config),
+            interval: Duration::from_secs(config.wireguard_interval_secs),
+        }
+    }
+}
+
+/// Relève périodiquement les tunnels WireGuard dans `cache`.
+pub async fn watch_wireguard(cache: Arc<WireGuardCache>, settings: WireGuardSettings) {
+    if settings.interval.is_zero() {
+        info!("WireGuard monitoring disabled");
+        return;
+    }
+    loop {
+        let report = wireguard_status(settings.thresholds).await;
+        if let Some(report) = &report {
+            debug!("WireGuard interfaces listed: {}", report.interfaces.len());
+        }
+        cache.set(report);
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Interfaces WireGuard et leurs pairs d'après `wg show all dump` ;
+/// `None` si `wg` n'est pas installé.
+async fn wireguard_status(thresholds: HandshakeThresholds) -> Option<WireGuardReport> {
+    Some(
+        match run_tool("wg", &["show", "all", "dump"], WG_TIMEOUT).await? {
+            Ok(dump) => WireGuardReport {
+                error: None,
+                interfaces: parse_dump(&dump, Utc::now().timestamp(), thresholds),
+            },
+            Err(e) => WireGuardReport::failed(e),
+        },
+    )
+}
+
+/// Décode `wg show all dump`, une ligne par interface puis une par pair, champs séparés
+/// par des tabulations :
+/// - interface : `nom clé-privée clé-publique port fwmark` ;
+/// - pair : `nom clé-publique clé-partagée endpoint allowed-ips handshake rx tx keepalive`.
+///
+/// Les clés privées et partagées ne sont jamais conservées. Seuls les pairs avec un
+/// keepalive sont tenus de renouveler leur session : un pair inactif qui n'en a pas
+/// peut rester longtemps sans handshake. S'il a un endpoint configuré et n'a jamais
+/// fait de handshake, le tunnel n'a en revanche jamais fonctionné : il est en Warning.
+fn parse_dump(dump: &str, now: i64, thresholds: HandshakeThresholds) -> Vec<WireGuardInterface> {
+    let mut interfaces: Vec<WireGuardInterface> = Vec::new();
+    for line in dump.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields[..] {
+            [name, _, public_key, listen_port, _] => interfaces.push(WireGuardInterface {
+                name: name.to_string(),
+                public_key: public_key.to_string(),
+                listen_port: listen_port.parse().ok(),
+                peers: Vec::new(),
+            }),
+            [name, public_key, _, endpoint, allowed_ips, handshake, rx, tx, keepalive] => {
+                let Some(interface) = interfaces
+                    .iter_mut()
+                    .find(|interface| interface.name == name)
+                else {
+                    continue;
+                };
+                // 0 : aucun handshake depuis la création du pair
+                let latest_handshake = handshake.parse().ok().filter(|ts: &i64| *ts > 0);
+                let age = latest_handshake.map(|ts| (now - ts).max(0) as u64);
+                // « off » : pas de keepalive
+                let persistent_keepalive = keepalive.parse().ok();
+                interface.peers.push(WireGuardPeer {
+                    public_key: public_key.to_string(),
+                    endpoint: (endpoint != "(none)").then(|| endpoint.to_string()),
+                    allowed_ips: allowed_ips
+                        .split(',')
+                        .filter(|ip| !ip.is_empty() && *ip != "(none)")
+                        .map(str::to_string)
+                        .collect(),
+                    latest_handshake,
+                    handshake: match age {
+                        Some(age) => format!("{} ago", human_duration(age as i64)),
+                        None => "never".to_string(),
+                    },
+                    transfer_rx: rx.parse().unwrap_or(0),
+                    transfer_tx: tx.parse().unwrap_or(0),
+                    persistent_keepalive,
+                    health: match age {
+                        None if persistent_keepalive.is_none() && endpoint != "(none)" => {
+                            CheckState::Warning
+                        }
+                        _ if persistent_keepalive.is_none() => CheckState::Ok,
+                        Some(age) if age < thresholds.warning => CheckState::Ok,
+                        Some(age) if age < thresholds.critical => CheckState::Warning,
+                        _ => CheckState::Critical,
+                    },
+                });
+            }
+            _ => {}
+        }
+    }
+    interfaces
+}
+
+/// Alerte pour chaque pair dont le dernier handshake est trop ancien.
+pub fn wireguard_alerts(report: &WireGuardReport) -> Vec<Alert> {
+    report
+        .interfaces
+        .iter()
+        .flat_map(|interface| {
+            interface
+                .peers
+                .iter()
+                .filter(|peer| peer.health != CheckState::Ok)
+                .map(move |peer| {
+                    Alert::new(
+                        &format!("wireguard:{}", interface.name),
+                        peer.health,
+                        format!(
+                            "peer {} ({}): latest handshake {}",
+                            peer.public_key,
+                            peer.endpoint.as_deref().unwrap_or("no endpoint"),
+                            peer.handshake
+                        ),
+                    )
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wg_dump() {
+        let dump = "wg0\tcHJpdmF0ZQ==\tc2VydmVyLXB1YmxpYw==\t51820\toff
+wg0\tcGVlci1h\t(none)\t203.0.113.10:51820\t10.8.0.2/32,fd00::2/128\t1700000000\t123456\t654321\t25
+wg0\tcGVlci1i\tcHNr\t(none)\t10.8.0.3/32\t0\t0\t0\toff
+wg0\tcGVlci1j\t(none)\t198.51.100.7:41000\t10.8.0.4/32\t1699999000\t10\t20\toff
+wg0\tcGVlci1k\t(none)\t192.0.2.5:51820\t10.8.0.5/32\t1699999000\t10\t20\t25
+wg0\tcGVlci1s\t(none)\t192.0.2.6:51820\t10.8.0.6/32\t0\t0\t0\t25
+wg0\tcGVlci1t\t(none)\t192.0.2.7:51820\t10.8.0.7/32\t0\t0\t0\toff
+";
+        let thresholds = HandshakeThresholds {
+            warning: 300,
+            critical: 900,
+        };
+        let interfaces = parse_dump(dump, 1_700_000_060, thresholds);
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0].listen_port, Some(51820));
+        let peers = &interfaces[0].peers;
+        assert_eq!(peers.len(), 6);
+        assert_eq!(peers[0].allowed_ips, vec!["10.8.0.2/32", "fd00::2/128"]);
+        assert_eq!(peers[0].handshake, "About a minute ago");
+        assert_eq!(peers[0].persistent_keepalive, Some(25));
+        assert_eq!(peers[0].health, CheckState::Ok);
+        assert_eq!(peers[1].endpoint, None);
+        assert_eq!(peers[1].handshake, "never");
+        // Sans keepalive, un pair inactif n'est pas en défaut
+        assert_eq!(peers[1].health, CheckState::Ok);
+        assert_eq!(peers[2].health, CheckState::Ok);
+        assert_eq!(peers[3].health, CheckState::Critical);
+        assert_eq!(peers[4].handshake, "never");
+        assert_eq!(peers[4].health, CheckState::Critical);
+        // Sans keepalive mais avec un endpoint, aucun handshake : tunnel jamais établi
+        assert_eq!(peers[5].health, CheckState::Warning);
+
+        let report = WireGuardReport {
+            error: None,
+            interfaces,
+        };
+        assert_eq!(wireguard_alerts(&report).len(), 3);
+    }
+}